@@ -24,7 +24,7 @@ function search() {
         listItem = document.createElement("li");
         listItem.className = "search-result-item";
         listItem.innerHTML =
-            "<a href='" + result.doc.uri + "'>" + result.doc.title +
+            "<a href='" + pageUrl(result.doc.uri) + "'>" + result.doc.title +
             "<p class='search-result-item-preview'>" + searchPreview(result.doc.body) + "</p>" +
             "</a>";
 
@@ -121,13 +121,19 @@ function toggleColor() {
 
 function setColor() {
     var color = localStorage.getItem('doctave-color')
+    // Only present on pages that contain code blocks
+    var prismTheme = document.querySelector("link[rel='stylesheet'][href*='prism-']");
 
     if (color === 'dark') {
-        document.querySelector("link[rel='stylesheet'][href*='prism-']").href = BASE_PATH + "assets/prism-atom-dark.css?v=" + DOCTAVE_TIMESTAMP;
+        if (prismTheme) {
+            prismTheme.href = BASE_PATH + "assets/prism-atom-dark.css?v=" + DOCTAVE_TIMESTAMP;
+        }
         document.getElementsByTagName('html')[0].classList.remove('light');
         document.getElementsByTagName('html')[0].classList.add('dark');
     } else {
-        document.querySelector("link[rel='stylesheet'][href*='prism-']").href = BASE_PATH + "assets/prism-ghcolors.css?" + DOCTAVE_TIMESTAMP;
+        if (prismTheme) {
+            prismTheme.href = BASE_PATH + "assets/prism-ghcolors.css?" + DOCTAVE_TIMESTAMP;
+        }
         document.getElementsByTagName('html')[0].classList.remove('dark');
         document.getElementsByTagName('html')[0].classList.add('light');
     }
@@ -136,66 +142,251 @@ function setColor() {
 document.getElementById("light-dark-mode-switch").addEventListener("click", toggleColor);
 
 
-// Initialize mermaid.js based on color theme
-var color = localStorage.getItem('doctave-color')
-if (color === 'dark') {
-    console.log("DARK MODE");
-    mermaid.initialize({ 'theme': 'dark' });
-} else {
-    mermaid.initialize({ 'theme': 'default' });
+// Initialize mermaid.js based on color theme. Only loaded on pages with diagrams.
+if (typeof mermaid !== 'undefined') {
+    var color = localStorage.getItem('doctave-color')
+    if (color === 'dark') {
+        console.log("DARK MODE");
+        mermaid.initialize({ 'theme': 'dark' });
+    } else {
+        mermaid.initialize({ 'theme': 'default' });
+    }
 }
 
-// Setup Katex
-var mathElements = document.getElementsByClassName("math");
-
-const macros = {}
+// Setup Katex. Only loaded on pages with math notation.
+if (typeof katex !== 'undefined') {
+    var mathElements = document.getElementsByClassName("math");
+
+    const macros = {}
+
+    for (let element of mathElements) {
+        let latex = element.textContent;
+
+        try {
+            katex.render(latex, element, {
+                displayMode: true,
+                macros: macros,
+            });
+        } catch (e) {
+            if (e instanceof katex.ParseError) {
+                // KaTeX can't parse the expression
+                var error_message = e.message
+                    .replaceAll(/^KaTeX parse error: /g, "Error parsing math notation:\n")
+                    .replaceAll(/&/g, "&amp;")
+                    .replaceAll(/</g, "&lt;")
+                    .replaceAll(/>/g, "&gt;")
+                    .replaceAll("\n", "<br />");
+
+                element.innerHTML = "<p class='katex-error-msg'>" + error_message + "</p>" + latex.trim().replaceAll("\n", "<br />");
+                element.classList.add("katex-error");
+            } else {
+                throw e;  // other error
+            }
+        }
+    }
+}
 
-for (let element of mathElements) {
-    let latex = element.textContent;
+// Setup Prism. Only loaded on pages with code blocks.
+if (typeof Prism !== 'undefined') {
+    Prism.plugins.autoloader.languages_path = BASE_PATH + 'assets/prism-grammars/';
 
-    try {
-        katex.render(latex, element, {
-            displayMode: true,
-            macros: macros,
+    // Code highlighted when the site was built already has the language on
+    // its <pre>, and is left alone.
+    Prism.hooks.add('before-all-elements-highlight', function (env) {
+        env.elements = env.elements.filter(function (element) {
+            return !element.parentNode.matches('pre[class*="language-"]');
         });
-    } catch (e) {
-        if (e instanceof katex.ParseError) {
-            // KaTeX can't parse the expression
-            var error_message = e.message
-                .replaceAll(/^KaTeX parse error: /g, "Error parsing math notation:\n")
-                .replaceAll(/&/g, "&amp;")
-                .replaceAll(/</g, "&lt;")
-                .replaceAll(/>/g, "&gt;")
-                .replaceAll("\n", "<br />");
-
-            element.innerHTML = "<p class='katex-error-msg'>" + error_message + "</p>" + latex.trim().replaceAll("\n", "<br />");
-            element.classList.add("katex-error");
-        } else {
-            throw e;  // other error
+    });
+}
+
+
+// Setup tabs. Selecting a tab also selects the tabs with the same title in
+// the rest of the page, and is remembered for the next pages.
+function selectTab(tab) {
+    for (var other of tab.parentNode.children) {
+        var selected = other === tab;
+
+        other.setAttribute('aria-selected', selected);
+        other.tabIndex = selected ? 0 : -1;
+        document.getElementById(other.getAttribute('aria-controls')).hidden = !selected;
+    }
+}
+
+function selectTabs(key) {
+    for (var tab of document.querySelectorAll('.tab-list .tab')) {
+        if (tab.dataset.tab === key) {
+            selectTab(tab);
         }
     }
 }
 
-// Setup Prism
-Prism.plugins.autoloader.languages_path = BASE_PATH + 'assets/prism-grammars/';
+// Links to headings inside a tab select the tab, so the heading is visible
+function showTabForHash() {
+    var id = decodeURIComponent(window.location.hash.substring(1));
+    var target = id && document.getElementById(id);
+    var panel = target && target.closest('.tab-panel');
+
+    if (panel) {
+        selectTab(document.getElementById(panel.getAttribute('aria-labelledby')));
+        target.scrollIntoView();
+    }
+}
+
+function setupTabs() {
+    var tabs = document.querySelectorAll('.tab-list .tab');
+
+    if (tabs.length === 0) {
+        return;
+    }
 
+    tabs.forEach(function (tab) {
+        tab.addEventListener('click', function () {
+            selectTabs(tab.dataset.tab);
+            localStorage.setItem('doctave-tab', tab.dataset.tab);
+        });
+
+        tab.addEventListener('keydown', function (e) {
+            var siblings = Array.from(tab.parentNode.children);
+            var index = siblings.indexOf(tab);
+            var next;
+
+            switch (e.keyCode) {
+                case 37: // if the LEFT key is pressed
+                    next = siblings[(index + siblings.length - 1) % siblings.length];
+                    break;
+                case 39: // if the RIGHT key is pressed
+                    next = siblings[(index + 1) % siblings.length];
+                    break;
+                case 36: // if the HOME key is pressed
+                    next = siblings[0];
+                    break;
+                case 35: // if the END key is pressed
+                    next = siblings[siblings.length - 1];
+                    break;
+                default:
+                    return;
+            }
+
+            next.focus();
+            next.click();
+            e.preventDefault();
+        });
+    });
+
+    var saved = localStorage.getItem('doctave-tab');
+    if (saved) {
+        selectTabs(saved);
+    }
+
+    showTabForHash();
+    window.addEventListener('hashchange', showTabForHash);
+}
+
+// Setup the buttons that copy the code in code blocks
+function setupCopyButtons() {
+    document.querySelectorAll('.code-block .copy-code').forEach(function (button) {
+        button.addEventListener('click', function () {
+            var code = button.parentNode.querySelector('code');
+
+            navigator.clipboard.writeText(code.textContent).then(function () {
+                button.textContent = 'Copied!';
+                setTimeout(function () {
+                    button.textContent = 'Copy';
+                }, 2000);
+            });
+        });
+    });
+}
+
+// Pages in the search index have URLs relative to the root of the site when
+// the site is built with relative links
+function pageUrl(uri) {
+    return uri.charAt(0) === '/' ? uri : BASE_PATH + uri;
+}
 
 // Load search index
 var INDEX;
 
-fetch(BASE_PATH + 'search_index.json')
-    .then(function (response) {
-        if (!response.ok) {
-            throw new Error("HTTP error " + response.status);
+function loadIndex(json) {
+    INDEX = elasticlunr.Index.load(json)
+    document.getElementById('search-box').oninput = search;
+    search();
+}
+
+// Inlined by search_index.js when the site is built with relative links
+if (typeof SEARCH_INDEX !== 'undefined') {
+    loadIndex(SEARCH_INDEX);
+} else {
+    fetch(BASE_PATH + 'search_index.json')
+        .then(function (response) {
+            if (!response.ok) {
+                throw new Error("HTTP error " + response.status);
+            }
+            return response.json();
+        })
+        .then(loadIndex);
+}
+
+// On the 404 page, suggest the page with the most similar URL. The pages are
+// listed on the page itself, since not all of them are in the search index.
+function suggestPage() {
+    var suggestion = document.getElementById('not-found-suggestion');
+
+    if (!suggestion || typeof SUGGESTED_PAGES === 'undefined') {
+        return;
+    }
+
+    var path = normalizePath(window.location.pathname);
+    var best = null;
+    var bestDistance = Infinity;
+
+    SUGGESTED_PAGES.forEach(function (page) {
+        var distance = editDistance(path, normalizePath(page.url));
+
+        if (distance < bestDistance) {
+            best = page;
+            bestDistance = distance;
         }
-        return response.json();
-    })
-    .then(function (json) {
-        INDEX = elasticlunr.Index.load(json)
-        document.getElementById('search-box').oninput = search;
-        search();
     });
 
+    // Only suggest pages that are reasonably close to what was asked for
+    if (best && bestDistance <= Math.max(path.length, 3) / 2) {
+        var link = suggestion.getElementsByTagName('a')[0];
+        link.href = best.url;
+        link.textContent = best.title;
+        suggestion.hidden = false;
+    }
+}
+
+suggestPage();
+
+function normalizePath(path) {
+    return decodeURIComponent(path)
+        .toLowerCase()
+        .replace(/(\/index)?\.html$/, '')
+        .replace(/\/+$/, '');
+}
+
+function editDistance(a, b) {
+    var previous = [];
+    for (var j = 0; j <= b.length; j++) {
+        previous.push(j);
+    }
+
+    for (var i = 1; i <= a.length; i++) {
+        var current = [i];
+
+        for (var j = 1; j <= b.length; j++) {
+            var cost = a[i - 1] === b[j - 1] ? 0 : 1;
+            current.push(Math.min(previous[j] + 1, current[j - 1] + 1, previous[j - 1] + cost));
+        }
+
+        previous = current;
+    }
+
+    return previous[b.length];
+}
+
 // Setup keyboard shortcuts
 document.onkeydown = function (e) {
     var searchResults = document.getElementById('search-results');
@@ -239,3 +430,5 @@ document.onkeydown = function (e) {
 disableScrollifMenuOpen();
 dragRightMenu();
 setColor();
+setupTabs();
+setupCopyButtons();
@@ -0,0 +1,71 @@
+use crate::site::{Site, SiteBackend};
+use crate::snippets::BrokenInclude;
+use crate::Directory;
+use crate::{Error, Result};
+
+use std::path::PathBuf;
+
+pub fn run<B: SiteBackend>(site: &Site<B>) -> Result<()> {
+    let mut broken = Vec::new();
+    find_broken_includes(&site.root(), &mut broken);
+
+    if broken.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::broken_includes(broken))
+    }
+}
+
+fn find_broken_includes(dir: &Directory, broken: &mut Vec<(PathBuf, BrokenInclude)>) {
+    for doc in &dir.docs {
+        for include in doc.broken_includes() {
+            broken.push((doc.original_path().to_owned(), include.clone()));
+        }
+    }
+
+    for child_dir in &dir.dirs {
+        find_broken_includes(child_dir, broken);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+    use crate::frontmatter;
+    use crate::snippets::Snippets;
+    use crate::Document;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    #[test]
+    fn detects_missing_source_files() {
+        let config =
+            Config::from_yaml_str(Path::new("project"), "---\ntitle: My project\n").unwrap();
+        let content = "---\ntitle: Home\n---\n\n# Welcome\n\n{{#code src/missing.rs }}";
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![Document::new(
+                Path::new("README.md"),
+                content.to_string(),
+                frontmatter::parse(content).unwrap(),
+                "/",
+                &Snippets::load(&config),
+                &BTreeMap::new(),
+            )],
+            dirs: vec![],
+        };
+
+        let site = Site::with_root(root, config);
+        let error = run(&site).unwrap_err();
+
+        assert!(
+            format!("{}", error).contains(
+                "README.md : line 7 : {{#code src/missing.rs }} : Could not read `src/missing.rs`"
+            ),
+            "Error was: {}",
+            error
+        );
+    }
+}
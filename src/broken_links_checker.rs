@@ -1,5 +1,6 @@
 use crate::config::Config;
 use crate::preview_server::resolve_file;
+use crate::redirects;
 use crate::site::{Site, SiteBackend};
 use crate::Directory;
 use crate::{Error, Result};
@@ -10,10 +11,21 @@ pub fn run<B: SiteBackend>(site: &Site<B>) -> Result<()> {
     let mut broken_links = Vec::new();
     find_broken_links(&site.root(), site, &mut broken_links, &site.config);
 
-    if broken_links.len() == 0 {
-        Ok(())
-    } else {
-        Err(Error::broken_links(broken_links))
+    let broken_redirects = redirects::find(&site.config, &site.root())
+        .into_iter()
+        .filter(|r| r.is_local() && !matches_a_target(Path::new(&r.to), site))
+        .collect::<Vec<_>>();
+
+    match (broken_links.is_empty(), broken_redirects.is_empty()) {
+        (true, true) => Ok(()),
+        (false, true) => Err(Error::broken_links(broken_links)),
+        (true, false) => Err(Error::broken_redirects(broken_redirects)),
+        // Report both at once, so fixing one doesn't reveal the other
+        (false, false) => Err(Error::new(format!(
+            "{}\n{}",
+            Error::broken_links(broken_links),
+            Error::broken_redirects(broken_redirects)
+        ))),
     }
 }
 
@@ -28,7 +40,13 @@ fn find_broken_links<B: SiteBackend>(
             match &link.url {
                 doctave_markdown::UrlType::Remote(_) => {}
                 doctave_markdown::UrlType::Local(path) => {
-                    if !matches_a_target(&path, site) {
+                    let found = match path.to_str().and_then(|p| p.strip_prefix('#')) {
+                        // Anchors on the same page must match one of its headings
+                        Some(anchor) => doc.headings().iter().any(|h| h.anchor == anchor),
+                        None => matches_a_target(&path, site),
+                    };
+
+                    if !found {
                         broken_links.push((doc.original_path().to_owned(), link.clone()))
                     }
                 }
@@ -49,6 +67,7 @@ fn matches_a_target<B: SiteBackend>(path: &Path, site: &Site<B>) -> bool {
 mod test {
     use super::*;
     use crate::config::Config;
+    use crate::snippets::Snippets;
     use crate::Document;
     use std::collections::BTreeMap;
 
@@ -56,14 +75,28 @@ mod test {
         let mut frontmatter = BTreeMap::new();
         frontmatter.insert("title".to_string(), name.to_string());
 
-        Document::new(Path::new(path), content.to_string(), frontmatter, "/")
+        Document::new(
+            Path::new(path),
+            content.to_string(),
+            frontmatter,
+            "/",
+            &Snippets::default(),
+            &BTreeMap::new(),
+        )
     }
 
     fn page_with_base_path(path: &str, name: &str, content: &str, base_path: &str) -> Document {
         let mut frontmatter = BTreeMap::new();
         frontmatter.insert("title".to_string(), name.to_string());
 
-        Document::new(Path::new(path), content.to_string(), frontmatter, base_path)
+        Document::new(
+            Path::new(path),
+            content.to_string(),
+            frontmatter,
+            base_path,
+            &Snippets::default(),
+            &BTreeMap::new(),
+        )
     }
 
     fn config(yaml: Option<&str>) -> Config {
@@ -114,6 +147,28 @@ mod test {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn reports_broken_links_and_redirects_together() {
+        let config = config(Some("---\ntitle: My project\nredirects:\n  /old: /gone\n"));
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![page("README.md", "Getting Started", "[nowhere](/nowhere)")],
+            dirs: vec![],
+        };
+
+        let site = Site::with_root(root, config);
+        site.build().unwrap();
+        let error = format!("{}", run(&site).unwrap_err());
+
+        assert!(
+            error.contains("[nowhere](/nowhere)"),
+            "Error was: {}",
+            error
+        );
+        assert!(error.contains("/old -> /gone"), "Error was: {}", error);
+    }
+
     #[test]
     fn does_not_mind_if_the_url_has_an_html_extension() {
         let config = config(None);
@@ -241,4 +296,53 @@ mod test {
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn checks_anchors_on_the_same_page() {
+        let config = config(None);
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![page(
+                "README.md",
+                "Getting Started",
+                "See [feeds](#feeds) and [nothing](#nothing).\n\n## Feeds",
+            )],
+            dirs: vec![],
+        };
+
+        let site = Site::with_root(root, config);
+        site.build().unwrap();
+        let error = format!("{}", run(&site).unwrap_err());
+
+        assert!(
+            error.contains("[nothing](#nothing)"),
+            "Error was: {}",
+            error
+        );
+        assert!(!error.contains("[feeds](#feeds)"), "Error was: {}", error);
+    }
+
+    #[test]
+    fn finds_anchors_of_headings_inside_tabs() {
+        let config = config(None);
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![page(
+                "README.md",
+                "Getting Started",
+                "See [Linux](#on-linux).\n\n:::tabs\n== macOS\n## On macOS\n== Linux\n## On Linux\n:::",
+            )],
+            dirs: vec![],
+        };
+
+        let site = Site::with_root(root, config);
+        site.build().unwrap();
+        let result = run(&site);
+
+        println!("{:?}", result);
+
+        assert!(result.is_ok());
+    }
 }
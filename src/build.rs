@@ -43,14 +43,14 @@ impl BuildCommand {
         if result.is_ok() {
             bunt::writeln!(stdout, "Site built in {$bold}{:?}{/$}\n", duration)?;
 
-            let dead_links_result = site.check_dead_links();
+            let checks_result = site.check();
 
-            if dead_links_result.is_err() && config.allow_failed_checks() {
+            if checks_result.is_err() && config.allow_failed_checks() {
                 bunt::writeln!(stdout, "{$bold}{$yellow}WARNING{/$}{/$}")?;
-                bunt::writeln!(stdout, "{}", dead_links_result.unwrap_err())?;
+                bunt::writeln!(stdout, "{}", checks_result.unwrap_err())?;
                 Ok(())
             } else {
-                dead_links_result
+                checks_result
             }
         } else {
             result
@@ -0,0 +1,360 @@
+//! Callouts written as GitHub style alerts, or as containers:
+//!
+//! ```markdown
+//! > [!WARNING] Optional title
+//! > Be careful!
+//!
+//! :::tip Optional title
+//! Try this instead.
+//! :::
+//! ```
+//!
+//! Both are turned into placeholders in the Markdown before it is parsed, so
+//! that their content is rendered as usual. The placeholders are then turned
+//! into styled boxes once the configured callout types are known.
+
+use std::collections::BTreeMap;
+
+use crate::containers::{
+    self, closes_fence, container_closing, container_opening, is_indented_code, opens_fence,
+    Opening,
+};
+use crate::html;
+
+/// The styles callouts can use, which set their colors
+pub static STYLES: &[&str] = &["info", "success", "warning", "error"];
+
+/// A kind of callout, such as `note` or `warning`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalloutType {
+    /// Shown at the top of the callout, unless it sets its own title
+    pub title: String,
+    /// One of `STYLES`
+    pub style: String,
+    pub icon: String,
+}
+
+impl CalloutType {
+    fn new(title: &str, style: &str, icon: &str) -> Self {
+        CalloutType {
+            title: title.to_string(),
+            style: style.to_string(),
+            icon: icon.to_string(),
+        }
+    }
+}
+
+/// The callout types available on the site, by name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Callouts {
+    types: BTreeMap<String, CalloutType>,
+}
+
+impl Default for Callouts {
+    fn default() -> Self {
+        let mut types = BTreeMap::new();
+        types.insert("note".to_string(), CalloutType::new("Note", "info", "ℹ️"));
+        types.insert("info".to_string(), CalloutType::new("Info", "info", "ℹ️"));
+        types.insert("tip".to_string(), CalloutType::new("Tip", "success", "💡"));
+        types.insert(
+            "success".to_string(),
+            CalloutType::new("Success", "success", "✅"),
+        );
+        types.insert(
+            "important".to_string(),
+            CalloutType::new("Important", "info", "❗"),
+        );
+        types.insert(
+            "warning".to_string(),
+            CalloutType::new("Warning", "warning", "⚠️"),
+        );
+        types.insert(
+            "caution".to_string(),
+            CalloutType::new("Caution", "error", "🛑"),
+        );
+        types.insert(
+            "danger".to_string(),
+            CalloutType::new("Danger", "error", "🛑"),
+        );
+        types.insert(
+            "error".to_string(),
+            CalloutType::new("Error", "error", "🛑"),
+        );
+
+        Callouts { types }
+    }
+}
+
+impl Callouts {
+    /// The built in callout types, with the given types added or replacing
+    /// them.
+    pub fn with_types(custom: BTreeMap<String, CalloutType>) -> Self {
+        let mut callouts = Callouts::default();
+        callouts.types.extend(custom);
+        callouts
+    }
+
+    /// The type with the given name. Unknown types fall back to a plain
+    /// callout titled after the name.
+    pub fn get(&self, name: &str) -> CalloutType {
+        self.types.get(name).cloned().unwrap_or_else(|| {
+            let mut chars = name.chars();
+            let title = chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect())
+                .unwrap_or_default();
+
+            CalloutType {
+                title,
+                style: "info".to_string(),
+                icon: String::new(),
+            }
+        })
+    }
+}
+
+/// Replaces callouts in the Markdown with placeholders that wrap their
+/// content. Callouts inside code blocks are left alone.
+pub fn expand(markdown: &str) -> String {
+    transform(markdown, &|opening| {
+        let title = opening
+            .title
+            .map(|t| format!(" data-title=\"{}\"", html::escape(t)))
+            .unwrap_or_default();
+
+        (
+            format!(
+                "<div class=\"callout\" data-callout=\"{}\"{}>\n\n",
+                opening.name, title
+            ),
+            "\n\n</div>\n".to_string(),
+        )
+    })
+}
+
+/// Replaces callouts in the Markdown with their titles and content, leaving
+/// out the callout syntax. Used for indexing pages for search.
+pub fn to_plain_text(markdown: &str) -> String {
+    transform(markdown, &|opening| {
+        let title = opening.title.map(|t| format!("{}\n\n", t));
+
+        (title.unwrap_or_default(), "\n".to_string())
+    })
+}
+
+/// Turns the placeholders in the rendered HTML into callouts, using the
+/// given callout types.
+pub fn render(html: &str, callouts: &Callouts) -> String {
+    static PLACEHOLDER: &str = "<div class=\"callout\" data-callout=";
+
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(PLACEHOLDER) {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let attributes = &rest[start..end];
+
+        let name = html::attribute(attributes, "data-callout").unwrap_or_default();
+        let kind = callouts.get(&name);
+        let title =
+            html::attribute(attributes, "data-title").unwrap_or_else(|| html::escape(&kind.title));
+        let icon = if kind.icon.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "<span class=\"callout-icon\" aria-hidden=\"true\">{}</span>",
+                html::escape(&kind.icon)
+            )
+        };
+
+        out.push_str(&rest[..start]);
+        out.push_str(&format!(
+            "<div class=\"callout {} callout-{}\"><p class=\"callout-title\">{}{}</p>",
+            kind.style, name, icon, title
+        ));
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Rewrites each callout in the Markdown with the opening and closing text
+/// returned by the function. Callouts can be nested.
+fn transform<F>(markdown: &str, wrap: &F) -> String
+where
+    F: Fn(&Opening) -> (String, String),
+{
+    let lines = markdown.lines().collect::<Vec<_>>();
+    let mut out = String::with_capacity(markdown.len());
+    let mut fence: Option<(char, usize)> = None;
+    // The closing text and colon count of each open container
+    let mut containers: Vec<(String, usize)> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+
+        if let Some(open) = fence {
+            if closes_fence(line, open) {
+                fence = None;
+            }
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+
+        if let Some(open) = opens_fence(line) {
+            fence = Some(open);
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+
+        if let Some((colons, opening)) = container_opening(line) {
+            let (start, end) = wrap(&opening);
+            out.push_str(&start);
+            containers.push((end, colons));
+            continue;
+        }
+
+        if let Some(colons) = container_closing(line) {
+            if containers.last().map(|(_, c)| *c <= colons) == Some(true) {
+                let (end, _) = containers.pop().unwrap();
+                out.push_str(&end);
+                continue;
+            }
+        }
+
+        if let Some(opening) = alert_opening(line) {
+            let mut content = String::new();
+            while i < lines.len() && lines[i].trim_start().starts_with('>') {
+                let quoted = lines[i].trim_start()[1..].strip_prefix(' ');
+                content.push_str(quoted.unwrap_or(&lines[i].trim_start()[1..]));
+                content.push('\n');
+                i += 1;
+            }
+
+            let (start, end) = wrap(&opening);
+            out.push_str(&start);
+            out.push_str(&transform(&content, wrap));
+            out.push_str(&end);
+            continue;
+        }
+
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    // Close containers left open at the end of the page
+    while let Some((end, _)) = containers.pop() {
+        out.push_str(&end);
+    }
+
+    if !markdown.ends_with('\n') && out.ends_with('\n') {
+        out.pop();
+    }
+
+    out
+}
+
+/// Matches `> [!NAME] Optional title`
+fn alert_opening(line: &str) -> Option<Opening<'_>> {
+    if is_indented_code(line) {
+        return None;
+    }
+
+    let rest = line.trim_start().strip_prefix('>')?.trim_start();
+    let rest = rest.strip_prefix("[!")?;
+    let end = rest.find(']')?;
+
+    containers::name(&rest[..end]).map(|name| Opening {
+        name,
+        title: Some(rest[end + 1..].trim()).filter(|t| !t.is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn expands_alerts() {
+        let markdown = "Before\n\n> [!WARNING]\n> Be *careful*\n>\n> Really\n\nAfter";
+
+        assert_eq!(
+            expand(markdown),
+            "Before\n\n\
+             <div class=\"callout\" data-callout=\"warning\">\n\n\
+             Be *careful*\n\nReally\n\
+             \n\n</div>\n\
+             \nAfter"
+        );
+    }
+
+    #[test]
+    fn expands_containers() {
+        let markdown = ":::tip Try this & that\nContent\n:::\n\nAfter\n";
+
+        assert_eq!(
+            expand(markdown),
+            "<div class=\"callout\" data-callout=\"tip\" data-title=\"Try this &amp; that\">\n\n\
+             Content\n\
+             \n\n</div>\n\
+             \nAfter\n"
+        );
+    }
+
+    #[test]
+    fn nests_containers() {
+        let markdown = "::::note\nOuter\n:::tip\nInner\n:::\n::::";
+
+        assert_eq!(to_plain_text(markdown), "Outer\nInner\n\n");
+        assert_eq!(
+            expand(markdown),
+            "<div class=\"callout\" data-callout=\"note\">\n\nOuter\n\
+             <div class=\"callout\" data-callout=\"tip\">\n\nInner\n\
+             \n\n</div>\n\n\n</div>"
+        );
+    }
+
+    #[test]
+    fn leaves_code_blocks_and_quotes_alone() {
+        let markdown =
+            "```\n:::tip\n> [!NOTE]\n```\n\n> Just a quote\n\n:: not a callout\n\n    :::tip";
+
+        assert_eq!(expand(markdown), markdown);
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let html = "<div class=\"callout\" data-callout=\"warning\">\n<p>Careful</p>\n</div>\
+                    <div class=\"callout\" data-callout=\"custom\" data-title=\"A &amp; B\">\n</div>";
+
+        assert_eq!(
+            render(html, &Callouts::default()),
+            "<div class=\"callout warning callout-warning\"><p class=\"callout-title\">\
+             <span class=\"callout-icon\" aria-hidden=\"true\">⚠️</span>Warning</p>\n\
+             <p>Careful</p>\n</div>\
+             <div class=\"callout info callout-custom\"><p class=\"callout-title\">A &amp; B</p>\n</div>"
+        );
+    }
+
+    #[test]
+    fn custom_types_replace_built_in_ones() {
+        let mut custom = BTreeMap::new();
+        custom.insert(
+            "note".to_string(),
+            CalloutType::new("Remember", "success", "📝"),
+        );
+        let callouts = Callouts::with_types(custom);
+
+        assert_eq!(callouts.get("note").title, "Remember");
+        assert_eq!(callouts.get("warning").title, "Warning");
+        assert_eq!(callouts.get("custom-type").title, "Custom-type");
+    }
+}
@@ -0,0 +1,323 @@
+//! Options for code blocks, set in the info string of the fence:
+//!
+//! ```markdown
+//! ```rust title="main.rs" {3-5} showLineNumbers
+//! ```
+//!
+//! The options are moved out of the info string before the Markdown is
+//! parsed, into a placeholder in front of the code block. Once the page is
+//! rendered, each code block is wrapped with its title, highlighted lines,
+//! line numbers and a button to copy the code.
+
+use crate::containers;
+use crate::html;
+
+/// Comes before code blocks that set options
+static OPTIONS: &str = "<div class=\"code-block-options\"";
+
+#[derive(Debug, Default, PartialEq)]
+struct CodeOptions {
+    title: Option<String>,
+    /// Inclusive ranges of the line numbers to highlight
+    highlight: Vec<(usize, usize)>,
+    line_numbers: bool,
+}
+
+impl CodeOptions {
+    fn is_empty(&self) -> bool {
+        self == &CodeOptions::default()
+    }
+}
+
+/// Moves the options out of the info strings of fenced code blocks, leaving
+/// only the language for the Markdown parser.
+pub fn expand(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut fence: Option<(char, usize)> = None;
+
+    for line in markdown.lines() {
+        if let Some(open) = fence {
+            if containers::closes_fence(line, open) {
+                fence = None;
+            }
+        } else if let Some((c, length)) = containers::opens_fence(line) {
+            fence = Some((c, length));
+
+            if !containers::is_indented_code(line) {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                let info = &line.trim_start()[length..];
+                let (language, options) = parse_info(info);
+
+                if !options.is_empty() {
+                    out.push_str(indent);
+                    out.push_str(&placeholder(&options));
+                    out.push_str("\n\n");
+                    out.push_str(indent);
+                    out.push_str(&c.to_string().repeat(length));
+                    out.push_str(language);
+                    out.push('\n');
+                    continue;
+                }
+            }
+        }
+
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    if !markdown.ends_with('\n') && out.ends_with('\n') {
+        out.pop();
+    }
+
+    out
+}
+
+/// Splits the info string into the language and the options
+fn parse_info(info: &str) -> (&str, CodeOptions) {
+    let mut options = CodeOptions::default();
+    let mut language = "";
+    let mut rest = info.trim();
+
+    while !rest.is_empty() {
+        let (token, tail) = next_token(rest);
+        rest = tail.trim_start();
+
+        if let Some(ranges) = token.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+            options.highlight.extend(parse_ranges(ranges));
+        } else if let Some(title) = token.strip_prefix("title=") {
+            options.title = Some(unquote(title).to_string());
+        } else if token == "showLineNumbers" {
+            options.line_numbers = true;
+        } else if language.is_empty() && !token.contains('=') {
+            language = token;
+        }
+    }
+
+    (language, options)
+}
+
+/// The next word in the info string, keeping quoted values together
+fn next_token(text: &str) -> (&str, &str) {
+    let mut quote = None;
+
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c.is_whitespace() => return (&text[..i], &text[i..]),
+            None => {}
+        }
+    }
+
+    (text, "")
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in &['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(*quote)
+            .and_then(|v| v.strip_suffix(*quote))
+        {
+            return inner;
+        }
+    }
+
+    value
+}
+
+/// Parses line ranges such as `1,3-5`. Parts that aren't numbers are skipped.
+fn parse_ranges(ranges: &str) -> Vec<(usize, usize)> {
+    ranges
+        .split(',')
+        .filter_map(|range| {
+            let mut bounds = range.trim().splitn(2, '-');
+            let start = bounds.next()?.trim().parse().ok()?;
+            let end = match bounds.next() {
+                Some(end) => end.trim().parse().ok()?,
+                None => start,
+            };
+
+            Some((start, end))
+        })
+        .collect()
+}
+
+fn placeholder(options: &CodeOptions) -> String {
+    let mut out = String::from(OPTIONS);
+
+    if let Some(title) = &options.title {
+        out.push_str(&format!(" data-title=\"{}\"", html::escape(title)));
+    }
+    if !options.highlight.is_empty() {
+        let ranges = options
+            .highlight
+            .iter()
+            .map(|(start, end)| format!("{}-{}", start, end))
+            .collect::<Vec<_>>();
+        out.push_str(&format!(" data-highlight=\"{}\"", ranges.join(",")));
+    }
+    if options.line_numbers {
+        out.push_str(" data-line-numbers=\"true\"");
+    }
+    out.push_str("></div>");
+
+    out
+}
+
+/// Reads the options back from a placeholder in the rendered HTML
+fn read_placeholder(attributes: &str) -> CodeOptions {
+    CodeOptions {
+        title: html::attribute(attributes, "data-title"),
+        highlight: html::attribute(attributes, "data-highlight")
+            .map(|ranges| parse_ranges(&ranges))
+            .unwrap_or_default(),
+        line_numbers: html::attribute(attributes, "data-line-numbers").is_some(),
+    }
+}
+
+/// Wraps each code block in the rendered HTML with its title, highlighted
+/// lines, line numbers and a copy button.
+pub fn render(html: &str) -> String {
+    static START: &str = "<pre><code";
+    static END: &str = "</code></pre>";
+
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(START) {
+        let end = match rest[start..].find(END) {
+            Some(end) => start + end + END.len(),
+            None => break,
+        };
+
+        // Use the options placeholder right before the code block, if any
+        let before = &rest[..start];
+        let (before, options) = match before.rfind(OPTIONS) {
+            Some(i) if only_placeholder(&before[i..]) => {
+                (&before[..i], read_placeholder(&before[i..]))
+            }
+            _ => (before, CodeOptions::default()),
+        };
+
+        out.push_str(before);
+        out.push_str(&wrap(&rest[start..end], &options));
+        rest = &rest[end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Whether the HTML is an options placeholder, followed by nothing but
+/// whitespace
+fn only_placeholder(html: &str) -> bool {
+    match html.find("></div>") {
+        Some(end) => html[end + "></div>".len()..].trim().is_empty(),
+        None => false,
+    }
+}
+
+fn wrap(block: &str, options: &CodeOptions) -> String {
+    let mut out = String::from("<div class=\"code-block\">");
+
+    if let Some(title) = &options.title {
+        out.push_str(&format!("<div class=\"code-block-title\">{}</div>", title));
+    }
+
+    if options.line_numbers || !options.highlight.is_empty() {
+        let code = &block["<pre>".len()..];
+        let lines = code
+            .trim_end_matches("</code></pre>")
+            .trim_end_matches('\n');
+        let count = lines.matches('\n').count() + 1;
+
+        if options.line_numbers {
+            out.push_str("<pre class=\"with-line-numbers\">");
+        } else {
+            out.push_str("<pre>");
+        }
+
+        out.push_str("<span class=\"code-lines\" aria-hidden=\"true\">");
+        for line in 1..=count {
+            let highlighted = options
+                .highlight
+                .iter()
+                .any(|(start, end)| *start <= line && line <= *end);
+
+            out.push_str(&format!(
+                "<span class=\"code-line{}\" data-line=\"{}\"></span>",
+                if highlighted { " highlighted" } else { "" },
+                line
+            ));
+        }
+        out.push_str("</span>");
+        out.push_str(code);
+    } else {
+        out.push_str(block);
+    }
+
+    out.push_str(
+        "<button type=\"button\" class=\"copy-code\" aria-label=\"Copy code\">Copy</button>",
+    );
+    out.push_str("</div>");
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_info_strings() {
+        let (language, options) = parse_info("rust title=\"src/main rs\" {1,3-5} showLineNumbers");
+
+        assert_eq!(language, "rust");
+        assert_eq!(
+            options,
+            CodeOptions {
+                title: Some("src/main rs".to_string()),
+                highlight: vec![(1, 1), (3, 5)],
+                line_numbers: true,
+            }
+        );
+
+        let (language, options) = parse_info("{2} title=main.rs");
+        assert_eq!(language, "");
+        assert_eq!(options.title, Some("main.rs".to_string()));
+        assert_eq!(options.highlight, vec![(2, 2)]);
+    }
+
+    #[test]
+    fn moves_options_out_of_fences() {
+        let markdown = "```rust title=\"a & b.rs\" {2}\nfn main() {}\n```\n\n```js\nlet a;\n```";
+
+        assert_eq!(
+            expand(markdown),
+            "<div class=\"code-block-options\" data-title=\"a &amp; b.rs\" data-highlight=\"2-2\"></div>\n\n\
+             ```rust\nfn main() {}\n```\n\n```js\nlet a;\n```"
+        );
+    }
+
+    #[test]
+    fn wraps_code_blocks() {
+        let html =
+            "<div class=\"code-block-options\" data-title=\"main.rs\" data-highlight=\"2-2\" \
+                    data-line-numbers=\"true\"></div>\n\
+                    <pre><code class=\"language-rust\">fn main() {\n}\n</code></pre>\n\
+                    <pre><code>plain</code></pre>";
+
+        assert_eq!(
+            render(html),
+            "<div class=\"code-block\"><div class=\"code-block-title\">main.rs</div>\
+             <pre class=\"with-line-numbers\"><span class=\"code-lines\" aria-hidden=\"true\">\
+             <span class=\"code-line\" data-line=\"1\"></span>\
+             <span class=\"code-line highlighted\" data-line=\"2\"></span></span>\
+             <code class=\"language-rust\">fn main() {\n}\n</code></pre>\
+             <button type=\"button\" class=\"copy-code\" aria-label=\"Copy code\">Copy</button></div>\n\
+             <div class=\"code-block\"><pre><code>plain</code></pre>\
+             <button type=\"button\" class=\"copy-code\" aria-label=\"Copy code\">Copy</button></div>"
+        );
+    }
+}
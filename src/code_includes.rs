@@ -0,0 +1,316 @@
+//! Code blocks included from source files in the project, so that examples
+//! don't drift from the real code:
+//!
+//! ```markdown
+//! {{#code src/main.rs }}
+//! {{#code src/main.rs#L10-L20 }}
+//! {{#code src/main.rs#setup {2} showLineNumbers }}
+//! ```
+//!
+//! Paths are relative to the project root. A file can be narrowed down to a
+//! range of lines, or to a named region marked with `#region name` and
+//! `#endregion` comments. Anything after the path is added to the code
+//! block's info string, after the language inferred from the file extension.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// The result of including a source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inclusion {
+    /// The fenced code block
+    pub content: String,
+    /// The file that was included
+    pub path: PathBuf,
+}
+
+/// Builds a fenced code block from the source file the directive points to.
+/// Lines after the first are prefixed with the indent, so the code block can
+/// be included inside lists.
+///
+/// Returns a message describing the problem if the file or the requested
+/// lines can't be read.
+pub fn include(
+    project_root: &Path,
+    directive: &str,
+    indent: &str,
+) -> std::result::Result<Inclusion, String> {
+    let directive = directive.trim();
+    let (target, options) = match directive.find(char::is_whitespace) {
+        Some(i) => (&directive[..i], directive[i..].trim()),
+        None => (directive, ""),
+    };
+    let (file, selector) = match target.find('#') {
+        Some(i) => (&target[..i], Some(&target[i + 1..])),
+        None => (target, None),
+    };
+
+    if file.is_empty() {
+        return Err("No file given to include".to_string());
+    }
+
+    let relative = Path::new(file.trim_start_matches('/'));
+    if relative.components().any(|c| c == Component::ParentDir) {
+        return Err(format!("`{}` must be inside the project root", file));
+    }
+
+    let path = project_root.join(relative);
+    let source = fs::read_to_string(&path).map_err(|_| format!("Could not read `{}`", file))?;
+    let lines = source.lines().collect::<Vec<_>>();
+
+    let selected = match selector {
+        None => lines
+            .iter()
+            .filter(|l| !is_region_marker(l))
+            .copied()
+            .collect(),
+        Some(selector) => match line_range(selector) {
+            Some((start, end)) => {
+                if start == 0 || start > end || end > lines.len() {
+                    return Err(format!(
+                        "Lines {}-{} are out of range for `{}`, which has {} lines",
+                        start,
+                        end,
+                        file,
+                        lines.len()
+                    ));
+                }
+                lines[start - 1..end].to_vec()
+            }
+            None => region(&lines, selector)
+                .ok_or_else(|| format!("Could not find region `{}` in `{}`", selector, file))?,
+        },
+    };
+
+    let fence = "`".repeat(longest_backtick_run(&selected).max(2) + 1);
+
+    let mut content = format!("{}{}", fence, language(relative));
+    if !options.is_empty() {
+        content.push(' ');
+        content.push_str(options);
+    }
+    content.push('\n');
+    for line in dedent(&selected) {
+        if !line.is_empty() {
+            content.push_str(indent);
+            content.push_str(line);
+        }
+        content.push('\n');
+    }
+    content.push_str(indent);
+    content.push_str(&fence);
+
+    Ok(Inclusion { content, path })
+}
+
+/// Parses `L10` or `L10-L20` into an inclusive range of line numbers
+fn line_range(selector: &str) -> Option<(usize, usize)> {
+    let mut bounds = selector.splitn(2, '-');
+    let start = bounds.next()?.strip_prefix('L')?.parse().ok()?;
+    let end = match bounds.next() {
+        Some(end) => end.trim_start_matches('L').parse().ok()?,
+        None => start,
+    };
+
+    Some((start, end))
+}
+
+/// The lines between `#region name` and its `#endregion`, leaving out the
+/// markers of any regions nested inside it.
+fn region<'a>(lines: &[&'a str], name: &str) -> Option<Vec<&'a str>> {
+    let start = lines
+        .iter()
+        .position(|l| marker(l, "#region") == Some(name))?;
+
+    let mut selected = Vec::new();
+    let mut depth = 0;
+
+    for line in &lines[start + 1..] {
+        if marker(line, "#region").is_some() {
+            depth += 1;
+            continue;
+        }
+
+        if let Some(end) = marker(line, "#endregion") {
+            if end == name || (end.is_empty() && depth == 0) {
+                return Some(selected);
+            }
+            depth -= 1;
+            continue;
+        }
+
+        selected.push(*line);
+    }
+
+    Some(selected)
+}
+
+fn is_region_marker(line: &str) -> bool {
+    marker(line, "#region").is_some() || marker(line, "#endregion").is_some()
+}
+
+/// The name after the keyword, if the line contains it, such as `setup` in
+/// `// #region setup`. The name is empty if there is none.
+fn marker<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let start = line.find(keyword)? + keyword.len();
+    let rest = &line[start..];
+
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    match rest.split_whitespace().next() {
+        // Closing comments, as in `/* #endregion */`
+        Some("*/") | Some("-->") | None => Some(""),
+        Some(name) => Some(name),
+    }
+}
+
+/// Removes the indentation shared by all lines that aren't blank. Only
+/// spaces and tabs count as indentation.
+fn dedent<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    let shared = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start_matches(&[' ', '\t'][..]).len())
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|l| {
+            if l.trim().is_empty() {
+                ""
+            } else {
+                &l[shared..]
+            }
+        })
+        .collect()
+}
+
+fn longest_backtick_run(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .flat_map(|l| l.split(|c| c != '`'))
+        .map(str::len)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The language to highlight the file as, based on its extension
+fn language(path: &Path) -> String {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let language = match extension.as_str() {
+        "rs" => "rust",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" => "typescript",
+        "py" => "python",
+        "rb" => "ruby",
+        "sh" | "bash" | "zsh" => "bash",
+        "yml" => "yaml",
+        "md" => "markdown",
+        "h" => "c",
+        "cc" | "hpp" => "cpp",
+        "cs" => "csharp",
+        "kt" => "kotlin",
+        "ex" | "exs" => "elixir",
+        "hs" => "haskell",
+        "htm" => "html",
+        other => other,
+    };
+
+    language.to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A project with a `src/main.rs` that has nested regions
+    fn project() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("code_includes")
+    }
+
+    #[test]
+    fn includes_whole_files_without_region_markers() {
+        let root = project();
+        let inclusion = include(&root, " src/main.rs ", "").unwrap();
+
+        assert_eq!(
+            inclusion.content,
+            "```rust\nuse std::io;\n\nfn main() {\n    let a = 1;\n    let b = 2;\n}\n```"
+        );
+        assert_eq!(inclusion.path, root.join("src/main.rs"));
+    }
+
+    #[test]
+    fn includes_line_ranges_with_options() {
+        let root = project();
+        let inclusion = include(&root, "/src/main.rs#L3-L5 {2} showLineNumbers", "  ").unwrap();
+
+        assert_eq!(
+            inclusion.content,
+            "```rust {2} showLineNumbers\n  fn main() {\n      // #region setup\n      let a = 1;\n  ```"
+        );
+    }
+
+    #[test]
+    fn includes_regions() {
+        let root = project();
+
+        assert_eq!(
+            include(&root, "src/main.rs#setup", "").unwrap().content,
+            "```rust\nlet a = 1;\nlet b = 2;\n```"
+        );
+        assert_eq!(
+            include(&root, "src/main.rs#inner", "").unwrap().content,
+            "```rust\nlet b = 2;\n```"
+        );
+    }
+
+    #[test]
+    fn reports_problems() {
+        let root = project();
+
+        assert_eq!(
+            include(&root, "src/missing.rs", ""),
+            Err("Could not read `src/missing.rs`".to_string())
+        );
+        assert_eq!(
+            include(&root, "src/main.rs#nope", ""),
+            Err("Could not find region `nope` in `src/main.rs`".to_string())
+        );
+        assert_eq!(
+            include(&root, "src/main.rs#L9-L20", ""),
+            Err("Lines 9-20 are out of range for `src/main.rs`, which has 10 lines".to_string())
+        );
+        assert_eq!(
+            include(&root, "../secrets.txt", ""),
+            Err("`../secrets.txt` must be inside the project root".to_string())
+        );
+    }
+
+    #[test]
+    fn dedents_by_spaces_and_tabs_only() {
+        assert_eq!(dedent(&["    a", "", "      b"]), vec!["a", "", "  b"]);
+        assert_eq!(dedent(&["\ta", "\t\tb"]), vec!["a", "\tb"]);
+        assert_eq!(
+            dedent(&[" x();", "\u{a0}y();"]),
+            vec![" x();", "\u{a0}y();"]
+        );
+    }
+
+    #[test]
+    fn fences_are_longer_than_backticks_in_the_code() {
+        assert_eq!(longest_backtick_run(&["let a = ```;", "`b`"]), 3);
+        assert_eq!(language(Path::new("a/b.yml")), "yaml");
+        assert_eq!(language(Path::new("Makefile")), "");
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -6,7 +7,9 @@ use colorsys::Rgb;
 use http::Uri;
 use serde::Deserialize;
 
+use crate::callouts::{self, Callouts};
 use crate::navigation::Link;
+use crate::renderers::Renderers;
 use crate::site::BuildMode;
 use crate::{Error, Result};
 
@@ -19,6 +22,23 @@ struct DoctaveYaml {
     navigation: Option<Vec<Navigation>>,
     base_path: Option<String>,
     docs_dir: Option<String>,
+    site_url: Option<String>,
+    description: Option<String>,
+    image: Option<String>,
+    feeds: Option<Vec<FeedYaml>>,
+    repository: Option<RepositoryYaml>,
+    variables: Option<BTreeMap<String, String>>,
+    extensions: Option<Vec<String>>,
+    ignore: Option<Vec<String>>,
+    gitignore: Option<bool>,
+    redirects: Option<BTreeMap<String, String>>,
+    pretty_urls: Option<bool>,
+    relative_links: Option<bool>,
+    toc_depth: Option<u32>,
+    callouts: Option<BTreeMap<String, CalloutYaml>>,
+    syntax_highlighting: Option<String>,
+    renderers: Option<RenderersYaml>,
+    glossary: Option<GlossaryYaml>,
 }
 
 impl DoctaveYaml {
@@ -64,6 +84,20 @@ impl DoctaveYaml {
             }
         }
 
+        // Validate social preview image exists, unless it is hosted elsewhere
+        if let Some(image) = &self.image {
+            let location = docs_dir_path
+                .join("_include")
+                .join(image.trim_start_matches('/'));
+            if !image.contains("://") && !location.exists() {
+                return Err(Error::new(format!(
+                    "Could not find image specified in doctave.yaml at {}.\n\
+                     The image path should be relative to the _include directory, or a full URL.",
+                    location.display()
+                )));
+            }
+        }
+
         // Validate navigation paths exist
         // Validate navigation wildcards recursively
         fn validate_level(
@@ -106,6 +140,126 @@ impl DoctaveYaml {
             }
         }
 
+        // Validate feed directories exist
+        if let Some(feeds) = &self.feeds {
+            if !feeds.is_empty() && self.site_url.is_none() {
+                return Err(Error::new(
+                    "Feeds require a site_url to be set in doctave.yaml, \
+                     since feed readers need full URLs to your pages.",
+                ));
+            }
+
+            for feed in feeds {
+                if !project_root.join(&feed.path).is_dir() {
+                    return Err(Error::new(format!(
+                        "Could not find directory specified in feeds at {}",
+                        feed.path.display()
+                    )));
+                }
+            }
+        }
+
+        // Validate source file extensions
+        if let Some(extensions) = &mut self.extensions {
+            for extension in extensions.iter_mut() {
+                *extension = extension.trim_start_matches('.').to_string();
+
+                if extension.is_empty() {
+                    return Err(Error::new("Found an empty file extension in extensions"));
+                }
+            }
+
+            if extensions.is_empty() {
+                return Err(Error::new(
+                    "extensions in doctave.yaml must list at least one file extension",
+                ));
+            }
+        }
+
+        // Validate redirects
+        if let Some(redirects) = &self.redirects {
+            for from in redirects.keys() {
+                if from.contains("://") {
+                    return Err(Error::new(format!(
+                        "Redirects must be from a path on this site, such as /old-page. Got `{}`.",
+                        from
+                    )));
+                }
+            }
+        }
+
+        // Validate table of contents depth
+        if let Some(depth) = self.toc_depth {
+            if !(1..=6).contains(&depth) {
+                return Err(Error::new(format!(
+                    "toc_depth must be a heading level from 1 to 6. Got `{}`.",
+                    depth
+                )));
+            }
+        }
+
+        // Validate callout types
+        if let Some(callouts) = &self.callouts {
+            for (name, callout) in callouts {
+                let valid_name = !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+                if !valid_name {
+                    return Err(Error::new(format!(
+                        "Callout names may only contain letters, numbers, dashes and \
+                         underscores. Got `{}`.",
+                        name
+                    )));
+                }
+
+                if let Some(style) = &callout.style {
+                    if !callouts::STYLES.contains(&style.as_str()) {
+                        return Err(Error::new(format!(
+                            "Invalid style `{}` for callout `{}`. Expected one of: {}",
+                            style,
+                            name,
+                            callouts::STYLES.join(", ")
+                        )));
+                    }
+                }
+            }
+        }
+
+        // Validate where code is highlighted
+        if let Some(highlighting) = &self.syntax_highlighting {
+            if highlighting != "client" && highlighting != "server" {
+                return Err(Error::new(format!(
+                    "syntax_highlighting must be either `client` or `server`. Got `{}`.",
+                    highlighting
+                )));
+            }
+        }
+
+        // Validate renderer commands
+        if let Some(renderers) = &self.renderers {
+            for (name, command) in &[("math", &renderers.math), ("mermaid", &renderers.mermaid)] {
+                if matches!(command, Some(c) if c.trim().is_empty()) {
+                    return Err(Error::new(format!(
+                        "renderers.{} in doctave.yaml must be a command. Got an empty string.",
+                        name
+                    )));
+                }
+            }
+        }
+
+        // Validate edit URL template
+        if let Some(url) = self.repository.as_ref().and_then(|r| r.edit_url.as_ref()) {
+            if !url.contains("{path}") {
+                return Err(Error::new(format!(
+                    "repository.edit_url must contain a {{path}} placeholder for \
+                     the page's source file. Got `{}`.",
+                    url
+                )));
+            }
+        }
+
         // Validate base path
         if let Some(path) = &mut self.base_path {
             let uri: Uri = path.parse().map_err(|_| {
@@ -127,6 +281,26 @@ impl DoctaveYaml {
             }
         }
 
+        // Validate site URL
+        if let Some(url) = &mut self.site_url {
+            let is_full_url = url
+                .parse::<Uri>()
+                .map(|uri| uri.scheme().is_some() && uri.host().is_some())
+                .unwrap_or(false);
+
+            if !is_full_url {
+                return Err(Error::new(format!(
+                    "site_url must be a full URL, including the scheme. Got `{}`.",
+                    url
+                )));
+            }
+
+            // Pages are appended to the URL along with the base path
+            while url.ends_with('/') {
+                url.pop();
+            }
+        }
+
         Ok(())
     }
 
@@ -153,6 +327,84 @@ pub enum NavChildren {
     List(Vec<Navigation>),
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct FeedYaml {
+    path: PathBuf,
+    title: Option<String>,
+    rss: Option<bool>,
+}
+
+/// A directory whose pages are published as an Atom feed, and optionally an
+/// RSS feed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feed {
+    /// Path to the directory, relative to the project root
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub rss: bool,
+}
+
+impl From<FeedYaml> for Feed {
+    fn from(other: FeedYaml) -> Self {
+        Feed {
+            path: other.path,
+            title: other.title,
+            rss: other.rss.unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RenderersYaml {
+    math: Option<String>,
+    mermaid: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GlossaryYaml {
+    link_terms: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CalloutYaml {
+    title: Option<String>,
+    style: Option<String>,
+    icon: Option<String>,
+}
+
+/// Builds the callout types, with the ones set in `doctave.yaml` replacing the
+/// built in ones. Fields left out keep the built in values.
+fn callouts_from_yaml(custom: BTreeMap<String, CalloutYaml>) -> Callouts {
+    let defaults = Callouts::default();
+
+    Callouts::with_types(
+        custom
+            .into_iter()
+            .map(|(name, yaml)| {
+                let name = name.to_ascii_lowercase();
+                let mut kind = defaults.get(&name);
+
+                if let Some(title) = yaml.title {
+                    kind.title = title;
+                }
+                if let Some(style) = yaml.style {
+                    kind.style = style;
+                }
+                if let Some(icon) = yaml.icon {
+                    kind.icon = icon;
+                }
+
+                (name, kind)
+            })
+            .collect(),
+    )
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RepositoryYaml {
+    edit_url: Option<String>,
+}
+
 static DEFAULT_THEME_COLOR: &str = "#445282";
 
 #[derive(Debug, Clone)]
@@ -239,7 +491,9 @@ impl NavRule {
             NavRule::Dir(_, _) => return false,
         };
 
-        root_dir.join(my_path) == docs_dir.join("README.md")
+        let path = root_dir.join(my_path);
+
+        path.parent() == Some(docs_dir) && crate::is_index_file(&path)
     }
 }
 
@@ -247,6 +501,7 @@ impl NavRule {
 pub struct Config {
     color: bool,
     allow_failed_checks: bool,
+    single_page: bool,
     project_root: PathBuf,
     out_dir: PathBuf,
     docs_dir: PathBuf,
@@ -254,6 +509,23 @@ pub struct Config {
     title: String,
     colors: Colors,
     logo: Option<String>,
+    site_url: Option<String>,
+    description: Option<String>,
+    image: Option<String>,
+    feeds: Vec<Feed>,
+    edit_url: Option<String>,
+    variables: BTreeMap<String, String>,
+    extensions: Vec<String>,
+    ignore: Vec<String>,
+    use_gitignore: bool,
+    redirects: BTreeMap<String, String>,
+    pretty_urls: bool,
+    relative_links: bool,
+    toc_depth: u32,
+    callouts: Callouts,
+    server_side_highlighting: bool,
+    renderers: Renderers,
+    link_glossary_terms: bool,
     navigation: Option<Vec<NavRule>>,
     port: u32,
     build_mode: BuildMode,
@@ -279,6 +551,7 @@ impl Config {
         let config = Config {
             color: true,
             allow_failed_checks: false,
+            single_page: false,
             project_root: project_root.to_path_buf(),
             out_dir: project_root.join("site"),
             docs_dir: doctave_yaml.docs_dir(project_root),
@@ -292,6 +565,37 @@ impl Config {
                 .logo
                 .map(|p| Link::path_to_uri_with_extension(&p))
                 .map(|p| p.as_str().trim_start_matches("/").to_owned()),
+            site_url: doctave_yaml.site_url,
+            description: doctave_yaml.description,
+            image: doctave_yaml.image,
+            feeds: doctave_yaml
+                .feeds
+                .map(|feeds| feeds.into_iter().map(|f| f.into()).collect())
+                .unwrap_or_default(),
+            edit_url: doctave_yaml.repository.and_then(|r| r.edit_url),
+            variables: doctave_yaml.variables.unwrap_or_default(),
+            extensions: doctave_yaml
+                .extensions
+                .unwrap_or_else(|| vec![String::from("md")]),
+            ignore: doctave_yaml.ignore.unwrap_or_default(),
+            use_gitignore: doctave_yaml.gitignore.unwrap_or(false),
+            redirects: doctave_yaml.redirects.unwrap_or_default(),
+            pretty_urls: doctave_yaml.pretty_urls.unwrap_or(false),
+            relative_links: doctave_yaml.relative_links.unwrap_or(false),
+            toc_depth: doctave_yaml.toc_depth.unwrap_or(crate::toc::DEFAULT_DEPTH),
+            callouts: doctave_yaml
+                .callouts
+                .map(callouts_from_yaml)
+                .unwrap_or_default(),
+            server_side_highlighting: doctave_yaml.syntax_highlighting.as_deref() == Some("server"),
+            renderers: doctave_yaml
+                .renderers
+                .map(|r| Renderers::new(r.math, r.mermaid, project_root))
+                .unwrap_or_default(),
+            link_glossary_terms: doctave_yaml
+                .glossary
+                .and_then(|g| g.link_terms)
+                .unwrap_or(false),
             navigation: doctave_yaml.navigation.map(|n| NavRule::from_yaml_input(n)),
             port: doctave_yaml.port.unwrap_or_else(|| 4001),
             build_mode: BuildMode::Dev,
@@ -330,6 +634,11 @@ impl Config {
         self.navigation.as_deref()
     }
 
+    /// Directories to publish as feeds
+    pub fn feeds(&self) -> &[Feed] {
+        &self.feeds
+    }
+
     /// Port to serve the development server on
     pub fn port(&self) -> u32 {
         self.port
@@ -343,6 +652,11 @@ impl Config {
         self.allow_failed_checks
     }
 
+    /// Whether the whole site should also be built into a single page
+    pub fn single_page(&self) -> bool {
+        self.single_page
+    }
+
     pub fn disable_colors(&mut self) {
         self.color = false
     }
@@ -351,6 +665,10 @@ impl Config {
         self.allow_failed_checks = true
     }
 
+    pub fn set_single_page(&mut self) {
+        self.single_page = true
+    }
+
     pub fn build_mode(&self) -> BuildMode {
         self.build_mode
     }
@@ -381,6 +699,102 @@ impl Config {
     pub fn logo(&self) -> Option<&str> {
         self.logo.as_deref()
     }
+
+    /// The URL the site is deployed at, without a trailing slash. Used to
+    /// build canonical and link preview URLs.
+    pub fn site_url(&self) -> Option<&str> {
+        self.site_url.as_deref()
+    }
+
+    /// Default description for pages that don't provide their own
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Default link preview image for pages that don't provide their own
+    pub fn image(&self) -> Option<&str> {
+        self.image.as_deref()
+    }
+
+    /// File extensions of the Markdown files to build pages from
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Gitignore-style patterns for files in the docs directory to skip
+    pub fn ignore(&self) -> &[String] {
+        &self.ignore
+    }
+
+    /// Whether files ignored by the project's `.gitignore` should be skipped
+    pub fn use_gitignore(&self) -> bool {
+        self.use_gitignore
+    }
+
+    /// Old paths on the site, mapped to the paths or URLs they moved to
+    pub fn redirects(&self) -> &BTreeMap<String, String> {
+        &self.redirects
+    }
+
+    /// Whether each page is built into an `index.html` file in a directory
+    /// of its own, instead of an HTML file named after the page
+    pub fn pretty_urls(&self) -> bool {
+        self.pretty_urls
+    }
+
+    /// Whether links in the generated site are relative to the page they are
+    /// on, so the site works from any path or straight from disk
+    pub fn relative_links(&self) -> bool {
+        self.relative_links
+    }
+
+    /// The deepest heading level shown in the outline of each page, and in
+    /// tables of contents inserted with `[[toc]]`
+    pub fn toc_depth(&self) -> u32 {
+        self.toc_depth
+    }
+
+    /// The types of callouts that can be used in pages
+    pub fn callouts(&self) -> &Callouts {
+        &self.callouts
+    }
+
+    /// Whether code blocks are highlighted when the site is built, instead of
+    /// by Prism in the browser
+    pub fn server_side_highlighting(&self) -> bool {
+        self.server_side_highlighting
+    }
+
+    /// The commands that render math notation and diagrams when the site is
+    /// built, instead of in the browser
+    pub fn renderers(&self) -> &Renderers {
+        &self.renderers
+    }
+
+    /// Whether the first occurrence of each glossary term on a page is linked
+    /// to its definition
+    pub fn link_glossary_terms(&self) -> bool {
+        self.link_glossary_terms
+    }
+
+    /// Variables that can be referenced in any page
+    pub fn variables(&self) -> &BTreeMap<String, String> {
+        &self.variables
+    }
+
+    /// The URL where the given source file can be edited, built from the
+    /// `repository.edit_url` template. The path is relative to the project root.
+    pub fn edit_url(&self, source: &Path) -> Option<String> {
+        let path = source
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        self.edit_url
+            .as_ref()
+            .map(|template| template.replace("{path}", &path))
+    }
 }
 
 pub fn project_root() -> Option<PathBuf> {
@@ -492,6 +906,186 @@ mod test {
         assert_eq!(config.base_path(), "/");
     }
 
+    #[test]
+    fn validate_site_url() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+            site_url: example.com/docs
+        "};
+
+        let error = Config::from_yaml_str(Path::new(""), yaml).unwrap_err();
+
+        assert!(
+            format!("{}", error).contains("site_url must be a full URL"),
+            "Error message was: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn validate_toc_depth() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+            toc_depth: 7
+        "};
+
+        let error = Config::from_yaml_str(Path::new(""), yaml).unwrap_err();
+
+        assert!(
+            format!("{}", error).contains("toc_depth must be a heading level from 1 to 6"),
+            "Error message was: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn validate_callout_style() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+            callouts:
+              deprecated:
+                style: purple
+        "};
+
+        let error = Config::from_yaml_str(Path::new(""), yaml).unwrap_err();
+
+        assert!(
+            format!("{}", error).contains("Invalid style `purple` for callout `deprecated`"),
+            "Error message was: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn validate_syntax_highlighting() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+            syntax_highlighting: build
+        "};
+
+        let error = Config::from_yaml_str(Path::new(""), yaml).unwrap_err();
+
+        assert!(
+            format!("{}", error)
+                .contains("syntax_highlighting must be either `client` or `server`. Got `build`."),
+            "Error message was: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn validate_site_url_strips_trailing_slash() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+            site_url: https://example.com/
+        "};
+
+        let config = Config::from_yaml_str(Path::new(""), yaml).unwrap();
+
+        assert_eq!(config.site_url(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn validate_feeds_require_site_url() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+            feeds:
+              - path: docs/changelog
+        "};
+
+        let error = Config::from_yaml_str(Path::new(""), yaml).unwrap_err();
+
+        assert!(
+            format!("{}", error).contains("Feeds require a site_url to be set in doctave.yaml"),
+            "Error message was: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn validate_edit_url_requires_path_placeholder() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+            repository:
+              edit_url: https://github.com/example/project/edit/main
+        "};
+
+        let error = Config::from_yaml_str(Path::new(""), yaml).unwrap_err();
+
+        assert!(
+            format!("{}", error).contains("must contain a {path} placeholder"),
+            "Error message was: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn edit_url_substitutes_source_path() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+            repository:
+              edit_url: https://github.com/example/project/edit/main/{path}
+        "};
+
+        let config = Config::from_yaml_str(Path::new(""), yaml).unwrap();
+
+        assert_eq!(
+            config
+                .edit_url(Path::new("docs/guides/setup.md"))
+                .as_deref(),
+            Some("https://github.com/example/project/edit/main/docs/guides/setup.md")
+        );
+    }
+
+    #[test]
+    fn variables_accept_numbers() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+            variables:
+              product: Doctave
+              version: 0.4
+        "};
+
+        let config = Config::from_yaml_str(Path::new(""), yaml).unwrap();
+
+        assert_eq!(config.variables()["product"], "Doctave");
+        assert_eq!(config.variables()["version"], "0.4");
+    }
+
+    #[test]
+    fn extensions_strip_leading_dots() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+            extensions: [md, .markdown]
+        "};
+
+        let config = Config::from_yaml_str(Path::new(""), yaml).unwrap();
+
+        assert_eq!(config.extensions(), &["md", "markdown"]);
+    }
+
+    #[test]
+    fn default_extensions() {
+        let yaml = indoc! {"
+            ---
+            title: The Title
+        "};
+
+        let config = Config::from_yaml_str(Path::new(""), yaml).unwrap();
+
+        assert_eq!(config.extensions(), &["md"]);
+    }
+
     #[test]
     fn validate_navigation_wildcard() {
         let yaml = indoc! {"
@@ -0,0 +1,94 @@
+//! The Markdown syntax that extensions such as callouts and tabs are built
+//! on: `:::` containers, and the code blocks they must not look inside.
+
+/// A container found in the Markdown, such as `:::tip Title`
+pub struct Opening<'a> {
+    pub name: String,
+    pub title: Option<&'a str>,
+}
+
+/// Matches `:::name Optional title`, returning the number of colons
+pub fn container_opening(line: &str) -> Option<(usize, Opening<'_>)> {
+    if is_indented_code(line) {
+        return None;
+    }
+
+    let line = line.trim();
+    let colons = line.chars().take_while(|c| *c == ':').count();
+    if colons < 3 {
+        return None;
+    }
+
+    let rest = line[colons..].trim_start();
+    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+
+    name(&rest[..end]).map(|name| {
+        (
+            colons,
+            Opening {
+                name,
+                title: non_empty(rest[end..].trim()),
+            },
+        )
+    })
+}
+
+/// Matches a line of only colons, returning how many there are
+pub fn container_closing(line: &str) -> Option<usize> {
+    let line = line.trim();
+
+    if line.len() >= 3 && line.chars().all(|c| c == ':') {
+        Some(line.len())
+    } else {
+        None
+    }
+}
+
+/// Container names are made up of letters, numbers, dashes and underscores,
+/// and are case insensitive.
+pub fn name(name: &str) -> Option<String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Some(name.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Lines indented by four or more spaces are code blocks in Markdown
+pub fn is_indented_code(line: &str) -> bool {
+    line.starts_with("    ") || line.starts_with('\t')
+}
+
+fn non_empty(text: &str) -> Option<&str> {
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Matches the start of a fenced code block, returning its fence character
+/// and length
+pub fn opens_fence(line: &str) -> Option<(char, usize)> {
+    let line = line.trim_start();
+    let c = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = line.chars().take_while(|x| *x == c).count();
+
+    if length >= 3 {
+        Some((c, length))
+    } else {
+        None
+    }
+}
+
+/// Matches the end of a fenced code block opened with the given fence
+pub fn closes_fence(line: &str, (c, length): (char, usize)) -> bool {
+    let line = line.trim();
+
+    line.len() >= length && line.chars().all(|x| x == c)
+}
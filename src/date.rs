@@ -0,0 +1,217 @@
+//! Minimal date handling for the few places that need it, such as feeds and
+//! last-updated timestamps.
+//!
+//! Only supports reading the date formats we expect people to write in
+//! their frontmatter, and printing the standard formats feeds require.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+static MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// A point in time in UTC, with second precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        Self::from(SystemTime::now())
+    }
+
+    pub fn from_unix(seconds: i64) -> Self {
+        Timestamp(seconds)
+    }
+
+    /// Parses dates such as `2021-01-31`, `2021-01-31 15:30`, or a full
+    /// RFC 3339 timestamp like `2021-01-31T15:30:00+02:00`.
+    ///
+    /// Dates without an offset are assumed to be in UTC.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let date = input.get(..10)?;
+        let rest = input.get(10..)?;
+
+        let mut parts = date.split('-');
+        let year = parts.next()?.parse::<i64>().ok()?;
+        let month = parts.next()?.parse::<i64>().ok()?;
+        let day = parts.next()?.parse::<i64>().ok()?;
+
+        if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        let rest = rest.trim_start_matches(&['T', 't', ' '][..]);
+        let (time, offset) = match rest.find(&['Z', 'z', '+', '-'][..]) {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+
+        let seconds_in_day = if time.is_empty() {
+            0
+        } else {
+            let mut parts = time.split(':');
+            let hours = parts.next()?.parse::<i64>().ok()?;
+            let minutes = parts.next()?.parse::<i64>().ok()?;
+            let seconds = match parts.next() {
+                // Ignore fractions of a second
+                Some(s) => s.split('.').next()?.parse::<i64>().ok()?,
+                None => 0,
+            };
+
+            if parts.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+                return None;
+            }
+
+            hours * 3600 + minutes * 60 + seconds
+        };
+
+        let offset_seconds = match offset {
+            "" | "Z" | "z" => 0,
+            _ => {
+                let sign = if offset.starts_with('-') { -1 } else { 1 };
+                let digits = offset[1..].replace(':', "");
+                if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                let hours = digits[..2].parse::<i64>().ok()?;
+                let minutes = digits[2..].parse::<i64>().ok()?;
+
+                sign * (hours * 3600 + minutes * 60)
+            }
+        };
+
+        Some(Timestamp(
+            days_from_civil(year, month, day) * 86400 + seconds_in_day - offset_seconds,
+        ))
+    }
+
+    /// Formats the timestamp as RFC 3339, e.g. `2021-01-31T15:30:00Z`, as
+    /// used by Atom feeds.
+    pub fn to_rfc3339(self) -> String {
+        let (year, month, day) = civil_from_days(self.days());
+        let (hours, minutes, seconds) = self.time_of_day();
+
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year, month, day, hours, minutes, seconds
+        )
+    }
+
+    /// Formats the timestamp as RFC 2822, e.g. `Sun, 31 Jan 2021 15:30:00 +0000`,
+    /// as used by RSS feeds.
+    pub fn to_rfc2822(self) -> String {
+        let (year, month, day) = civil_from_days(self.days());
+        let (hours, minutes, seconds) = self.time_of_day();
+        let weekday = (self.days() + 4).rem_euclid(7) as usize;
+
+        format!(
+            "{}, {:02} {} {:04} {:02}:{:02}:{:02} +0000",
+            WEEKDAYS[weekday],
+            day,
+            MONTHS[month as usize - 1],
+            year,
+            hours,
+            minutes,
+            seconds
+        )
+    }
+
+    /// Formats just the calendar date, e.g. `2021-01-31`, for showing to readers.
+    pub fn to_date_string(self) -> String {
+        let (year, month, day) = civil_from_days(self.days());
+
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    fn days(self) -> i64 {
+        self.0.div_euclid(86400)
+    }
+
+    fn time_of_day(self) -> (i64, i64, i64) {
+        let seconds = self.0.rem_euclid(86400);
+
+        (seconds / 3600, seconds % 3600 / 60, seconds % 60)
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => Timestamp(duration.as_secs() as i64),
+            Err(e) => Timestamp(-(e.duration().as_secs() as i64)),
+        }
+    }
+}
+
+/// Days since the Unix epoch for the given date in the proleptic Gregorian
+/// calendar. See http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+/// The inverse of `days_from_civil`
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_part = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_part + 2) / 5 + 1;
+    let month = if month_part < 10 {
+        month_part + 3
+    } else {
+        month_part - 9
+    };
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_plain_dates() {
+        let timestamp = Timestamp::parse("2022-04-02").unwrap();
+
+        assert_eq!(timestamp, Timestamp(1648857600));
+        assert_eq!(timestamp.to_rfc3339(), "2022-04-02T00:00:00Z");
+    }
+
+    #[test]
+    fn parses_timestamps_with_offsets() {
+        let timestamp = Timestamp::parse("2021-01-31T15:30:10+02:00").unwrap();
+
+        assert_eq!(timestamp.to_rfc3339(), "2021-01-31T13:30:10Z");
+        assert_eq!(
+            Timestamp::parse("2021-01-31 15:30").unwrap().to_rfc3339(),
+            "2021-01-31T15:30:00Z"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert_eq!(Timestamp::parse("yesterday"), None);
+        assert_eq!(Timestamp::parse("2021-13-01"), None);
+        assert_eq!(Timestamp::parse("2021-01-01T25:00"), None);
+    }
+
+    #[test]
+    fn formats_rfc2822() {
+        assert_eq!(
+            Timestamp::parse("2022-04-02").unwrap().to_rfc2822(),
+            "Sat, 02 Apr 2022 00:00:00 +0000"
+        );
+    }
+}
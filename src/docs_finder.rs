@@ -1,8 +1,14 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::OsStr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::config::Config;
+use crate::date::Timestamp;
+use crate::git;
+use crate::glossary;
+use crate::ignore::IgnoreRules;
+use crate::site_generator::NOT_FOUND_FILE;
+use crate::snippets::{Snippets, SNIPPETS_DIR};
 use crate::{Directory, Document};
 
 use walkdir::WalkDir;
@@ -10,18 +16,36 @@ use walkdir::WalkDir;
 /// Loads the current state of the documentation from disk, returning the root
 /// directory which contains all files and nested directories.
 pub fn find(config: &Config) -> Directory {
-    let mut root_dir = walk_dir(config.docs_dir(), config).unwrap_or(Directory {
-        path: config.docs_dir().to_path_buf(),
-        docs: vec![],
-        dirs: vec![],
-    });
+    find_with_history(config, &git::last_commit_times(config.docs_dir()))
+}
+
+/// Like `find`, but with the last commit times of the docs already read from
+/// git, so that repeated loads don't have to read the history again.
+pub fn find_with_history(config: &Config, commit_times: &HashMap<PathBuf, Timestamp>) -> Directory {
+    let snippets = Snippets::load(config);
+    let ignore = IgnoreRules::for_config(config);
 
+    let mut root_dir = walk_dir(config.docs_dir(), config, commit_times, &snippets, &ignore)
+        .unwrap_or(Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![],
+            dirs: vec![],
+        });
+
+    glossary::add_page(&mut root_dir, config);
     generate_missing_indices(&mut root_dir, config);
+    glossary::link_terms(&mut root_dir, config);
 
     root_dir
 }
 
-fn walk_dir<P: AsRef<Path>>(dir: P, config: &Config) -> Option<Directory> {
+fn walk_dir<P: AsRef<Path>>(
+    dir: P,
+    config: &Config,
+    commit_times: &HashMap<PathBuf, Timestamp>,
+    snippets: &Snippets,
+    ignore: &IgnoreRules,
+) -> Option<Directory> {
     let mut docs = vec![];
     let mut dirs = vec![];
 
@@ -32,18 +56,42 @@ fn walk_dir<P: AsRef<Path>>(dir: P, config: &Config) -> Option<Directory> {
         .into_iter()
         .filter_map(|e| e.ok())
     {
-        if entry.file_type().is_file() && entry.path().extension() == Some(OsStr::new("md")) {
+        if entry.depth() > 0 && ignore.is_ignored(entry.path(), entry.file_type().is_dir()) {
+            continue;
+        }
+
+        // The 404 page is built separately, outside of the navigation
+        if entry.path() == config.docs_dir().join(NOT_FOUND_FILE) {
+            continue;
+        }
+
+        if entry.file_type().is_file() && is_source_file(entry.path(), config) {
             let path = entry.path().strip_prefix(config.docs_dir()).unwrap();
 
-            docs.push(Document::load(entry.path(), path, config.base_path()));
+            let mut doc = Document::load(
+                entry.path(),
+                path,
+                config.base_path(),
+                snippets,
+                config.variables(),
+            );
+
+            // Prefer the git history, since checkouts reset file modification times
+            if let Some(time) = commit_times.get(path) {
+                doc.modified = Some(*time);
+            }
+            doc.apply_config(config);
+
+            docs.push(doc);
         } else {
             let path = entry.into_path();
 
-            if path.as_path() == current_dir {
+            // Snippets are only included into other pages
+            if path.as_path() == current_dir || path == config.docs_dir().join(SNIPPETS_DIR) {
                 continue;
             }
 
-            if let Some(dir) = walk_dir(path, config) {
+            if let Some(dir) = walk_dir(path, config, commit_times, snippets, ignore) {
                 dirs.push(dir);
             }
         }
@@ -60,13 +108,16 @@ fn walk_dir<P: AsRef<Path>>(dir: P, config: &Config) -> Option<Directory> {
     }
 }
 
+/// Whether the file has one of the extensions configured for Markdown sources
+fn is_source_file(path: &Path, config: &Config) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| config.extensions().iter().any(|e| e == ext))
+        .unwrap_or(false)
+}
+
 fn generate_missing_indices(dir: &mut Directory, config: &Config) {
-    if dir
-        .docs
-        .iter()
-        .find(|d| d.original_file_name() == Some(OsStr::new("README.md")))
-        .is_none()
-    {
+    if dir.docs.iter().find(|d| d.is_index()).is_none() {
         let new_index = generate_missing_index(dir, config);
         dir.docs.push(new_index);
     }
@@ -93,14 +144,14 @@ fn generate_missing_index(dir: &mut Directory, config: &Config) -> Document {
     let tmp = dir.path().join("README.md");
     let path = tmp.strip_prefix(config.docs_dir()).unwrap();
 
-    Document::new(
+    let mut doc = Document::new(
         path,
         format!(
             "# Index of {}\n \
                 \n \
                 This page was generated automatically by Doctave, because the directory \
-                `{}` did not contain an index `README.md` file. You can customize this page by \
-                creating one yourself.\
+                `{}` did not contain an index `README.md` or `index.md` file. You can customize \
+                this page by creating one yourself.\
                 \n\
                 ## Pages\n\
                 \n\
@@ -114,5 +165,11 @@ fn generate_missing_index(dir: &mut Directory, config: &Config) -> Document {
         ),
         frontmatter,
         config.base_path(),
-    )
+        // Generated pages don't include snippets or variables
+        &Snippets::default(),
+        &BTreeMap::new(),
+    );
+    doc.apply_config(config);
+
+    doc
 }
@@ -0,0 +1,430 @@
+//! Packages the documentation into an EPUB 3 book, so that it can be read
+//! offline on e-readers.
+//!
+//! Each page becomes a chapter in navigation order. Links between pages point
+//! to the chapters instead, and images from the `_include` directory are
+//! bundled into the book.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path};
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::date::Timestamp;
+use crate::navigation::Link;
+use crate::zip::ZipWriter;
+use crate::{highlight, html, print};
+use crate::{Directory, Document, Error, Result};
+
+static CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+    <rootfiles>
+        <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml" />
+    </rootfiles>
+</container>
+"#;
+
+static STYLE_CSS: &str = include_str!("../templates/epub/style.css");
+static RENDER_MATH_JS: &str = include_str!("../templates/epub/render-math.js");
+
+/// Where images from the `_include` directory are placed, inside `OEBPS`
+static IMAGES_DIR: &str = "images";
+
+#[derive(Debug, Serialize)]
+struct PackageData<'a> {
+    identifier: String,
+    title: &'a str,
+    modified: String,
+    resources: Vec<Resource>,
+    chapters: Vec<Resource>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Resource {
+    id: String,
+    href: String,
+    media_type: &'static str,
+    properties: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct NavData<'a> {
+    title: &'a str,
+    items: Vec<NavItem>,
+}
+
+#[derive(Debug, Serialize)]
+struct NavItem {
+    title: String,
+    href: Option<String>,
+    children: Vec<NavItem>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChapterData<'a> {
+    title: &'a str,
+    anchor: &'a str,
+    content: String,
+    has_math: bool,
+    /// Whether the math is rendered by a script in the chapter
+    math_in_browser: bool,
+}
+
+/// Builds the EPUB file for the documentation in the given directory.
+pub fn package(config: &Config, root: &Directory, navigation: &[Link]) -> Result<Vec<u8>> {
+    let pages = print::pages(root, navigation);
+    let anchors = print::anchors(&pages);
+
+    let mut zip = ZipWriter::new();
+    // The mimetype has to be the first file in the archive, so that readers
+    // can recognize the format
+    zip.add_file("mimetype", b"application/epub+zip")?;
+    zip.add_file("META-INF/container.xml", CONTAINER_XML.as_bytes())?;
+
+    let mut resources = vec![Resource {
+        id: "style".to_string(),
+        href: "style.css".to_string(),
+        media_type: "text/css",
+        properties: None,
+    }];
+    zip.add_file("OEBPS/style.css", STYLE_CSS.as_bytes())?;
+
+    let mut images = BTreeMap::new();
+    let mut chapters = Vec::new();
+
+    for (i, (doc, _)) in pages.iter().enumerate() {
+        let anchor = print::anchor_for(doc);
+        let content = print::rewrite(doc.html(), &anchor, &anchors, chapter_file);
+        let content = config.renderers().render(&content)?;
+        let content = if config.server_side_highlighting() {
+            highlight::highlight(&content)
+        } else {
+            content
+        };
+        let content = html::to_xhtml(&bundle_images(&content, config, doc, &mut images));
+
+        let data = ChapterData {
+            title: if i == 0 { config.title() } else { doc.title() },
+            anchor: &anchor,
+            content,
+            has_math: doc.has_math(),
+            math_in_browser: doc.has_math() && config.renderers().math().is_none(),
+        };
+
+        let mut out = Vec::new();
+        crate::HANDLEBARS
+            .render_to_write("epub_chapter", &data, &mut out)
+            .map_err(|e| Error::handlebars(e, "Could not render EPUB chapter"))?;
+        zip.add_file(&format!("OEBPS/pages/{}", chapter_file(&anchor)), &out)?;
+
+        chapters.push(Resource {
+            id: format!("page-{}", i),
+            href: format!("pages/{}", chapter_file(&anchor)),
+            media_type: "application/xhtml+xml",
+            properties: if doc.has_math() && config.renderers().math().is_none() {
+                Some("scripted")
+            } else {
+                None
+            },
+        });
+    }
+
+    for (i, (path, source)) in images.iter().enumerate() {
+        let content = std::fs::read(source)
+            .map_err(|e| Error::io(e, format!("Could not read image {}", source.display())))?;
+        zip.add_file(&format!("OEBPS/{}/{}", IMAGES_DIR, path), &content)?;
+
+        resources.push(Resource {
+            id: format!("image-{}", i),
+            href: format!("{}/{}", IMAGES_DIR, path),
+            media_type: media_type(path).expect("bundled image without a media type"),
+            properties: None,
+        });
+    }
+
+    if pages.iter().any(|(doc, _)| doc.has_math()) {
+        add_katex(&mut zip, &mut resources)?;
+    }
+
+    let nav = NavData {
+        title: config.title(),
+        items: nav_items(root, navigation, &anchors, config.title()),
+    };
+    let mut out = Vec::new();
+    crate::HANDLEBARS
+        .render_to_write("epub_nav", &nav, &mut out)
+        .map_err(|e| Error::handlebars(e, "Could not render EPUB navigation"))?;
+    zip.add_file("OEBPS/nav.xhtml", &out)?;
+
+    resources.extend(chapters.iter().cloned());
+
+    let package = PackageData {
+        identifier: format!("doctave:{}", config.title()),
+        title: config.title(),
+        modified: Timestamp::now().to_rfc3339(),
+        resources,
+        chapters,
+    };
+    let mut out = Vec::new();
+    crate::HANDLEBARS
+        .render_to_write("epub_package", &package, &mut out)
+        .map_err(|e| Error::handlebars(e, "Could not render EPUB package document"))?;
+    zip.add_file("OEBPS/content.opf", &out)?;
+
+    zip.finish()
+}
+
+/// The file of the chapter with the given anchor, inside `OEBPS/pages`
+fn chapter_file(anchor: &str) -> String {
+    format!("{}.xhtml", anchor)
+}
+
+/// Points images found in the `_include` directory to their copies in the
+/// book, and records which files need to be bundled.
+fn bundle_images(
+    html: &str,
+    config: &Config,
+    doc: &Document,
+    images: &mut BTreeMap<String, std::path::PathBuf>,
+) -> String {
+    let include_dir = config.docs_dir().join("_include");
+    let found = RefCell::new(BTreeMap::new());
+
+    let html = html::rewrite_attributes(html, &["src"], |src| {
+        let path = src
+            .strip_prefix(&doc.base_path)
+            .or_else(|| src.strip_prefix('/'))?;
+        let path = path.split(&['?', '#'][..]).next().unwrap_or(path);
+
+        let is_safe = Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+        let source = include_dir.join(path);
+
+        if !is_safe || media_type(path).is_none() || !source.is_file() {
+            return None;
+        }
+
+        found.borrow_mut().insert(path.to_string(), source);
+        Some(format!("../{}/{}", IMAGES_DIR, path))
+    });
+
+    images.extend(found.into_inner());
+    html
+}
+
+/// The media type of the images that can be bundled into the book
+fn media_type(path: &str) -> Option<&'static str> {
+    let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "svg" => Some("image/svg+xml"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Adds the KaTeX stylesheet, fonts and script, for rendering math notation
+fn add_katex(zip: &mut ZipWriter, resources: &mut Vec<Resource>) -> Result<()> {
+    let css = crate::KATEX_CSS.replace("/assets/katex-fonts/", "katex-fonts/");
+    zip.add_file("OEBPS/katex.css", css.as_bytes())?;
+    zip.add_file("OEBPS/katex.js", crate::KATEX_JS.as_bytes())?;
+    zip.add_file("OEBPS/render-math.js", RENDER_MATH_JS.as_bytes())?;
+
+    resources.push(Resource {
+        id: "katex-css".to_string(),
+        href: "katex.css".to_string(),
+        media_type: "text/css",
+        properties: None,
+    });
+    for (id, href) in &[
+        ("katex-js", "katex.js"),
+        ("render-math-js", "render-math.js"),
+    ] {
+        resources.push(Resource {
+            id: id.to_string(),
+            href: href.to_string(),
+            media_type: "application/javascript",
+            properties: None,
+        });
+    }
+
+    for (i, font) in crate::KATEX_FONTS
+        .entries()
+        .iter()
+        .filter_map(|f| f.as_file())
+        .enumerate()
+    {
+        let name = font.path().file_name().unwrap().to_string_lossy();
+        let media_type = match font.path().extension().and_then(|e| e.to_str()) {
+            Some("woff2") => "font/woff2",
+            Some("woff") => "font/woff",
+            _ => "font/ttf",
+        };
+
+        zip.add_file(&format!("OEBPS/katex-fonts/{}", name), font.contents())?;
+        resources.push(Resource {
+            id: format!("font-{}", i),
+            href: format!("katex-fonts/{}", name),
+            media_type,
+            properties: None,
+        });
+    }
+
+    Ok(())
+}
+
+/// The table of contents, following the sidebar. The root page is listed
+/// first if the sidebar doesn't include it.
+fn nav_items(
+    root: &Directory,
+    navigation: &[Link],
+    anchors: &HashMap<String, String>,
+    root_title: &str,
+) -> Vec<NavItem> {
+    let mut items = to_nav_items(navigation, anchors);
+    let root_href = Some(format!(
+        "pages/{}",
+        chapter_file(&print::anchor_for(root.index()))
+    ));
+
+    if !items.iter().any(|i| i.href == root_href) {
+        items.insert(
+            0,
+            NavItem {
+                title: root_title.to_string(),
+                href: root_href,
+                children: vec![],
+            },
+        );
+    }
+
+    items
+}
+
+fn to_nav_items(links: &[Link], anchors: &HashMap<String, String>) -> Vec<NavItem> {
+    links
+        .iter()
+        .map(|link| NavItem {
+            title: link.title.clone(),
+            href: anchors
+                .get(&print::normalize(&link.path))
+                .map(|anchor| format!("pages/{}", chapter_file(anchor))),
+            children: to_nav_items(&link.children, anchors),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::navigation::Navigation;
+    use crate::snippets::Snippets;
+    use std::path::Path;
+
+    fn page(path: &str, content: &str) -> Document {
+        Document::new(
+            Path::new(path),
+            content.to_string(),
+            BTreeMap::new(),
+            "/",
+            &Snippets::default(),
+            &BTreeMap::new(),
+        )
+    }
+
+    /// The names of the files in the archive, read from the local file headers
+    fn file_names(data: &[u8]) -> Vec<String> {
+        let mut names = vec![];
+        let mut offset = 0;
+
+        while data[offset..].starts_with(b"PK\x03\x04") {
+            let field = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+            let size = u32::from_le_bytes([
+                data[offset + 18],
+                data[offset + 19],
+                data[offset + 20],
+                data[offset + 21],
+            ]) as usize;
+            let name_length = field(offset + 26);
+            let extra_length = field(offset + 28);
+            let name_start = offset + 30;
+
+            names.push(
+                String::from_utf8_lossy(&data[name_start..name_start + name_length]).to_string(),
+            );
+            offset = name_start + name_length + extra_length + size;
+        }
+
+        names
+    }
+
+    #[test]
+    fn packages_pages_in_navigation_order() {
+        let config = Config::from_yaml_str(Path::new("project"), "---\ntitle: Book\n").unwrap();
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![
+                page("README.md", "# Home\n\nSee [B](/b#usage)"),
+                page("b.md", "# B\n\n## Usage"),
+                page("a.md", "# A\n\n```math\nx^2\n```"),
+            ],
+            dirs: vec![],
+        };
+
+        let navigation = Navigation::new(&config).build_for(&root);
+        let data = package(&config, &root, &navigation).unwrap();
+        let names = file_names(&data);
+
+        assert_eq!(&names[..2], &["mimetype", "META-INF/container.xml"]);
+        assert!(names.contains(&"OEBPS/pages/index.xhtml".to_string()));
+        assert!(names.contains(&"OEBPS/katex.css".to_string()));
+        assert_eq!(names.last().unwrap(), "OEBPS/content.opf");
+
+        let content = String::from_utf8_lossy(&data);
+        assert!(content.contains("href=\"b.xhtml#b--usage\""));
+        assert!(content.contains("url(katex-fonts/KaTeX_AMS-Regular.woff2)"));
+
+        let spine = &content[content.find("<spine>").unwrap()..];
+        let order = ["page-0", "page-1", "page-2"]
+            .iter()
+            .map(|id| spine.find(id).unwrap())
+            .collect::<Vec<_>>();
+        assert!(order.windows(2).all(|w| w[0] < w[1]));
+
+        let nav = &content[content.find("epub:type=\"toc\"").unwrap()..];
+        let a = nav.find("pages/a.xhtml").unwrap();
+        let b = nav.find("pages/b.xhtml").unwrap();
+        assert!(nav.find("pages/index.xhtml").unwrap() < a);
+        assert!(a < b);
+    }
+
+    #[test]
+    fn writes_raw_html_as_xhtml() {
+        let config = Config::from_yaml_str(Path::new("project"), "---\ntitle: Book\n").unwrap();
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![page(
+                "README.md",
+                "# Home\n\n<p>One<br>two&nbsp;&mdash; <img src=\"x.png\" alt=\"X\"></p>",
+            )],
+            dirs: vec![],
+        };
+
+        let navigation = Navigation::new(&config).build_for(&root);
+        let data = package(&config, &root, &navigation).unwrap();
+        let content = String::from_utf8_lossy(&data);
+
+        assert!(
+            content.contains("<p>One<br />two&#160;&#8212; <img src=\"x.png\" alt=\"X\" /></p>")
+        );
+        assert!(!content.contains("&nbsp;"));
+    }
+}
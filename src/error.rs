@@ -1,6 +1,9 @@
 use std::fmt;
 use std::path::PathBuf;
 
+use crate::redirects::Redirect;
+use crate::snippets::{BrokenInclude, UndefinedVariable};
+
 #[derive(Debug)]
 pub struct Error {
     kind: ErrorKind,
@@ -42,6 +45,34 @@ impl Error {
             message: "Detected broken internal links".into(),
         }
     }
+
+    pub fn broken_redirects(redirects: Vec<Redirect>) -> Self {
+        Error {
+            kind: ErrorKind::BrokenRedirects(redirects),
+            message: "Detected broken redirects".into(),
+        }
+    }
+
+    pub fn undefined_variables(variables: Vec<(PathBuf, UndefinedVariable)>) -> Self {
+        Error {
+            kind: ErrorKind::UndefinedVariables(variables),
+            message: "Detected undefined variables".into(),
+        }
+    }
+
+    pub fn broken_includes(includes: Vec<(PathBuf, BrokenInclude)>) -> Self {
+        Error {
+            kind: ErrorKind::BrokenIncludes(includes),
+            message: "Detected source files that could not be included".into(),
+        }
+    }
+
+    pub fn undefined_terms(terms: Vec<(PathBuf, String)>) -> Self {
+        Error {
+            kind: ErrorKind::UndefinedTerms(terms),
+            message: "Detected undefined glossary terms".into(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -50,6 +81,10 @@ pub enum ErrorKind {
     Handlebars(handlebars::RenderError),
     Yaml(serde_yaml::Error),
     BrokenLinks(Vec<(PathBuf, doctave_markdown::Link)>),
+    BrokenRedirects(Vec<Redirect>),
+    UndefinedVariables(Vec<(PathBuf, UndefinedVariable)>),
+    BrokenIncludes(Vec<(PathBuf, BrokenInclude)>),
+    UndefinedTerms(Vec<(PathBuf, String)>),
     Generic,
 }
 
@@ -62,6 +97,24 @@ impl fmt::Display for Error {
             ErrorKind::BrokenLinks(links) => {
                 write!(f, "{}.\n{}", self.message, format_broken_links(&links))
             }
+            ErrorKind::BrokenRedirects(redirects) => write!(
+                f,
+                "{}.\n{}",
+                self.message,
+                format_broken_redirects(redirects)
+            ),
+            ErrorKind::UndefinedVariables(variables) => write!(
+                f,
+                "{}.\n{}",
+                self.message,
+                format_undefined_variables(variables)
+            ),
+            ErrorKind::BrokenIncludes(includes) => {
+                write!(f, "{}.\n{}", self.message, format_broken_includes(includes))
+            }
+            ErrorKind::UndefinedTerms(terms) => {
+                write!(f, "{}.\n{}", self.message, format_undefined_terms(terms))
+            }
             ErrorKind::Generic => write!(f, "{}", self.message),
         }
     }
@@ -87,6 +140,74 @@ fn format_broken_links(links: &[(PathBuf, doctave_markdown::Link)]) -> String {
     buf
 }
 
+fn format_broken_redirects(redirects: &[Redirect]) -> String {
+    let mut buf = String::from("The following redirects point to pages that do not exist:\n\n");
+
+    for redirect in redirects {
+        buf.push_str(&format!(
+            "\t{} : {} -> {}\n",
+            redirect.source.display(),
+            redirect.from,
+            redirect.to
+        ));
+    }
+
+    buf
+}
+
+fn format_undefined_variables(variables: &[(PathBuf, UndefinedVariable)]) -> String {
+    let mut buf = String::from(
+        "The following variables are not defined in doctave.yaml or the page's frontmatter:\n\n",
+    );
+
+    for (path, variable) in variables {
+        let location = match &variable.snippet {
+            Some(snippet) => format!("line {} of snippet `{}`", variable.line, snippet),
+            None => format!("line {}", variable.line),
+        };
+
+        buf.push_str(&format!(
+            "\t{} : {} : {{{{ {} }}}}\n",
+            path.display(),
+            location,
+            variable.name
+        ));
+    }
+
+    buf
+}
+
+fn format_broken_includes(includes: &[(PathBuf, BrokenInclude)]) -> String {
+    let mut buf = String::from("The following code includes could not be expanded:\n\n");
+
+    for (path, include) in includes {
+        let location = match &include.snippet {
+            Some(snippet) => format!("line {} of snippet `{}`", include.line, snippet),
+            None => format!("line {}", include.line),
+        };
+
+        buf.push_str(&format!(
+            "\t{} : {} : {} : {}\n",
+            path.display(),
+            location,
+            include.directive,
+            include.message
+        ));
+    }
+
+    buf
+}
+
+fn format_undefined_terms(terms: &[(PathBuf, String)]) -> String {
+    let mut buf = String::from("The following terms are not defined in the glossary:\n\n");
+
+    for (path, term) in terms {
+        buf.push_str(&format!("\t{} : [[{}]]\n", path.display(), term));
+    }
+
+    buf
+}
+
 impl std::error::Error for Error {}
 
 impl From<std::io::Error> for Error {
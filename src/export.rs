@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Instant;
+
+use bunt::termcolor::{ColorChoice, StandardStream};
+
+use crate::config::Config;
+use crate::docs_finder;
+use crate::epub;
+use crate::json;
+use crate::navigation::Navigation;
+use crate::{Directory, Error, Result};
+
+pub struct ExportCommand {}
+
+/// The formats the documentation can be exported into
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Epub,
+    Json,
+}
+
+impl ExportFormat {
+    /// The file the export is written into, if no output path is given
+    fn default_file_name(self) -> &'static str {
+        match self {
+            ExportFormat::Epub => "docs.epub",
+            ExportFormat::Json => "docs.json",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "epub" => Ok(ExportFormat::Epub),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(Error::new(format!(
+                "Unknown export format \"{}\". Expected one of: epub, json",
+                s
+            ))),
+        }
+    }
+}
+
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Where to write the export. Defaults to a file in the project root.
+    pub output: Option<PathBuf>,
+}
+
+impl ExportCommand {
+    pub fn run(options: ExportOptions, config: Config) -> Result<()> {
+        let mut stdout = if config.color_enabled() {
+            StandardStream::stdout(ColorChoice::Auto)
+        } else {
+            StandardStream::stdout(ColorChoice::Never)
+        };
+
+        let output = options.output.clone().unwrap_or_else(|| {
+            config
+                .project_root()
+                .join(options.format.default_file_name())
+        });
+
+        bunt::writeln!(stdout, "{$bold}{$blue}Doctave | Export{/$}{/$}")?;
+        bunt::writeln!(
+            stdout,
+            "Exporting documentation into {$bold}{}{/$}\n",
+            output.display()
+        )?;
+
+        let start = Instant::now();
+
+        let root = docs_finder::find(&config);
+        check_snippets(&root)?;
+        let navigation = Navigation::new(&config).build_for(&root);
+
+        let content = match options.format {
+            ExportFormat::Epub => epub::package(&config, &root, &navigation)?,
+            ExportFormat::Json => json::export(&config, &root, &navigation)?,
+        };
+
+        fs::write(&output, content)
+            .map_err(|e| Error::io(e, format!("Could not write {}", output.display())))?;
+
+        bunt::writeln!(
+            stdout,
+            "Documentation exported in {$bold}{:?}{/$}\n",
+            start.elapsed()
+        )?;
+
+        Ok(())
+    }
+}
+
+fn check_snippets(dir: &Directory) -> Result<()> {
+    for doc in &dir.docs {
+        doc.check_snippets()?;
+    }
+
+    for child in &dir.dirs {
+        check_snippets(child)?;
+    }
+
+    Ok(())
+}
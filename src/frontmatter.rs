@@ -1,16 +1,80 @@
 use std::collections::BTreeMap;
 
+use serde_yaml::Value;
+
+/// Parses the frontmatter at the start of the input.
+///
+/// Values that are not plain strings, numbers or booleans, such as lists and
+/// maps, are kept as YAML that can be read with `nested`.
 pub fn parse(input: &str) -> std::io::Result<BTreeMap<String, String>> {
     let pos = end_pos(input);
 
     if pos > 0 {
-        serde_yaml::from_str(&input[0..pos].trim_end().trim_end_matches('-'))
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+        serde_yaml::from_str::<Option<BTreeMap<String, Value>>>(
+            &input[0..pos].trim_end().trim_end_matches('-'),
+        )
+        .map(|values| {
+            values
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(key, value)| (key, to_string(value)))
+                .collect()
+        })
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
     } else {
         Ok(BTreeMap::new())
     }
 }
 
+/// Reads a frontmatter value that holds a map of values, such as
+/// `variables`. Returns `None` if the value is not a map.
+pub fn nested(value: &str) -> Option<BTreeMap<String, String>> {
+    serde_yaml::from_str::<BTreeMap<String, Value>>(value)
+        .ok()
+        .map(|values| {
+            values
+                .into_iter()
+                .map(|(key, value)| (key, to_string(value)))
+                .collect()
+        })
+}
+
+/// Reads a frontmatter value that holds a list of values, such as `aliases`.
+/// A single value is treated as a list of one.
+pub fn list(value: &str) -> Vec<String> {
+    match serde_yaml::from_str::<Vec<Value>>(value) {
+        Ok(values) => values.into_iter().map(to_string).collect(),
+        Err(_) => vec![value.to_string()],
+    }
+}
+
+/// Reads a frontmatter value back as the list or map it was written as.
+/// Returns `None` for plain values, and for strings that only look like a
+/// list or map, such as a `"[draft]"` title.
+pub fn structured(value: &str) -> Option<Value> {
+    let parsed = serde_yaml::from_str::<Value>(value).ok()?;
+
+    match parsed {
+        Value::Sequence(_) | Value::Mapping(_) if to_string(parsed.clone()) == value => {
+            Some(parsed)
+        }
+        _ => None,
+    }
+}
+
+fn to_string(value: Value) -> String {
+    match value {
+        Value::String(s) => s,
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => String::new(),
+        other => serde_yaml::to_string(&other)
+            .unwrap_or_default()
+            .trim_start_matches("---\n")
+            .to_string(),
+    }
+}
+
 pub fn end_pos(input: &str) -> usize {
     if input.starts_with("---\n") {
         let after_starter_mark = &input[4..];
@@ -61,6 +125,48 @@ mod test {
         assert_eq!(actual, expected.as_ref());
     }
 
+    #[test]
+    fn nested_values() {
+        let input = indoc! {"
+            ---
+            version: 1.2
+            draft: true
+            variables:
+              product: Doctave
+              port: 4001
+            ---
+
+            # Some content
+        "};
+
+        let values = parse(input).unwrap();
+
+        assert_eq!(values["version"], "1.2");
+        assert_eq!(values["draft"], "true");
+
+        let variables = nested(&values["variables"]).unwrap();
+
+        assert_eq!(variables["product"], "Doctave");
+        assert_eq!(variables["port"], "4001");
+    }
+
+    #[test]
+    fn list_values() {
+        let input = indoc! {"
+            ---
+            aliases:
+              - /old
+              - /older
+            alias: /single
+            ---
+        "};
+
+        let values = parse(input).unwrap();
+
+        assert_eq!(list(&values["aliases"]), vec!["/old", "/older"]);
+        assert_eq!(list(&values["alias"]), vec!["/single"]);
+    }
+
     #[test]
     fn missing_frontmatter() {
         let input = indoc! {"
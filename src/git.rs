@@ -0,0 +1,78 @@
+//! Reads page history from the git repository the docs live in, if any.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::date::Timestamp;
+
+/// The time of the last commit that touched each file under `dir`, keyed by
+/// the path relative to `dir`.
+///
+/// Returns an empty map if `dir` is not inside a git repository, or if git
+/// is not installed.
+pub fn last_commit_times(dir: &Path) -> HashMap<PathBuf, Timestamp> {
+    let output = Command::new("git")
+        .args([
+            "-c",
+            "core.quotepath=off",
+            "log",
+            "--format=%x00%ct",
+            "--name-only",
+            "--relative",
+            "--",
+            ".",
+        ])
+        .current_dir(dir)
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_log(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => HashMap::new(),
+    }
+}
+
+/// Parses the output of `git log --format=%x00%ct --name-only`. Commits are
+/// listed newest first, so the first time we see a file is its last change.
+fn parse_log(log: &str) -> HashMap<PathBuf, Timestamp> {
+    let mut times = HashMap::new();
+    let mut current = None;
+
+    for line in log.lines() {
+        if let Some(seconds) = line.strip_prefix('\0') {
+            current = seconds.trim().parse::<i64>().ok().map(Timestamp::from_unix);
+        } else if !line.is_empty() {
+            if let Some(time) = current {
+                times.entry(PathBuf::from(line)).or_insert(time);
+            }
+        }
+    }
+
+    times
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keeps_the_latest_commit_per_file() {
+        let log =
+            "\u{0}1648857600\n\nREADME.md\nguides/setup.md\n\u{0}1600000000\n\nREADME.md\nold.md\n";
+
+        let times = parse_log(log);
+
+        assert_eq!(times.len(), 3);
+        assert_eq!(
+            times[Path::new("README.md")],
+            Timestamp::from_unix(1648857600)
+        );
+        assert_eq!(
+            times[Path::new("guides/setup.md")],
+            Timestamp::from_unix(1648857600)
+        );
+        assert_eq!(times[Path::new("old.md")], Timestamp::from_unix(1600000000));
+    }
+}
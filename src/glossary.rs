@@ -0,0 +1,540 @@
+//! A glossary of the terms used in the docs.
+//!
+//! Terms are defined in a `glossary.yaml` file at the root of the docs
+//! directory, which maps each term to its definition in Markdown:
+//!
+//! ```yaml
+//! Workspace: A directory that holds the projects of one team.
+//! Runner: The machine a job is run on.
+//! ```
+//!
+//! A glossary page is built from the file. Alternatively, the glossary can be
+//! written as a `glossary.md` page at the root of the docs directory, where
+//! each level 2 heading is a term, followed by its definition.
+//!
+//! `[[Term]]` in a page links to the definition of the term. With
+//! `link_terms` turned on, the first occurrence of each term on every page is
+//! linked to its definition as well.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::html;
+use crate::snippets::Snippets;
+use crate::{Directory, Document};
+use crate::{Error, Result};
+
+/// The file the terms are defined in, inside the docs directory
+pub static GLOSSARY_FILE: &str = "glossary.yaml";
+
+/// The name of the glossary page, without an extension
+static GLOSSARY_PAGE: &str = "glossary";
+
+/// Elements whose text is never linked, neither by references nor
+/// automatically
+static VERBATIM_ELEMENTS: &[&str] = &["code", "pre", "script", "style", "svg"];
+
+/// Elements whose text is only linked by references
+static UNLINKED_ELEMENTS: &[&str] = &["a", "button", "h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// Reserved for tables of contents
+static TOC_MARKER: &str = "toc";
+
+/// The longest definition shown when hovering over a term
+static MAX_DEFINITION_LENGTH: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub name: String,
+    /// The anchor of the term's heading on the glossary page
+    pub anchor: String,
+    /// The first paragraph of the definition, as plain text
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Glossary {
+    /// The URI path to the glossary page
+    url: String,
+    /// Longest first, so that terms are preferred over the shorter terms they
+    /// contain
+    terms: Vec<Term>,
+}
+
+impl Glossary {
+    /// Collects the terms from the glossary page at the root of the docs, if
+    /// there is one.
+    pub fn for_root(root: &Directory) -> Option<Glossary> {
+        let page = root
+            .docs
+            .iter()
+            .find(|d| is_glossary_page(d.original_path()))?;
+        let html = page.html();
+
+        let mut terms = page
+            .headings()
+            .iter()
+            .filter(|h| h.level == 2)
+            .map(|heading| {
+                let start = html
+                    .find(&format!("id=\"{}\"", heading.anchor))
+                    .unwrap_or(html.len());
+                let section = &html[start..];
+                let end = section[1..]
+                    .find("<h1")
+                    .into_iter()
+                    .chain(section[1..].find("<h2"))
+                    .min()
+                    .map(|end| end + 1)
+                    .unwrap_or(section.len());
+
+                Term {
+                    name: heading.title.clone(),
+                    anchor: heading.anchor.clone(),
+                    definition: html::first_paragraph(&section[..end])
+                        .map(|d| html::truncate(&d, MAX_DEFINITION_LENGTH))
+                        .unwrap_or_default(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        terms.sort_by_key(|t| std::cmp::Reverse(t.name.len()));
+
+        Some(Glossary {
+            url: page.uri_path(),
+            terms,
+        })
+    }
+
+    /// Finds the term with the given name, ignoring case and allowing a
+    /// plural "s".
+    pub fn find(&self, name: &str) -> Option<&Term> {
+        let name = name.trim().to_lowercase();
+
+        self.terms.iter().find(|t| {
+            let term = t.name.to_lowercase();
+            term == name || format!("{}s", term) == name
+        })
+    }
+
+    /// Replaces the `[[Term]]` references in the rendered HTML of a page with
+    /// links to the definitions of the terms. When `link_first` is set, the
+    /// first occurrence of each term that is not already referenced is linked
+    /// too. Text in code and headings is left alone.
+    ///
+    /// Returns the new HTML, and the references to terms that are not
+    /// defined.
+    pub fn link(&self, html: &str, link_first: bool) -> (String, Vec<String>) {
+        let mut out = String::with_capacity(html.len());
+        let mut undefined = Vec::new();
+        let mut linked = HashSet::new();
+        let mut open: Vec<String> = Vec::new();
+        let mut rest = html;
+
+        while !rest.is_empty() {
+            if rest.starts_with('<') {
+                let end = rest.find('>').map(|e| e + 1).unwrap_or(rest.len());
+                track_element(&rest[..end], &mut open);
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+
+            let end = rest.find('<').unwrap_or(rest.len());
+            let mut text = &rest[..end];
+            rest = &rest[end..];
+
+            if open.iter().any(|e| VERBATIM_ELEMENTS.contains(&e.as_str())) {
+                out.push_str(text);
+                continue;
+            }
+            let link_first = link_first && open.is_empty();
+
+            while let Some((start, end, name)) = next_reference(text) {
+                self.link_occurrences(&text[..start], link_first, &mut linked, &mut out);
+
+                match self.find(&html::unescape(name)) {
+                    Some(term) => {
+                        linked.insert(term.name.as_str());
+                        out.push_str(&self.term_link(term, name));
+                    }
+                    None => {
+                        undefined.push(html::unescape(name).trim().to_string());
+                        out.push_str(&text[start..end]);
+                    }
+                }
+
+                text = &text[end..];
+            }
+            self.link_occurrences(text, link_first, &mut linked, &mut out);
+        }
+
+        (out, undefined)
+    }
+
+    /// Links the first occurrence of each term in the text that has not been
+    /// linked yet, if `enabled`.
+    fn link_occurrences<'a>(
+        &'a self,
+        mut text: &str,
+        enabled: bool,
+        linked: &mut HashSet<&'a str>,
+        out: &mut String,
+    ) {
+        if !enabled {
+            out.push_str(text);
+            return;
+        }
+
+        loop {
+            // The earliest match, preferring longer terms at the same position
+            let next = self
+                .terms
+                .iter()
+                .filter(|t| !linked.contains(t.name.as_str()))
+                .filter_map(|t| find_word(text, &html::escape(&t.name)).map(|m| (m, t)))
+                .min_by_key(|((start, _), _)| *start);
+
+            match next {
+                Some(((start, end), term)) => {
+                    linked.insert(term.name.as_str());
+                    out.push_str(&text[..start]);
+                    out.push_str(&self.term_link(term, &text[start..end]));
+                    text = &text[end..];
+                }
+                None => break,
+            }
+        }
+
+        out.push_str(text);
+    }
+
+    fn term_link(&self, term: &Term, text: &str) -> String {
+        let definition = if term.definition.is_empty() {
+            String::new()
+        } else {
+            format!(" data-definition=\"{}\"", html::escape(&term.definition))
+        };
+
+        format!(
+            "<a class=\"glossary-term\" href=\"{}#{}\"{}>{}</a>",
+            self.url, term.anchor, definition, text
+        )
+    }
+}
+
+/// Whether the path inside the docs directory is the glossary page or the
+/// `glossary.yaml` it is built from.
+fn is_glossary_page(path: &Path) -> bool {
+    path.parent() == Some(Path::new(""))
+        && path.file_stem().and_then(|s| s.to_str()) == Some(GLOSSARY_PAGE)
+}
+
+/// Reads the terms and their definitions from the `glossary.yaml` file, if
+/// there is one.
+pub fn load(config: &Config) -> Result<Option<BTreeMap<String, String>>> {
+    let path = config.docs_dir().join(GLOSSARY_FILE);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let yaml = fs::read_to_string(&path)
+        .map_err(|e| Error::io(e, format!("Could not read {}", path.display())))?;
+
+    serde_yaml::from_str::<Option<BTreeMap<String, String>>>(&yaml)
+        .map(|terms| Some(terms.unwrap_or_default()))
+        .map_err(|e| Error::yaml(e, format!("Could not parse {}", path.display())))
+}
+
+/// Adds the glossary page built from `glossary.yaml` to the root directory.
+/// A file that can't be read is reported by the glossary checks instead.
+pub fn add_page(root: &mut Directory, config: &Config) {
+    let terms = match load(config) {
+        Ok(Some(terms)) => terms,
+        _ => return,
+    };
+
+    let mut frontmatter = BTreeMap::new();
+    frontmatter.insert("title".to_string(), "Glossary".to_string());
+
+    let mut doc = Document::new(
+        Path::new(GLOSSARY_FILE),
+        page_content(&terms),
+        frontmatter,
+        config.base_path(),
+        // Generated pages don't include snippets or variables
+        &Snippets::default(),
+        &BTreeMap::new(),
+    );
+    doc.modified = fs::metadata(config.docs_dir().join(GLOSSARY_FILE))
+        .and_then(|m| m.modified())
+        .ok()
+        .map(crate::date::Timestamp::from);
+    doc.apply_config(config);
+
+    root.docs.push(doc);
+}
+
+/// The Markdown of the glossary page, with a section for each term in
+/// alphabetical order.
+fn page_content(terms: &BTreeMap<String, String>) -> String {
+    let mut names = terms.keys().collect::<Vec<_>>();
+    names.sort_by(|a, b| alphanumeric_sort::compare_str(a.to_lowercase(), b.to_lowercase()));
+
+    let mut content = String::from("# Glossary\n");
+    for name in names {
+        content.push_str(&format!("\n## {}\n\n{}\n", name, terms[name].trim()));
+    }
+
+    content
+}
+
+/// Links the terms of the glossary in every page, and records the references
+/// to terms that are not defined.
+pub fn link_terms(root: &mut Directory, config: &Config) {
+    let glossary = Glossary::for_root(root).unwrap_or_default();
+
+    link_terms_in(root, &glossary, config.link_glossary_terms());
+}
+
+fn link_terms_in(dir: &mut Directory, glossary: &Glossary, link_first: bool) {
+    for doc in &mut dir.docs {
+        // The glossary itself would only link terms to themselves
+        let link_first = link_first && doc.uri_path() != glossary.url;
+        doc.link_terms(glossary, link_first);
+    }
+
+    for child in &mut dir.dirs {
+        link_terms_in(child, glossary, link_first);
+    }
+}
+
+/// Leaves out the brackets around references to terms, for indexing the
+/// page for search.
+pub fn to_plain_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some((start, end, name)) = next_reference(rest) {
+        out.push_str(&rest[..start]);
+        out.push_str(name.trim());
+        rest = &rest[end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Finds the next `[[Term]]` reference in the text, returning where it starts
+/// and ends along with the name of the term.
+fn next_reference(text: &str) -> Option<(usize, usize, &str)> {
+    let mut from = 0;
+
+    while let Some(open) = text[from..].find("[[") {
+        let start = from + open;
+        let name_start = start + "[[".len();
+        let name_end = text[name_start..].find("]]").map(|e| name_start + e)?;
+        let name = &text[name_start..name_end];
+
+        if name.trim().is_empty() || name.contains(&['[', ']', '\n'][..]) {
+            from = name_start;
+            continue;
+        }
+
+        if name.trim() == TOC_MARKER {
+            from = name_end;
+            continue;
+        }
+
+        return Some((start, name_end + "]]".len(), name));
+    }
+
+    None
+}
+
+/// Keeps track of the open elements whose text is not linked, given the next
+/// tag in the HTML.
+fn track_element(tag: &str, open: &mut Vec<String>) {
+    let closing = tag.starts_with("</");
+    let name = tag
+        .trim_start_matches('<')
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    if closing {
+        if open.last() == Some(&name) {
+            open.pop();
+        }
+        return;
+    }
+
+    // Math and diagrams are still to be rendered from their source
+    let is_source = name == "div"
+        && matches!(
+            html::attribute(tag, "class").as_deref(),
+            Some("math") | Some("mermaid")
+        );
+
+    if is_source {
+        // Tracked like code, since their text must stay as it is
+        open.push("code".to_string());
+    } else if (VERBATIM_ELEMENTS.contains(&name.as_str())
+        || UNLINKED_ELEMENTS.contains(&name.as_str())
+        || open.last() == Some(&name))
+        && !tag.ends_with("/>")
+    {
+        open.push(name);
+    }
+}
+
+/// Finds the first occurrence of the word in the text, ignoring case and
+/// allowing a plural "s". Returns where the match starts and ends.
+fn find_word(text: &str, word: &str) -> Option<(usize, usize)> {
+    if word.is_empty() {
+        return None;
+    }
+
+    // ASCII lowercasing keeps the byte offsets the same
+    let haystack = text.to_ascii_lowercase();
+    let word = word.to_ascii_lowercase();
+    let is_word_char = |c: Option<char>| c.map(char::is_alphanumeric).unwrap_or(false);
+
+    let mut from = 0;
+    while let Some(found) = haystack[from..].find(&word) {
+        let start = from + found;
+        let mut end = start + word.len();
+
+        if haystack[end..].starts_with('s') && !is_word_char(haystack[end + 1..].chars().next()) {
+            end += 1;
+        }
+
+        if !is_word_char(haystack[..start].chars().next_back())
+            && !is_word_char(haystack[end..].chars().next())
+        {
+            return Some((start, end));
+        }
+
+        from = start
+            + haystack[start..]
+                .chars()
+                .next()
+                .map(char::len_utf8)
+                .unwrap_or(1);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn glossary() -> Glossary {
+        Glossary {
+            url: "/glossary".to_string(),
+            terms: vec![
+                Term {
+                    name: "API key".to_string(),
+                    anchor: "api-key".to_string(),
+                    definition: "A secret that identifies you.".to_string(),
+                },
+                Term {
+                    name: "Runner".to_string(),
+                    anchor: "runner".to_string(),
+                    definition: "The machine a \"job\" is run on.".to_string(),
+                },
+                Term {
+                    name: "API".to_string(),
+                    anchor: "api".to_string(),
+                    definition: String::new(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn links_references() {
+        let (html, undefined) =
+            glossary().link("<p>Jobs need a [[runner]] and [[Workspace]].</p>", false);
+
+        assert_eq!(
+            html,
+            "<p>Jobs need a <a class=\"glossary-term\" href=\"/glossary#runner\" \
+             data-definition=\"The machine a &quot;job&quot; is run on.\">runner</a> \
+             and [[Workspace]].</p>"
+        );
+        assert_eq!(undefined, vec!["Workspace".to_string()]);
+    }
+
+    #[test]
+    fn references_skip_code_and_tables_of_contents() {
+        let html = "<p>[[toc]]</p>\n<pre><code>[[runner]]</code></pre><p><code>[[nope]]</code></p>";
+        let (linked, undefined) = glossary().link(html, false);
+
+        assert_eq!(linked, html);
+        assert!(undefined.is_empty());
+    }
+
+    #[test]
+    fn links_first_occurrences() {
+        let (html, _) = glossary().link(
+            "<h2 id=\"runners\">Runners</h2>\n\
+             <p>Each API key belongs to a runner. Runners use the API.</p>\n\
+             <p>Another runner, <code>api</code> and <a href=\"/\">API</a>.</p>",
+            true,
+        );
+
+        assert_eq!(
+            html,
+            "<h2 id=\"runners\">Runners</h2>\n\
+             <p>Each <a class=\"glossary-term\" href=\"/glossary#api-key\" \
+             data-definition=\"A secret that identifies you.\">API key</a> belongs to a \
+             <a class=\"glossary-term\" href=\"/glossary#runner\" \
+             data-definition=\"The machine a &quot;job&quot; is run on.\">runner</a>. \
+             Runners use the <a class=\"glossary-term\" href=\"/glossary#api\">API</a>.</p>\n\
+             <p>Another runner, <code>api</code> and <a href=\"/\">API</a>.</p>"
+        );
+    }
+
+    #[test]
+    fn finds_whole_words_and_plurals() {
+        assert_eq!(find_word("Rapid apis", "api"), Some((6, 10)));
+        assert_eq!(find_word("apiary", "api"), None);
+        assert_eq!(find_word("The API.", "api"), Some((4, 7)));
+    }
+
+    #[test]
+    fn finds_terms_ignoring_case() {
+        let glossary = glossary();
+
+        assert_eq!(glossary.find("api KEYS").unwrap().anchor, "api-key");
+        assert_eq!(glossary.find("Runner").unwrap().anchor, "runner");
+        assert!(glossary.find("Run").is_none());
+    }
+
+    #[test]
+    fn page_lists_terms_alphabetically() {
+        let mut terms = BTreeMap::new();
+        terms.insert("runner".to_string(), "The machine.\n".to_string());
+        terms.insert("API".to_string(), "An interface.".to_string());
+
+        assert_eq!(
+            page_content(&terms),
+            "# Glossary\n\n## API\n\nAn interface.\n\n## runner\n\nThe machine.\n"
+        );
+    }
+
+    #[test]
+    fn plain_text_leaves_out_brackets() {
+        assert_eq!(
+            to_plain_text("Use a [[runner]] and [[toc]]"),
+            "Use a runner and [[toc]]"
+        );
+    }
+}
@@ -0,0 +1,867 @@
+//! Syntax highlighting for code blocks at build time, as an alternative to
+//! highlighting them in the browser with Prism.
+//!
+//! Tokens are wrapped in the same `token` classes Prism uses, so the Prism
+//! themes style them just like before. The grammars here are simpler than
+//! Prism's: they know about comments, strings, numbers, keywords, function
+//! names and punctuation, which covers what most code examples need. Code in
+//! languages without a grammar is left for Prism to highlight in the browser.
+
+use crate::html;
+
+/// How the tokens of a language are recognized
+struct Grammar {
+    line_comments: &'static [&'static str],
+    block_comments: &'static [(&'static str, &'static str)],
+    /// String delimiters, with the longer ones first
+    strings: &'static [&'static str],
+    /// Separated by spaces
+    keywords: &'static str,
+    booleans: &'static [&'static str],
+    /// Keywords are matched regardless of case, as in SQL
+    case_insensitive: bool,
+    /// Capitalized words are the names of types
+    class_names: bool,
+    /// Words followed by `!` are macros, as in Rust
+    macros: bool,
+    /// `'a` is a lifetime rather than a character, as in Rust
+    lifetimes: bool,
+    /// `#[...]` are attributes, as in Rust
+    attributes: bool,
+    /// Words starting with `$` are variables, as in shell scripts
+    variables: bool,
+    /// Lines starting with `#` are preprocessor directives, as in C
+    preprocessor: bool,
+    /// Strings followed by a `:` are property names, as in JSON
+    string_properties: bool,
+    /// Words inside braces followed by a `:` are property names, as in CSS.
+    /// Words may also contain dashes.
+    css_properties: bool,
+    /// Keys at the start of a line, followed by the separator, and the
+    /// classes they get
+    keys: Option<(char, &'static str)>,
+}
+
+const PLAIN: Grammar = Grammar {
+    line_comments: &[],
+    block_comments: &[],
+    strings: &["\"", "'"],
+    keywords: "",
+    booleans: &["true", "false"],
+    case_insensitive: false,
+    class_names: false,
+    macros: false,
+    lifetimes: false,
+    attributes: false,
+    variables: false,
+    preprocessor: false,
+    string_properties: false,
+    css_properties: false,
+    keys: None,
+};
+
+static RUST: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &["\""],
+    keywords:
+        "as async await break const continue crate dyn else enum extern fn for if impl in let \
+         loop match mod move mut pub ref return self Self static struct super trait type \
+         union unsafe use where while",
+    class_names: true,
+    macros: true,
+    lifetimes: true,
+    attributes: true,
+    ..PLAIN
+};
+
+static C: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    keywords: "auto break case catch char class const constexpr continue default delete do double \
+         else enum explicit extern float for friend goto if inline int long namespace new \
+         nullptr operator private protected public register return short signed sizeof static \
+         struct switch template this throw try typedef typename union unsigned using virtual \
+         void volatile while NULL",
+    preprocessor: true,
+    ..PLAIN
+};
+
+static CSHARP: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &["@\"", "\"", "'"],
+    keywords: "abstract as async await base bool break byte case catch char class const continue \
+         decimal default delegate do double else enum event explicit extern finally float for \
+         foreach get if implicit in int interface internal is lock long namespace new null \
+         object out override params private protected public readonly record ref return \
+         sealed set short static string struct switch this throw try typeof uint ulong using \
+         var virtual void while",
+    class_names: true,
+    ..PLAIN
+};
+
+static JAVA: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &["\"\"\"", "\"", "'"],
+    keywords: "abstract assert boolean break byte case catch char class const continue default do \
+         double else enum extends final finally float for if implements import instanceof int \
+         interface long native new null package private protected public record return short \
+         static super switch synchronized this throw throws try var void volatile while",
+    class_names: true,
+    ..PLAIN
+};
+
+static KOTLIN: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &["\"\"\"", "\"", "'"],
+    keywords: "abstract as break by catch class companion const continue data do else enum final \
+         finally for fun if import in init interface internal is lateinit null object open \
+         override package private protected public return sealed super suspend this throw try \
+         typealias val var when while",
+    class_names: true,
+    ..PLAIN
+};
+
+static SWIFT: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &["\"\"\"", "\""],
+    keywords:
+        "as associatedtype break case catch class continue default defer deinit do else enum \
+         extension fallthrough fileprivate for func guard if import in init inout internal is \
+         let nil open operator private protocol public repeat rethrows return self Self \
+         static struct subscript super switch throw throws try typealias var where while",
+    class_names: true,
+    ..PLAIN
+};
+
+static GO: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &["\"", "'", "`"],
+    keywords: "break case chan const continue default defer else fallthrough for func go goto if \
+         import interface map nil package range return select struct switch type var",
+    ..PLAIN
+};
+
+static JAVASCRIPT: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &["\"", "'", "`"],
+    keywords: "abstract as async await break case catch class const continue debugger declare \
+         default delete do else enum export extends finally for from function get if \
+         implements import in instanceof interface let new null of private protected public \
+         readonly return set static super switch this throw try type typeof undefined var \
+         void while with yield",
+    class_names: true,
+    ..PLAIN
+};
+
+static JSON: Grammar = Grammar {
+    line_comments: &["//"],
+    block_comments: &[("/*", "*/")],
+    strings: &["\""],
+    keywords: "null",
+    string_properties: true,
+    ..PLAIN
+};
+
+static PYTHON: Grammar = Grammar {
+    line_comments: &["#"],
+    strings: &["\"\"\"", "'''", "\"", "'"],
+    keywords:
+        "and as assert async await break class continue def del elif else except finally for \
+         from global if import in is lambda None nonlocal not or pass raise return try while \
+         with yield",
+    booleans: &["True", "False"],
+    ..PLAIN
+};
+
+static RUBY: Grammar = Grammar {
+    line_comments: &["#"],
+    keywords: "alias and begin break case class def defined do else elsif end ensure for if in \
+         module next nil not or redo require rescue retry return self super then undef unless \
+         until when while yield",
+    class_names: true,
+    ..PLAIN
+};
+
+static PHP: Grammar = Grammar {
+    line_comments: &["//", "#"],
+    block_comments: &[("/*", "*/")],
+    keywords:
+        "abstract array as break case catch class const continue default do echo else elseif \
+         extends final finally fn for foreach function if implements include instanceof \
+         interface match namespace new null private protected public require return static \
+         switch throw trait try use while yield",
+    class_names: true,
+    variables: true,
+    ..PLAIN
+};
+
+static BASH: Grammar = Grammar {
+    line_comments: &["#"],
+    keywords: "case do done elif else esac export fi for function if in local return select then \
+         until while",
+    variables: true,
+    ..PLAIN
+};
+
+static YAML: Grammar = Grammar {
+    line_comments: &["#"],
+    keywords: "null",
+    keys: Some((':', "key atrule")),
+    ..PLAIN
+};
+
+static TOML: Grammar = Grammar {
+    line_comments: &["#"],
+    strings: &["\"\"\"", "'''", "\"", "'"],
+    keys: Some(('=', "key property")),
+    ..PLAIN
+};
+
+static SQL: Grammar = Grammar {
+    line_comments: &["--"],
+    block_comments: &[("/*", "*/")],
+    keywords:
+        "add all alter and as asc begin between by case commit create database default delete \
+         desc distinct drop else end exists foreign from group having in index inner insert \
+         into is join key left like limit not null on or order outer primary references right \
+         rollback select set table then union unique update values view when where with",
+    case_insensitive: true,
+    ..PLAIN
+};
+
+static CSS: Grammar = Grammar {
+    block_comments: &[("/*", "*/")],
+    booleans: &[],
+    css_properties: true,
+    ..PLAIN
+};
+
+fn grammar(language: &str) -> Option<&'static Grammar> {
+    let grammar = match language {
+        "rust" | "rs" => &RUST,
+        "c" | "h" | "cpp" | "c++" | "cc" | "hpp" | "arduino" => &C,
+        "csharp" | "cs" | "dotnet" => &CSHARP,
+        "java" => &JAVA,
+        "kotlin" | "kt" | "kts" => &KOTLIN,
+        "swift" => &SWIFT,
+        "go" => &GO,
+        "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => &JAVASCRIPT,
+        "json" | "json5" | "webmanifest" => &JSON,
+        "python" | "py" => &PYTHON,
+        "ruby" | "rb" => &RUBY,
+        "php" => &PHP,
+        "bash" | "sh" | "shell" | "zsh" => &BASH,
+        "yaml" | "yml" => &YAML,
+        "toml" => &TOML,
+        "sql" => &SQL,
+        "css" | "scss" | "less" => &CSS,
+        _ => return None,
+    };
+
+    Some(grammar)
+}
+
+/// Whether code in the language is highlighted when the site is built
+pub fn supports(language: &str) -> bool {
+    is_markup(language) || grammar(language).is_some()
+}
+
+fn is_markup(language: &str) -> bool {
+    matches!(
+        language,
+        "markup" | "html" | "xml" | "svg" | "mathml" | "ssml" | "atom" | "rss"
+    )
+}
+
+/// Highlights the code blocks in the rendered HTML of a page. Like Prism,
+/// the language class is also added to the `<pre>` around the code. Code
+/// blocks in other languages are left as they are.
+pub fn highlight(html: &str) -> String {
+    static START: &str = "<code class=\"language-";
+    static END: &str = "</code>";
+
+    let mut out = String::with_capacity(html.len() * 2);
+    let mut rest = html;
+
+    while let Some(start) = rest.find(START) {
+        let language_end = match rest[start + START.len()..].find('"') {
+            Some(end) => start + START.len() + end,
+            None => break,
+        };
+        let code_start = match rest[language_end..].find('>') {
+            Some(end) => language_end + end + 1,
+            None => break,
+        };
+        let code_end = match rest[code_start..].find(END) {
+            Some(end) => code_start + end,
+            None => break,
+        };
+
+        let language = &rest[start + START.len()..language_end];
+        if !supports(language) {
+            out.push_str(&rest[..code_end]);
+            rest = &rest[code_end..];
+            continue;
+        }
+
+        let before = &rest[..start];
+
+        match before.rfind("<pre") {
+            Some(pre) if !before[pre..].contains("</pre>") => {
+                let tag_end = pre + before[pre..].find('>').unwrap_or(0);
+                out.push_str(&before[..pre]);
+                out.push_str(&with_class(
+                    &before[pre..tag_end],
+                    &format!("language-{}", language),
+                ));
+                out.push_str(&before[tag_end..]);
+            }
+            _ => out.push_str(before),
+        }
+        out.push_str(&rest[start..code_start]);
+
+        let code = html::unescape(&rest[code_start..code_end]);
+        if is_markup(language) {
+            out.push_str(&markup(&code));
+        } else if let Some(grammar) = grammar(language) {
+            out.push_str(&tokenize(&code, grammar));
+        }
+
+        rest = &rest[code_end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Adds a class to an opening tag, without its closing `>`
+fn with_class(tag: &str, class: &str) -> String {
+    match tag.find(" class=\"") {
+        Some(i) => {
+            let at = i + " class=\"".len();
+            format!("{}{} {}", &tag[..at], class, &tag[at..])
+        }
+        None => format!("{} class=\"{}\"", tag, class),
+    }
+}
+
+fn push_token(out: &mut String, class: &str, text: &str) {
+    out.push_str("<span class=\"token ");
+    out.push_str(class);
+    out.push_str("\">");
+    out.push_str(&html::escape(text));
+    out.push_str("</span>");
+}
+
+fn tokenize(code: &str, grammar: &Grammar) -> String {
+    let mut out = String::with_capacity(code.len() * 2);
+    let mut i = 0;
+    let mut line_start = true;
+    let mut depth = 0usize;
+
+    'outer: while i < code.len() {
+        let rest = &code[i..];
+        let c = rest.chars().next().unwrap();
+
+        if c.is_whitespace() {
+            line_start |= c == '\n';
+            out.push(c);
+            i += c.len_utf8();
+            continue;
+        }
+
+        let at_line_start = line_start;
+        line_start = false;
+        let after_space = i == 0 || code[..i].ends_with(char::is_whitespace);
+
+        if at_line_start && grammar.preprocessor && c == '#' {
+            let end = line_end(rest);
+            push_token(&mut out, "macro property", &rest[..end]);
+            i += end;
+            continue;
+        }
+
+        if let Some((separator, class)) = grammar.keys {
+            if at_line_start {
+                // Keys of items in YAML lists
+                if separator == ':' && rest.starts_with("- ") {
+                    push_token(&mut out, "punctuation", "-");
+                    line_start = true;
+                    i += 1;
+                    continue;
+                }
+
+                if let Some(end) = key(rest, separator) {
+                    push_token(&mut out, class, &rest[..end]);
+                    i += end;
+                    continue;
+                }
+            }
+        }
+
+        for marker in grammar.line_comments {
+            // Shell variables like `$#` and anchors in URLs aren't comments
+            if rest.starts_with(marker) && (*marker != "#" || after_space) {
+                let end = line_end(rest);
+                push_token(&mut out, "comment", &rest[..end]);
+                i += end;
+                continue 'outer;
+            }
+        }
+
+        for (open, close) in grammar.block_comments {
+            if let Some(comment) = rest.strip_prefix(open) {
+                let end = comment
+                    .find(close)
+                    .map_or(rest.len(), |e| open.len() + e + close.len());
+                push_token(&mut out, "comment", &rest[..end]);
+                i += end;
+                continue 'outer;
+            }
+        }
+
+        if grammar.attributes && (rest.starts_with("#[") || rest.starts_with("#![")) {
+            let end = attribute_end(rest);
+            push_token(&mut out, "attribute attr-name", &rest[..end]);
+            i += end;
+            continue;
+        }
+
+        if grammar.lifetimes && c == '\'' {
+            let name = word_end(&rest[1..], false);
+            if name > 0 && !rest[1 + name..].starts_with('\'') {
+                push_token(&mut out, "lifetime-annotation symbol", &rest[..1 + name]);
+                i += 1 + name;
+                continue;
+            }
+            let end = string_end(rest, "'");
+            push_token(&mut out, "char string", &rest[..end]);
+            i += end;
+            continue;
+        }
+
+        for delimiter in grammar.strings {
+            if rest.starts_with(delimiter) {
+                let end = string_end(rest, delimiter);
+                let class = if grammar.string_properties
+                    && rest[end..].trim_start_matches(' ').starts_with(':')
+                {
+                    "property"
+                } else {
+                    "string"
+                };
+                push_token(&mut out, class, &rest[..end]);
+                i += end;
+                continue 'outer;
+            }
+        }
+
+        if grammar.variables && c == '$' {
+            let end = if rest[1..].starts_with('{') {
+                rest.find('}').map_or(1, |e| e + 1)
+            } else {
+                1 + word_end(&rest[1..], false)
+            };
+            if end > 1 {
+                push_token(&mut out, "variable", &rest[..end]);
+                i += end;
+                continue;
+            }
+        }
+
+        if c.is_ascii_digit() {
+            let end = number_end(rest);
+            push_token(&mut out, "number", &rest[..end]);
+            i += end;
+            continue;
+        }
+
+        let word = word_end(rest, grammar.css_properties);
+        if word > 0 {
+            let text = &rest[..word];
+            let after = &rest[word..];
+
+            let (class, end) = if is_keyword(text, grammar) {
+                (Some("keyword"), word)
+            } else if grammar.booleans.contains(&text) {
+                (Some("boolean"), word)
+            } else if grammar.macros && after.starts_with('!') && !after.starts_with("!=") {
+                (Some("macro property"), word + 1)
+            } else if grammar.css_properties
+                && depth > 0
+                && after.trim_start_matches(' ').starts_with(':')
+            {
+                (Some("property"), word)
+            } else if after.starts_with('(') {
+                (Some("function"), word)
+            } else if grammar.class_names && text.starts_with(|c: char| c.is_ascii_uppercase()) {
+                (Some("class-name"), word)
+            } else {
+                (None, word)
+            };
+
+            match class {
+                Some(class) => push_token(&mut out, class, &rest[..end]),
+                None => out.push_str(&html::escape(text)),
+            }
+            i += end;
+            continue;
+        }
+
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+
+        if "{}[]();,.".contains(c) {
+            push_token(&mut out, "punctuation", &rest[..1]);
+            i += 1;
+        } else if "+-*/%=!<>&|^~?:".contains(c) {
+            let end = rest
+                .find(|c: char| !"+-*/%=!<>&|^~?:".contains(c))
+                .unwrap_or(rest.len());
+            push_token(&mut out, "operator", &rest[..end]);
+            i += end;
+        } else {
+            out.push_str(&html::escape(&rest[..c.len_utf8()]));
+            i += c.len_utf8();
+        }
+    }
+
+    out
+}
+
+fn is_keyword(word: &str, grammar: &Grammar) -> bool {
+    let mut keywords = grammar.keywords.split_whitespace();
+
+    if grammar.case_insensitive {
+        keywords.any(|k| k.eq_ignore_ascii_case(word))
+    } else {
+        keywords.any(|k| k == word)
+    }
+}
+
+fn line_end(text: &str) -> usize {
+    text.find('\n').unwrap_or(text.len())
+}
+
+/// The length of the word at the start of the text, if any
+fn word_end(text: &str, dashes: bool) -> usize {
+    if !text.starts_with(|c: char| c.is_alphabetic() || c == '_' || (dashes && c == '-')) {
+        return 0;
+    }
+
+    text.find(|c: char| !(c.is_alphanumeric() || c == '_' || (dashes && c == '-')))
+        .unwrap_or(text.len())
+}
+
+/// Numbers such as `42`, `3.14`, `0xff` and `1_000u32`. A dot is only part of
+/// the number if a digit follows it, so that ranges like `0..10` work.
+fn number_end(text: &str) -> usize {
+    for (i, c) in text.char_indices() {
+        let continues = c.is_ascii_alphanumeric()
+            || c == '_'
+            || (c == '.' && text[i + 1..].starts_with(|c: char| c.is_ascii_digit()));
+
+        if !continues {
+            return i;
+        }
+    }
+
+    text.len()
+}
+
+/// The length of the string at the start of the text, including its
+/// delimiters. Strings with a single character delimiter end at the end of
+/// the line if they are not closed, except for template strings.
+fn string_end(text: &str, delimiter: &str) -> usize {
+    let closing = delimiter.trim_start_matches('@');
+    let multiline = closing.len() > 1 || closing == "`";
+    let mut chars = text.char_indices().skip(delimiter.chars().count());
+
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if text[i..].starts_with(closing) {
+            return i + closing.len();
+        } else if c == '\n' && !multiline {
+            return i;
+        }
+    }
+
+    text.len()
+}
+
+/// The length of a `#[...]` attribute, which may contain brackets itself
+fn attribute_end(text: &str) -> usize {
+    let mut depth = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            '\n' => return i,
+            _ => {}
+        }
+    }
+
+    text.len()
+}
+
+/// The length of the key at the start of a line, such as `title` in
+/// `title: Doctave`, if the line starts with one
+fn key(line: &str, separator: char) -> Option<usize> {
+    let line = &line[..line_end(line)];
+
+    let end = if line.starts_with(&['"', '\''][..]) {
+        string_end(line, &line[..1])
+    } else {
+        line.find(&[separator, '#'][..])?
+    };
+    let end = line[..end].trim_end().len();
+
+    // YAML keys are followed by a space, unlike `:` in URLs
+    let value = line[end..]
+        .trim_start_matches(' ')
+        .strip_prefix(separator)?;
+    let is_key =
+        end > 0 && (separator != ':' || value.is_empty() || value.starts_with(char::is_whitespace));
+
+    if is_key {
+        Some(end)
+    } else {
+        None
+    }
+}
+
+/// Highlights HTML and XML: tags, their attributes, comments and entities
+fn markup(code: &str) -> String {
+    let mut out = String::with_capacity(code.len() * 2);
+    let mut i = 0;
+
+    while i < code.len() {
+        let rest = &code[i..];
+        let c = rest.chars().next().unwrap();
+
+        let end = if rest.starts_with("<!--") {
+            let end = rest.find("-->").map_or(rest.len(), |e| e + "-->".len());
+            push_token(&mut out, "comment", &rest[..end]);
+            end
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            let end = rest.find('>').map_or(rest.len(), |e| e + 1);
+            push_token(&mut out, "prolog", &rest[..end]);
+            end
+        } else if c == '<' && rest[1..].starts_with(|c: char| c.is_alphabetic() || c == '/') {
+            let end = tag_end(rest);
+            tag(&mut out, &rest[..end]);
+            end
+        } else if let Some(end) = entity_end(rest) {
+            push_token(&mut out, "entity", &rest[..end]);
+            end
+        } else {
+            out.push_str(&html::escape(&rest[..c.len_utf8()]));
+            c.len_utf8()
+        };
+
+        i += end;
+    }
+
+    out
+}
+
+/// The length of the tag at the start of the text, skipping over any `>`
+/// in quoted attribute values
+fn tag_end(text: &str) -> usize {
+    let mut quote = None;
+
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '>' => return i + 1,
+            None => {}
+        }
+    }
+
+    text.len()
+}
+
+fn entity_end(text: &str) -> Option<usize> {
+    let rest = text.strip_prefix('&')?;
+    let name = &rest[..rest.find(';')?];
+    let valid = !name.is_empty()
+        && name.len() <= 10
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '#');
+
+    if valid {
+        Some(name.len() + 2)
+    } else {
+        None
+    }
+}
+
+/// Highlights a single tag, nesting the tokens like Prism does
+fn tag(out: &mut String, text: &str) {
+    let opening = if text.starts_with("</") { 2 } else { 1 };
+    let name_end = opening
+        + text[opening..]
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(text.len() - opening);
+
+    out.push_str("<span class=\"token tag\"><span class=\"token tag\">");
+    push_token(out, "punctuation", &text[..opening]);
+    out.push_str(&html::escape(&text[opening..name_end]));
+    out.push_str("</span>");
+
+    let mut rest = &text[name_end..];
+    while let Some(c) = rest.chars().next() {
+        let end = if c.is_whitespace() {
+            out.push(c);
+            c.len_utf8()
+        } else if c == '>' || c == '/' {
+            let end = rest.find(|c| c != '>' && c != '/').unwrap_or(rest.len());
+            push_token(out, "punctuation", &rest[..end]);
+            end
+        } else {
+            let name = rest
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+                .unwrap_or(rest.len());
+            push_token(out, "attr-name", &rest[..name]);
+
+            if rest[name..].starts_with('=') {
+                name + attribute_value(out, &rest[name..])
+            } else {
+                name
+            }
+        };
+
+        rest = &rest[end..];
+    }
+
+    out.push_str("</span>");
+}
+
+/// Highlights `="value"` after the name of an attribute, returning its length
+fn attribute_value(out: &mut String, text: &str) -> usize {
+    out.push_str("<span class=\"token attr-value\">");
+    out.push_str("<span class=\"token punctuation attr-equals\">=</span>");
+
+    let value = &text[1..];
+    let end = match value.chars().next() {
+        Some(quote) if quote == '"' || quote == '\'' => {
+            let close = value[1..].find(quote).map(|e| e + 1);
+            push_token(out, "punctuation", &value[..1]);
+            match close {
+                Some(close) => {
+                    out.push_str(&html::escape(&value[1..close]));
+                    push_token(out, "punctuation", &value[close..=close]);
+                    close + 1
+                }
+                None => {
+                    out.push_str(&html::escape(&value[1..]));
+                    value.len()
+                }
+            }
+        }
+        _ => {
+            let end = value
+                .find(|c: char| c.is_whitespace() || c == '>')
+                .unwrap_or(value.len());
+            out.push_str(&html::escape(&value[..end]));
+            end
+        }
+    };
+
+    out.push_str("</span>");
+    1 + end
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn highlights_rust() {
+        let code = "#[derive(Debug)]\nfn main() {\n    let s: &'static str = \"hi\"; // greet\n    println!(\"{}\", 1.5);\n}";
+
+        assert_eq!(
+            tokenize(code, &RUST),
+            "<span class=\"token attribute attr-name\">#[derive(Debug)]</span>\n\
+             <span class=\"token keyword\">fn</span> <span class=\"token function\">main</span>\
+             <span class=\"token punctuation\">(</span><span class=\"token punctuation\">)</span> \
+             <span class=\"token punctuation\">{</span>\n    \
+             <span class=\"token keyword\">let</span> s<span class=\"token operator\">:</span> \
+             <span class=\"token operator\">&amp;</span>\
+             <span class=\"token lifetime-annotation symbol\">&#39;static</span> str \
+             <span class=\"token operator\">=</span> <span class=\"token string\">&quot;hi&quot;</span>\
+             <span class=\"token punctuation\">;</span> <span class=\"token comment\">// greet</span>\n    \
+             <span class=\"token macro property\">println!</span><span class=\"token punctuation\">(</span>\
+             <span class=\"token string\">&quot;{}&quot;</span><span class=\"token punctuation\">,</span> \
+             <span class=\"token number\">1.5</span><span class=\"token punctuation\">)</span>\
+             <span class=\"token punctuation\">;</span>\n<span class=\"token punctuation\">}</span>"
+        );
+    }
+
+    #[test]
+    fn highlights_keys_and_comments() {
+        assert_eq!(
+            tokenize("title: Docs # the title\nitems:\n  - path: a.md\n", &YAML),
+            "<span class=\"token key atrule\">title</span><span class=\"token operator\">:</span> Docs \
+             <span class=\"token comment\"># the title</span>\n\
+             <span class=\"token key atrule\">items</span><span class=\"token operator\">:</span>\n  \
+             <span class=\"token punctuation\">-</span> \
+             <span class=\"token key atrule\">path</span><span class=\"token operator\">:</span> \
+             a<span class=\"token punctuation\">.</span>md\n"
+        );
+        assert_eq!(
+            tokenize("echo $HOME#not-a-comment", &BASH),
+            "echo <span class=\"token variable\">$HOME</span>#not<span class=\"token operator\">-</span>\
+             a<span class=\"token operator\">-</span>comment"
+        );
+    }
+
+    #[test]
+    fn highlights_markup() {
+        assert_eq!(
+            markup("<a href=\"/\">Home &amp; away</a><!-- x -->"),
+            "<span class=\"token tag\"><span class=\"token tag\"><span class=\"token punctuation\">&lt;</span>a</span> \
+             <span class=\"token attr-name\">href</span><span class=\"token attr-value\">\
+             <span class=\"token punctuation attr-equals\">=</span><span class=\"token punctuation\">&quot;</span>/\
+             <span class=\"token punctuation\">&quot;</span></span><span class=\"token punctuation\">&gt;</span></span>\
+             Home <span class=\"token entity\">&amp;amp;</span> away\
+             <span class=\"token tag\"><span class=\"token tag\"><span class=\"token punctuation\">&lt;/</span>a</span>\
+             <span class=\"token punctuation\">&gt;</span></span>\
+             <span class=\"token comment\">&lt;!-- x --&gt;</span>"
+        );
+    }
+
+    #[test]
+    fn highlights_code_blocks_in_html() {
+        let html = "<pre class=\"with-line-numbers\"><span class=\"code-lines\"></span>\
+                    <code class=\"language-json\">{&quot;a&quot;: null}</code></pre>\
+                    <pre><code class=\"language-brainfuck\">+&lt;</code></pre>";
+
+        assert_eq!(
+            highlight(html),
+            "<pre class=\"language-json with-line-numbers\"><span class=\"code-lines\"></span>\
+             <code class=\"language-json\"><span class=\"token punctuation\">{</span>\
+             <span class=\"token property\">&quot;a&quot;</span><span class=\"token operator\">:</span> \
+             <span class=\"token keyword\">null</span><span class=\"token punctuation\">}</span></code></pre>\
+             <pre><code class=\"language-brainfuck\">+&lt;</code></pre>"
+        );
+    }
+}
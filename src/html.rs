@@ -0,0 +1,579 @@
+//! Helpers for working with the HTML generated from Markdown.
+
+/// Removes all tags from the given HTML, leaving only the text content.
+/// Common entities are decoded back to the characters they represent.
+pub fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    unescape(&text)
+}
+
+/// Decodes the common entities back to the characters they represent.
+pub fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Escapes the characters that have a special meaning in HTML, so that the
+/// text can be placed inside elements and attribute values.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Finds the text of the first paragraph in the given HTML, if any.
+pub fn first_paragraph(html: &str) -> Option<String> {
+    let start = html.find("<p>")? + "<p>".len();
+    let end = html[start..].find("</p>")? + start;
+
+    let text = strip_tags(&html[start..end])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Shortens the text to at most `max` characters, cutting at a word boundary
+/// and marking the cut with an ellipsis.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let mut shortened = text.chars().take(max).collect::<String>();
+
+    if let Some(boundary) = shortened.rfind(char::is_whitespace) {
+        shortened.truncate(boundary);
+    }
+
+    format!("{}...", shortened.trim_end())
+}
+
+/// Replaces the values of the `href` and `src` attributes in the given HTML
+/// with the result of the function, leaving them unchanged if it returns
+/// `None`.
+pub fn rewrite_urls<F: Fn(&str) -> Option<String>>(html: &str, rewrite: F) -> String {
+    rewrite_attributes(html, &["href", "src"], rewrite)
+}
+
+/// Replaces the values of the named attributes in the given HTML with the
+/// result of the function, leaving them unchanged if it returns `None`.
+pub fn rewrite_attributes<F: Fn(&str) -> Option<String>>(
+    html: &str,
+    names: &[&str],
+    rewrite: F,
+) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = find_attribute(rest, names) {
+        let (before, after) = rest.split_at(start);
+        out.push_str(before);
+
+        let quote = after.chars().next().unwrap();
+        let value_end = match after[1..].find(quote) {
+            Some(end) => end + 1,
+            None => {
+                rest = after;
+                break;
+            }
+        };
+        let value = &after[1..value_end];
+
+        out.push(quote);
+        out.push_str(&rewrite(value).unwrap_or_else(|| value.to_string()));
+        out.push(quote);
+
+        rest = &after[value_end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// The raw value of an attribute in the HTML of an opening tag, without
+/// decoding any entities in it.
+pub fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!(" {}=\"", name))? + name.len() + 3;
+    let end = tag[start..].find('"')? + start;
+
+    Some(tag[start..end].to_string())
+}
+
+/// Finds the opening quote of the value of the next attribute with one of
+/// the given names
+fn find_attribute(html: &str, names: &[&str]) -> Option<usize> {
+    let mut offset = 0;
+
+    while let Some(pos) = html[offset..].find('=') {
+        let equals = offset + pos;
+        let name = html[..equals].trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '-');
+        let attribute = &html[name.len()..equals];
+        let preceded_by_space = name.ends_with(char::is_whitespace);
+
+        if preceded_by_space && names.contains(&attribute) {
+            if let Some('"') | Some('\'') = html[equals + 1..].chars().next() {
+                return Some(equals + 1);
+            }
+        }
+
+        offset = equals + 1;
+    }
+
+    None
+}
+
+/// Elements that have no content, and so no closing tag in HTML
+static VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// The named entities of HTML 4 for the characters from U+00A0 to U+00FF
+static LATIN_1_ENTITIES: &[&str] = &[
+    "nbsp", "iexcl", "cent", "pound", "curren", "yen", "brvbar", "sect", "uml", "copy", "ordf",
+    "laquo", "not", "shy", "reg", "macr", "deg", "plusmn", "sup2", "sup3", "acute", "micro",
+    "para", "middot", "cedil", "sup1", "ordm", "raquo", "frac14", "frac12", "frac34", "iquest",
+    "Agrave", "Aacute", "Acirc", "Atilde", "Auml", "Aring", "AElig", "Ccedil", "Egrave", "Eacute",
+    "Ecirc", "Euml", "Igrave", "Iacute", "Icirc", "Iuml", "ETH", "Ntilde", "Ograve", "Oacute",
+    "Ocirc", "Otilde", "Ouml", "times", "Oslash", "Ugrave", "Uacute", "Ucirc", "Uuml", "Yacute",
+    "THORN", "szlig", "agrave", "aacute", "acirc", "atilde", "auml", "aring", "aelig", "ccedil",
+    "egrave", "eacute", "ecirc", "euml", "igrave", "iacute", "icirc", "iuml", "eth", "ntilde",
+    "ograve", "oacute", "ocirc", "otilde", "ouml", "divide", "oslash", "ugrave", "uacute", "ucirc",
+    "uuml", "yacute", "thorn", "yuml",
+];
+
+/// The named entities of HTML 4 for Greek letters, from U+0391
+static GREEK_ENTITIES: &[&str] = &[
+    "Alpha", "Beta", "Gamma", "Delta", "Epsilon", "Zeta", "Eta", "Theta", "Iota", "Kappa",
+    "Lambda", "Mu", "Nu", "Xi", "Omicron", "Pi", "Rho", "", "Sigma", "Tau", "Upsilon", "Phi",
+    "Chi", "Psi", "Omega", "", "", "", "", "", "", "", "alpha", "beta", "gamma", "delta",
+    "epsilon", "zeta", "eta", "theta", "iota", "kappa", "lambda", "mu", "nu", "xi", "omicron",
+    "pi", "rho", "sigmaf", "sigma", "tau", "upsilon", "phi", "chi", "psi", "omega",
+];
+
+/// The rest of the named entities of HTML 4
+static OTHER_ENTITIES: &[(&str, u32)] = &[
+    ("OElig", 338),
+    ("oelig", 339),
+    ("Scaron", 352),
+    ("scaron", 353),
+    ("Yuml", 376),
+    ("fnof", 402),
+    ("circ", 710),
+    ("tilde", 732),
+    ("thetasym", 977),
+    ("upsih", 978),
+    ("piv", 982),
+    ("ensp", 8194),
+    ("emsp", 8195),
+    ("thinsp", 8201),
+    ("zwnj", 8204),
+    ("zwj", 8205),
+    ("lrm", 8206),
+    ("rlm", 8207),
+    ("ndash", 8211),
+    ("mdash", 8212),
+    ("lsquo", 8216),
+    ("rsquo", 8217),
+    ("sbquo", 8218),
+    ("ldquo", 8220),
+    ("rdquo", 8221),
+    ("bdquo", 8222),
+    ("dagger", 8224),
+    ("Dagger", 8225),
+    ("bull", 8226),
+    ("hellip", 8230),
+    ("permil", 8240),
+    ("prime", 8242),
+    ("Prime", 8243),
+    ("lsaquo", 8249),
+    ("rsaquo", 8250),
+    ("oline", 8254),
+    ("frasl", 8260),
+    ("euro", 8364),
+    ("image", 8465),
+    ("weierp", 8472),
+    ("real", 8476),
+    ("trade", 8482),
+    ("alefsym", 8501),
+    ("larr", 8592),
+    ("uarr", 8593),
+    ("rarr", 8594),
+    ("darr", 8595),
+    ("harr", 8596),
+    ("crarr", 8629),
+    ("lArr", 8656),
+    ("uArr", 8657),
+    ("rArr", 8658),
+    ("dArr", 8659),
+    ("hArr", 8660),
+    ("forall", 8704),
+    ("part", 8706),
+    ("exist", 8707),
+    ("empty", 8709),
+    ("nabla", 8711),
+    ("isin", 8712),
+    ("notin", 8713),
+    ("ni", 8715),
+    ("prod", 8719),
+    ("sum", 8721),
+    ("minus", 8722),
+    ("lowast", 8727),
+    ("radic", 8730),
+    ("prop", 8733),
+    ("infin", 8734),
+    ("ang", 8736),
+    ("and", 8743),
+    ("or", 8744),
+    ("cap", 8745),
+    ("cup", 8746),
+    ("int", 8747),
+    ("there4", 8756),
+    ("sim", 8764),
+    ("cong", 8773),
+    ("asymp", 8776),
+    ("ne", 8800),
+    ("equiv", 8801),
+    ("le", 8804),
+    ("ge", 8805),
+    ("sub", 8834),
+    ("sup", 8835),
+    ("nsub", 8836),
+    ("sube", 8838),
+    ("supe", 8839),
+    ("oplus", 8853),
+    ("otimes", 8855),
+    ("perp", 8869),
+    ("sdot", 8901),
+    ("lceil", 8968),
+    ("rceil", 8969),
+    ("lfloor", 8970),
+    ("rfloor", 8971),
+    ("lang", 9001),
+    ("rang", 9002),
+    ("loz", 9674),
+    ("spades", 9824),
+    ("clubs", 9827),
+    ("hearts", 9829),
+    ("diams", 9830),
+];
+
+/// Turns the HTML into well-formed XHTML, as EPUB chapters require. Void
+/// elements such as `<br>` are closed, attributes get quoted values, and
+/// named entities that XML doesn't know are replaced with numeric ones.
+pub fn to_xhtml(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(i) = rest.find(&['<', '&'][..]) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        let length = if rest.starts_with('&') {
+            let (entity, length) = xml_entity(rest);
+            out.push_str(&entity);
+            length
+        } else if let Some(end) = ["<!--", "<![CDATA["]
+            .iter()
+            .zip(&["-->", "]]>"])
+            .find(|(start, _)| rest.starts_with(*start))
+            .map(|(_, end)| rest.find(end).map_or(rest.len(), |i| i + end.len()))
+        {
+            out.push_str(&rest[..end]);
+            end
+        } else if let Some((tag, length)) = xhtml_tag(rest) {
+            out.push_str(&tag);
+            length
+        } else {
+            out.push_str("&lt;");
+            1
+        };
+
+        rest = &rest[length..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Replaces the entities in the text with ones XML understands
+fn xml_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        let (entity, length) = xml_entity(&rest[i..]);
+        out.push_str(&entity);
+        rest = &rest[i + length..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// The entity at the start of the text as XML understands it, and the
+/// length of the original. A `&` that doesn't start an entity is escaped.
+fn xml_entity(text: &str) -> (String, usize) {
+    let name = text[1..]
+        .find(';')
+        .filter(|end| *end > 0 && *end <= 32)
+        .map(|end| &text[1..end + 1])
+        .filter(|name| name.chars().all(|c| c.is_ascii_alphanumeric() || c == '#'));
+
+    let name = match name {
+        Some(name) => name,
+        None => return ("&amp;".to_string(), 1),
+    };
+    let length = name.len() + 2;
+
+    let numeric = name
+        .strip_prefix("#x")
+        .or_else(|| name.strip_prefix("#X"))
+        .map(|hex| u32::from_str_radix(hex, 16))
+        .or_else(|| name.strip_prefix('#').map(|decimal| decimal.parse()));
+
+    let code = match numeric {
+        Some(Ok(code)) if std::char::from_u32(code).is_some() => code,
+        Some(_) => return ("&amp;".to_string(), 1),
+        None if ["amp", "lt", "gt", "quot", "apos"].contains(&name) => {
+            return (text[..length].to_string(), length)
+        }
+        None => match named_entity(name) {
+            Some(code) => code,
+            None => return ("&amp;".to_string(), 1),
+        },
+    };
+
+    (format!("&#{};", code), length)
+}
+
+/// The code point of a named HTML 4 entity
+fn named_entity(name: &str) -> Option<u32> {
+    let position = |names: &[&str]| names.iter().position(|n| *n == name).map(|i| i as u32);
+
+    position(LATIN_1_ENTITIES)
+        .map(|i| 0xA0 + i)
+        .or_else(|| position(GREEK_ENTITIES).map(|i| 0x391 + i))
+        .or_else(|| {
+            OTHER_ENTITIES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, code)| *code)
+        })
+}
+
+/// The length of the tag at the start of the text, up to the first `>` that
+/// isn't inside a quoted attribute value
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '>' => return Some(i + 1),
+            None => {}
+        }
+    }
+
+    None
+}
+
+/// The tag at the start of the text written as XHTML, and the length of the
+/// original. Returns `None` if the text doesn't start with a tag.
+fn xhtml_tag(text: &str) -> Option<(String, usize)> {
+    let end = tag_end(text)?;
+    let tag = &text[..end];
+
+    // Closing tags, doctypes and processing instructions are left as they are
+    if tag.starts_with("</") || tag.starts_with("<!") || tag.starts_with("<?") {
+        return Some((tag.to_string(), end));
+    }
+
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == ':';
+    let name_end = tag[1..].find(|c: char| !is_name(c))? + 1;
+    let name = &tag[1..name_end];
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut out = format!("<{}", name);
+    let mut rest = &tag[name_end..tag.len() - 1];
+    let mut self_closing = false;
+
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        if rest == "/" {
+            self_closing = true;
+            break;
+        }
+
+        let attribute_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len())
+            .max(1);
+        let attribute = &rest[..attribute_end];
+        rest = rest[attribute_end..].trim_start();
+
+        if !attribute.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == ':') {
+            continue;
+        }
+
+        // Attributes without a value, such as `disabled`, repeat their name
+        let value = match rest.strip_prefix('=') {
+            Some(after) => {
+                let after = after.trim_start();
+                let (value, remaining) = match after.chars().next() {
+                    Some(quote) if quote == '"' || quote == '\'' => {
+                        // An unterminated quote runs to the end of the tag
+                        let close = after[1..].find(quote).map_or(after.len(), |i| i + 1);
+                        (&after[1..close], after.get(close + 1..).unwrap_or(""))
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                rest = remaining;
+                value
+            }
+            None => attribute,
+        };
+
+        out.push_str(&format!(
+            " {}=\"{}\"",
+            attribute,
+            xml_entities(value)
+                .replace('<', "&lt;")
+                .replace('"', "&quot;")
+        ));
+    }
+
+    if self_closing || VOID_ELEMENTS.contains(&name.to_ascii_lowercase().as_str()) {
+        out.push_str(" />");
+    } else {
+        out.push('>');
+    }
+
+    Some((out, end))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strips_tags_and_decodes_entities() {
+        assert_eq!(
+            strip_tags("<p>Some <code>&lt;b&gt;</code> &amp; <em>more</em></p>"),
+            "Some <b> & more"
+        );
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        assert_eq!(
+            escape("<a href=\"x\">Tom & Jerry's</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+        assert_eq!(strip_tags(&escape("a < b & c")), "a < b & c");
+    }
+
+    #[test]
+    fn finds_the_first_paragraph() {
+        let html = "<h1>Title</h1>\n<p>The <strong>first</strong>\nparagraph</p>\n<p>Second</p>";
+
+        assert_eq!(
+            first_paragraph(html),
+            Some("The first paragraph".to_string())
+        );
+        assert_eq!(first_paragraph("<h1>Only a title</h1>"), None);
+    }
+
+    #[test]
+    fn truncates_at_word_boundaries() {
+        assert_eq!(truncate("short enough", 20), "short enough");
+        assert_eq!(truncate("this is too long", 10), "this is...");
+    }
+
+    #[test]
+    fn rewrites_urls_in_attributes() {
+        let html = "<a href=\"/one\">href=\"/not-an-attribute\"</a><img src='/two.png'><a class=\"x\" href=\"#top\">";
+
+        assert_eq!(
+            rewrite_urls(html, |url| url.strip_prefix('/').map(|u| format!("./{}", u))),
+            "<a href=\"./one\">href=\"/not-an-attribute\"</a><img src='./two.png'><a class=\"x\" href=\"#top\">"
+        );
+    }
+
+    #[test]
+    fn rewrites_named_attributes() {
+        let html = "<h2 id=\"usage\">Usage</h2><a href=\"#usage\">";
+
+        assert_eq!(
+            rewrite_attributes(html, &["id"], |id| Some(format!("page--{}", id))),
+            "<h2 id=\"page--usage\">Usage</h2><a href=\"#usage\">"
+        );
+        assert_eq!(
+            rewrite_attributes("<a href=\"unterminated", &["href"], |_| None),
+            "<a href=\"unterminated"
+        );
+        assert_eq!(
+            rewrite_attributes(
+                "<div aria-labelledby=\"tab\" data-id=\"x\">",
+                &["id", "aria-labelledby"],
+                |id| Some(format!("page--{}", id))
+            ),
+            "<div aria-labelledby=\"page--tab\" data-id=\"x\">"
+        );
+    }
+
+    #[test]
+    fn converts_html_to_xhtml() {
+        assert_eq!(
+            to_xhtml(
+                "<p>A<br>B &nbsp;&copy; &Omega; &amp; &#169; &#xA9; &bogus; & < 3</p>\
+                 <img src=\"a.png\" alt='Say \"hi\" > &eacute;' width=10><input disabled>\
+                 <br/><!-- <br> --><hr class=\"x\" />"
+            ),
+            "<p>A<br />B &#160;&#169; &#937; &amp; &#169; &#169; &amp;bogus; &amp; &lt; 3</p>\
+             <img src=\"a.png\" alt=\"Say &quot;hi&quot; > &#233;\" width=\"10\" /><input disabled=\"disabled\" />\
+             <br /><!-- <br> --><hr class=\"x\" />"
+        );
+    }
+
+    #[test]
+    fn knows_every_latin_1_entity() {
+        assert_eq!(LATIN_1_ENTITIES.len(), 96);
+        assert_eq!(named_entity("yuml"), Some(0xFF));
+        assert_eq!(named_entity("omega"), Some(0x3C9));
+        assert_eq!(named_entity("hellip"), Some(0x2026));
+    }
+}
@@ -0,0 +1,289 @@
+//! Gitignore-style patterns for files in the docs directory that should not
+//! be turned into pages.
+//!
+//! Supports the commonly used parts of the gitignore format: `*`, `?`, `**`,
+//! character classes, negation with `!`, directory-only patterns ending in
+//! `/`, and patterns anchored to their base directory with a `/`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<Pattern>,
+}
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    /// The directory the pattern is relative to
+    base: PathBuf,
+    glob: Vec<char>,
+    negated: bool,
+    dir_only: bool,
+    /// Whether the pattern matches the whole path, or just the file name
+    anchored: bool,
+}
+
+impl IgnoreRules {
+    /// Collects the ignore patterns from `doctave.yaml`, and if enabled, from
+    /// the project's `.gitignore` files and the repository's
+    /// `.git/info/exclude`.
+    pub fn for_config(config: &Config) -> Self {
+        let mut rules = IgnoreRules::default();
+
+        if config.use_gitignore() {
+            let root = config.project_root();
+
+            if let Some(repository) = root.ancestors().find(|dir| dir.join(".git").is_dir()) {
+                rules.add_file(repository, &repository.join(".git/info/exclude"));
+            }
+
+            // Files in deeper directories come later, so that they take
+            // precedence over the ones above them, as in git
+            let mut dirs = config
+                .docs_dir()
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.starts_with(root))
+                .collect::<Vec<_>>();
+            dirs.reverse();
+            for dir in dirs {
+                rules.add_file(dir, &dir.join(".gitignore"));
+            }
+
+            rules.add_nested_files(config.docs_dir());
+        }
+
+        for line in config.ignore() {
+            rules.add(config.docs_dir(), line);
+        }
+
+        rules
+    }
+
+    /// Adds the patterns in the `.gitignore` files in the directory and the
+    /// directories below it, skipping directories that are already ignored.
+    fn add_nested_files(&mut self, dir: &Path) {
+        self.add_file(dir, &dir.join(".gitignore"));
+
+        let mut children = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+                .filter(|e| e.file_name() != ".git")
+                .map(|e| e.path())
+                .collect::<Vec<_>>(),
+            Err(_) => return,
+        };
+        children.sort();
+
+        for child in children {
+            if !self.is_ignored(&child, true) {
+                self.add_nested_files(&child);
+            }
+        }
+    }
+
+    /// Adds the patterns in a gitignore file, relative to the given
+    /// directory. Missing files are skipped.
+    fn add_file(&mut self, base: &Path, file: &Path) {
+        if let Ok(content) = fs::read_to_string(file) {
+            for line in content.lines() {
+                self.add(base, line);
+            }
+        }
+    }
+
+    /// Adds a pattern, relative to the given directory. Blank lines and
+    /// comments are skipped.
+    pub fn add(&mut self, base: &Path, line: &str) {
+        let line = line.trim_end();
+
+        if line.is_empty() || line.starts_with('#') {
+            return;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+
+        self.patterns.push(Pattern {
+            base: base.to_path_buf(),
+            glob: line.trim_start_matches('/').chars().collect(),
+            negated,
+            dir_only,
+            anchored,
+        });
+    }
+
+    /// Whether the file or directory at the given path should be ignored,
+    /// either by itself or because one of its parent directories is.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+
+        path.ancestors()
+            .skip(1)
+            .any(|parent| self.matches(parent, true))
+            || self.matches(path, is_dir)
+    }
+
+    /// Checks the path against the patterns. Later patterns take precedence.
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+
+        for pattern in &self.patterns {
+            if pattern.matches(path, is_dir) {
+                ignored = !pattern.negated;
+            }
+        }
+
+        ignored
+    }
+}
+
+impl Pattern {
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+
+        let relative = match path.strip_prefix(&self.base) {
+            Ok(relative) if relative != Path::new("") => relative,
+            _ => return false,
+        };
+
+        let text: Vec<char> = if self.anchored {
+            relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+                .chars()
+                .collect()
+        } else {
+            match relative.file_name() {
+                Some(name) => name.to_string_lossy().chars().collect(),
+                None => return false,
+            }
+        };
+
+        glob_match(&self.glob, &text)
+    }
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            // Zero or more directories
+            glob_match(rest, text)
+                || (0..text.len()).any(|i| text[i] == '/' && glob_match(rest, &text[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        ['*', rest @ ..] => {
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        ['?', rest @ ..] => match text {
+            [c, text @ ..] if *c != '/' => glob_match(rest, text),
+            _ => false,
+        },
+        ['[', class @ ..] => match class.iter().position(|c| *c == ']') {
+            Some(end) if end > 0 => match text {
+                [c, text @ ..] if *c != '/' && class_matches(&class[..end], *c) => {
+                    glob_match(&class[end + 1..], text)
+                }
+                _ => false,
+            },
+            // No closing bracket, so match it literally
+            _ => text.first() == Some(&'[') && glob_match(class, &text[1..]),
+        },
+        ['\\', c, rest @ ..] => text.first() == Some(c) && glob_match(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+/// Matches a character against the inside of a `[...]` class, such as `abc`,
+/// `a-z` or `!0-9`.
+fn class_matches(class: &[char], c: char) -> bool {
+    let (negated, class) = match class {
+        ['!', rest @ ..] | ['^', rest @ ..] => (true, rest),
+        _ => (false, class),
+    };
+
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            found |= class[i] <= c && c <= class[i + 2];
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+
+    found != negated
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rules(patterns: &[&str]) -> IgnoreRules {
+        let mut rules = IgnoreRules::default();
+        for pattern in patterns {
+            rules.add(Path::new("docs"), pattern);
+        }
+        rules
+    }
+
+    #[test]
+    fn matches_file_names_at_any_depth() {
+        let rules = rules(&["*.draft.md", "# A comment", "", "node_modules/"]);
+
+        assert!(rules.is_ignored(Path::new("docs/ideas.draft.md"), false));
+        assert!(rules.is_ignored(Path::new("docs/guides/wip.draft.md"), false));
+        assert!(rules.is_ignored(Path::new("docs/lib/node_modules/pkg/README.md"), false));
+        assert!(!rules.is_ignored(Path::new("docs/guides/setup.md"), false));
+    }
+
+    #[test]
+    fn anchored_patterns_match_from_the_base() {
+        let rules = rules(&["/drafts", "guides/*/notes.md", "vendor/**/*.md"]);
+
+        assert!(rules.is_ignored(Path::new("docs/drafts/idea.md"), false));
+        assert!(!rules.is_ignored(Path::new("docs/guides/drafts/idea.md"), false));
+        assert!(rules.is_ignored(Path::new("docs/guides/linux/notes.md"), false));
+        assert!(!rules.is_ignored(Path::new("docs/guides/linux/deep/notes.md"), false));
+        assert!(rules.is_ignored(Path::new("docs/vendor/a/b/README.md"), false));
+        assert!(rules.is_ignored(Path::new("docs/vendor/README.md"), false));
+    }
+
+    #[test]
+    fn negated_patterns_and_classes() {
+        let rules = rules(&["notes-[0-9].md", "*.md", "!keep.md", "dir-only/"]);
+
+        assert!(rules.is_ignored(Path::new("docs/notes-1.md"), false));
+        assert!(!rules.is_ignored(Path::new("docs/keep.md"), false));
+        assert!(rules.is_ignored(Path::new("docs/dir-only"), true));
+        assert!(!rules.is_ignored(Path::new("docs/x/dir-only"), false));
+    }
+}
@@ -0,0 +1,208 @@
+//! Exports the documentation as JSON, for tools that want to work with the
+//! content without parsing the generated HTML.
+//!
+//! The format is documented in `docs/features/json-export.md`. Any change to
+//! it that could break existing consumers must bump `SCHEMA_VERSION`.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::frontmatter;
+use crate::navigation::Link;
+use crate::relative_links::to_url_path;
+use crate::{Directory, Document, Error, Result};
+
+pub static SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+struct Export<'a> {
+    schema_version: u32,
+    title: &'a str,
+    base_path: &'a str,
+    documents: Vec<DocumentData<'a>>,
+    navigation: &'a [Link],
+}
+
+#[derive(Debug, Serialize)]
+struct DocumentData<'a> {
+    path: String,
+    uri_path: String,
+    title: &'a str,
+    frontmatter: BTreeMap<&'a str, serde_json::Value>,
+    headings: Vec<HeadingData<'a>>,
+    links: Vec<LinkData<'a>>,
+    markdown: &'a str,
+    html: String,
+}
+
+#[derive(Debug, Serialize)]
+struct HeadingData<'a> {
+    title: &'a str,
+    anchor: &'a str,
+    level: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct LinkData<'a> {
+    title: &'a str,
+    url: String,
+    /// Whether the link points to a page on the site
+    local: bool,
+}
+
+/// Serializes every document in the given directory, along with the
+/// navigation.
+pub fn export(config: &Config, root: &Directory, navigation: &[Link]) -> Result<Vec<u8>> {
+    let mut documents = Vec::new();
+    collect_documents(config, root, &mut documents)?;
+
+    let export = Export {
+        schema_version: SCHEMA_VERSION,
+        title: config.title(),
+        base_path: config.base_path(),
+        documents,
+        navigation,
+    };
+
+    serde_json::to_vec_pretty(&export)
+        .map_err(|e| Error::new(format!("Could not serialize documentation: {}", e)))
+}
+
+fn collect_documents<'a>(
+    config: &Config,
+    dir: &'a Directory,
+    documents: &mut Vec<DocumentData<'a>>,
+) -> Result<()> {
+    for doc in &dir.docs {
+        documents.push(document_data(config, doc)?);
+    }
+
+    for child in &dir.dirs {
+        collect_documents(config, child, documents)?;
+    }
+
+    Ok(())
+}
+
+fn document_data<'a>(config: &Config, doc: &'a Document) -> Result<DocumentData<'a>> {
+    Ok(DocumentData {
+        path: to_url_path(doc.original_path()),
+        uri_path: doc.uri_path(),
+        title: doc.title(),
+        frontmatter: doc
+            .frontmatter
+            .iter()
+            .map(|(key, value)| (key.as_str(), frontmatter_value(value)))
+            .collect(),
+        headings: doc
+            .headings()
+            .iter()
+            .map(|h| HeadingData {
+                title: &h.title,
+                anchor: &h.anchor,
+                level: h.level,
+            })
+            .collect(),
+        links: doc
+            .outgoing_links()
+            .iter()
+            .map(|l| match &l.url {
+                doctave_markdown::UrlType::Local(path) => LinkData {
+                    title: &l.title,
+                    url: to_url_path(path),
+                    local: true,
+                },
+                doctave_markdown::UrlType::Remote(url) => LinkData {
+                    title: &l.title,
+                    url: url.clone(),
+                    local: false,
+                },
+            })
+            .collect(),
+        markdown: doc.markdown_section(),
+        html: config.renderers().render(doc.html())?,
+    })
+}
+
+/// Lists and maps in the frontmatter are exported as JSON arrays and
+/// objects. Other values are exported as strings.
+fn frontmatter_value(value: &str) -> serde_json::Value {
+    frontmatter::structured(value)
+        .and_then(|value| serde_json::to_value(value).ok())
+        .unwrap_or_else(|| serde_json::Value::String(value.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::navigation::Navigation;
+    use crate::snippets::Snippets;
+    use std::path::Path;
+
+    #[test]
+    fn exports_documents_and_navigation() {
+        let config = Config::from_yaml_str(Path::new("project"), "---\ntitle: Export\n").unwrap();
+
+        let frontmatter = frontmatter::parse(
+            "---\ntitle: Setup\ntags: [install, linux]\nauthor:\n  name: Ada\n  posts: 3\nsummary: \"[draft]\"\n---\n",
+        )
+        .unwrap();
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![
+                Document::new(
+                    Path::new("README.md"),
+                    "# Home\n\nSee the [setup](/setup).".to_string(),
+                    BTreeMap::new(),
+                    "/",
+                    &Snippets::default(),
+                    &BTreeMap::new(),
+                ),
+                Document::new(
+                    Path::new("setup.md"),
+                    "# Setup\n\n## Install".to_string(),
+                    frontmatter,
+                    "/",
+                    &Snippets::default(),
+                    &BTreeMap::new(),
+                ),
+            ],
+            dirs: vec![],
+        };
+
+        let navigation = Navigation::new(&config).build_for(&root);
+        let out = export(&config, &root, &navigation).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["title"], "Export");
+
+        let setup = &json["documents"][1];
+        assert_eq!(setup["path"], "setup.md");
+        assert_eq!(setup["uri_path"], "/setup");
+        assert_eq!(setup["title"], "Setup");
+        assert_eq!(setup["frontmatter"]["title"], "Setup");
+        assert_eq!(
+            setup["frontmatter"]["tags"],
+            serde_json::json!(["install", "linux"])
+        );
+        assert_eq!(
+            setup["frontmatter"]["author"],
+            serde_json::json!({ "name": "Ada", "posts": 3 })
+        );
+        assert_eq!(setup["frontmatter"]["summary"], "[draft]");
+        assert_eq!(setup["headings"][1]["title"], "Install");
+        assert_eq!(setup["headings"][1]["level"], 2);
+        assert_eq!(setup["markdown"], "# Setup\n\n## Install");
+
+        let link = &json["documents"][0]["links"][0];
+        assert_eq!(link["title"], "setup");
+        assert_eq!(link["local"], true);
+
+        assert_eq!(json["navigation"][0]["path"], "/setup");
+        assert_eq!(json["navigation"][0]["title"], "Setup");
+    }
+}
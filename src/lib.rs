@@ -6,21 +6,46 @@ extern crate indoc;
 #[macro_use]
 extern crate lazy_static;
 
+mod broken_includes_checker;
 mod broken_links_checker;
 mod build;
+mod callouts;
+mod code_blocks;
+mod code_includes;
 pub mod config;
+mod containers;
+mod date;
 mod docs_finder;
+mod epub;
 mod error;
+mod export;
 mod frontmatter;
+mod git;
+mod glossary;
+mod highlight;
+mod html;
+mod ignore;
 mod init;
+mod json;
 mod livereload_server;
 mod navigation;
 mod preview_server;
+mod print;
+mod prism;
+mod redirects;
+mod relative_links;
+mod renderers;
 #[allow(dead_code, unused_variables)]
 mod serve;
 mod site;
 mod site_generator;
+mod snippets;
+mod tabs;
+mod toc;
+mod undefined_terms_checker;
+mod undefined_variables_checker;
 mod watcher;
+mod zip;
 
 use std::collections::BTreeMap;
 use std::ffi::OsStr;
@@ -30,14 +55,18 @@ use std::path::{Path, PathBuf};
 pub use build::BuildCommand;
 pub use config::Config;
 pub use error::Error;
+pub use export::{ExportCommand, ExportFormat, ExportOptions};
 pub use init::InitCommand;
 pub use serve::{ServeCommand, ServeOptions};
 pub use site::BuildMode;
 
+use callouts::Callouts;
 pub use doctave_markdown::{Heading, Markdown};
+use glossary::Glossary;
 use handlebars::Handlebars;
 use include_dir::{include_dir, Dir};
 use navigation::Link;
+use snippets::{BrokenInclude, Expansion, Snippets, UndefinedVariable};
 
 static APP_JS: &str = include_str!("assets/app.js");
 static MERMAID_JS: &str = include_str!("assets/mermaid.min.js");
@@ -76,6 +105,39 @@ lazy_static! {
         handlebars
             .register_template_string("style.css", include_str!("../templates/style.css"))
             .unwrap();
+        handlebars
+            .register_template_string("atom.xml", include_str!("../templates/atom.xml"))
+            .unwrap();
+        handlebars
+            .register_template_string("rss.xml", include_str!("../templates/rss.xml"))
+            .unwrap();
+        handlebars
+            .register_template_string("redirect", include_str!("../templates/redirect.html"))
+            .unwrap();
+        handlebars
+            .register_template_string("print", include_str!("../templates/print.html"))
+            .unwrap();
+        handlebars
+            .register_template_string(
+                "epub_package",
+                include_str!("../templates/epub/content.opf"),
+            )
+            .unwrap();
+        handlebars
+            .register_template_string("epub_nav", include_str!("../templates/epub/nav.xhtml"))
+            .unwrap();
+        handlebars
+            .register_template_string(
+                "epub_nav_items",
+                include_str!("../templates/epub/nav_items.xhtml"),
+            )
+            .unwrap();
+        handlebars
+            .register_template_string(
+                "epub_chapter",
+                include_str!("../templates/epub/chapter.xhtml"),
+            )
+            .unwrap();
 
         handlebars
     };
@@ -95,11 +157,83 @@ impl Directory {
         &self.path
     }
 
+    /// Finds the directory at the given path, looking through this directory
+    /// and all of its descendants.
+    fn find_dir(&self, path: &Path) -> Option<&Directory> {
+        if self.path == path {
+            Some(self)
+        } else {
+            self.dirs.iter().find_map(|d| d.find_dir(path))
+        }
+    }
+
+    /// All documents in this directory and its descendants that include the
+    /// given snippet.
+    fn docs_using_snippet(&self, name: &str) -> Vec<&Document> {
+        let mut docs = self
+            .docs
+            .iter()
+            .filter(|d| d.snippets.iter().any(|s| s == name))
+            .collect::<Vec<_>>();
+
+        for dir in &self.dirs {
+            docs.append(&mut dir.docs_using_snippet(name));
+        }
+
+        docs
+    }
+
+    /// The source files included as code blocks in any document
+    fn sources(&self) -> Vec<PathBuf> {
+        let mut sources = Vec::new();
+
+        for doc in &self.docs {
+            for source in doc.sources() {
+                if !sources.contains(source) {
+                    sources.push(source.clone());
+                }
+            }
+        }
+
+        for dir in &self.dirs {
+            for source in dir.sources() {
+                if !sources.contains(&source) {
+                    sources.push(source);
+                }
+            }
+        }
+
+        sources
+    }
+
+    fn docs_including_source(&self, path: &Path) -> Vec<&Document> {
+        let mut docs = self
+            .docs
+            .iter()
+            .filter(|d| d.sources().iter().any(|s| s == path))
+            .collect::<Vec<_>>();
+
+        for dir in &self.dirs {
+            docs.append(&mut dir.docs_including_source(path));
+        }
+
+        docs
+    }
+
+    /// Finds the document with the given path inside the docs directory,
+    /// looking through this directory and all of its descendants.
+    fn find_doc(&self, path: &Path) -> Option<&Document> {
+        self.docs
+            .iter()
+            .find(|d| d.original_path() == path)
+            .or_else(|| self.dirs.iter().find_map(|d| d.find_doc(path)))
+    }
+
     fn index(&self) -> &Document {
         &self
             .docs
             .iter()
-            .find(|d| d.original_file_name() == Some(OsStr::new("README.md")))
+            .find(|d| d.is_index())
             .expect("No index file found for directory")
     }
 
@@ -107,6 +241,7 @@ impl Directory {
         let mut links = self
             .docs
             .iter()
+            .filter(|d| !d.is_hidden())
             .map(|d| Link {
                 title: d.title().to_owned(),
                 path: d.uri_path(),
@@ -122,6 +257,8 @@ impl Directory {
         let mut children = self
             .dirs
             .iter()
+            // Hiding the index of a directory hides the whole directory
+            .filter(|d| !d.index().is_hidden())
             .map(|d| Link {
                 title: d.index().title().to_owned(),
                 path: d.index().uri_path(),
@@ -136,6 +273,46 @@ impl Directory {
     }
 }
 
+/// File names, without the extension, that are used as the index page of
+/// their directory.
+static INDEX_FILE_NAMES: &[&str] = &["README", "index", "_index"];
+
+/// Whether the file at the given path is the index page of its directory.
+fn is_index_file(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .map(|s| INDEX_FILE_NAMES.contains(&s))
+        .unwrap_or(false)
+}
+
+/// The HTML file that serves the given URI path, relative to the output
+/// directory. Paths ending in a slash are served by an `index.html` file.
+///
+/// E.g: /foo/bar => foo/bar.html, /foo/ => foo/index.html
+fn uri_to_html_path(uri: &str) -> PathBuf {
+    let path = uri.trim_start_matches('/');
+
+    if path.is_empty() || path.ends_with('/') {
+        Path::new(path).join("index.html")
+    } else if path.ends_with(".html") {
+        PathBuf::from(path)
+    } else {
+        PathBuf::from(format!("{}.html", path))
+    }
+}
+
+/// Moves an HTML file into a directory of its own, so that it can be served
+/// without the `.html` extension by any host.
+///
+/// E.g: foo/bar.html => foo/bar/index.html
+fn pretty_html_path(path: PathBuf) -> PathBuf {
+    if path.file_stem() == Some(OsStr::new("index")) {
+        path
+    } else {
+        path.with_extension("").join("index.html")
+    }
+}
+
 use std::sync::atomic::AtomicU32;
 
 static DOCUMENT_ID: AtomicU32 = AtomicU32::new(1);
@@ -146,10 +323,32 @@ struct Document {
     /// The relative path in the docs folder to the file
     path: PathBuf,
     rename: Option<String>,
-    raw: String,
+    /// The Markdown content, after snippets have been expanded
+    content: String,
+    /// Names of the snippets included in this document
+    snippets: Vec<String>,
+    /// Set if the snippets in this document could not be expanded
+    snippet_error: Option<String>,
+    /// Variables referenced in this document that were not defined
+    undefined_variables: Vec<UndefinedVariable>,
+    /// Source files included in this document as code blocks
+    sources: Vec<PathBuf>,
+    /// Source files that could not be included
+    broken_includes: Vec<BrokenInclude>,
+    /// Glossary terms referenced in this document that were not defined
+    undefined_terms: Vec<String>,
     markdown: Markdown,
+    /// The rendered HTML, with any table of contents inserted
+    html: String,
     frontmatter: BTreeMap<String, String>,
     base_path: String,
+    /// When the source file was last changed, according to git or the file system
+    modified: Option<date::Timestamp>,
+    /// Whether the page is built into a directory of its own
+    pretty_urls: bool,
+    /// The deepest heading level shown in tables of contents, unless the page
+    /// sets its own `toc_depth`
+    default_toc_depth: u32,
 }
 
 impl Document {
@@ -157,22 +356,47 @@ impl Document {
     ///
     /// Must be provided both the absolute path to the file, and the relative
     /// path inside the docs directory to the original file.
-    fn load(absolute_path: &Path, relative_docs_path: &Path, base_path: &str) -> Self {
+    fn load(
+        absolute_path: &Path,
+        relative_docs_path: &Path,
+        base_path: &str,
+        snippets: &Snippets,
+        variables: &BTreeMap<String, String>,
+    ) -> Self {
         let raw = fs::read_to_string(absolute_path).unwrap();
         let frontmatter =
             frontmatter::parse(&raw).expect("TODO: Print an error when frontmatter is busted");
 
-        Document::new(relative_docs_path, raw, frontmatter, base_path)
+        let mut doc = Document::new(
+            relative_docs_path,
+            raw,
+            frontmatter,
+            base_path,
+            snippets,
+            variables,
+        );
+        doc.modified = fs::metadata(absolute_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(date::Timestamp::from);
+
+        doc
     }
 
-    /// Creates a new document from its raw components
+    /// Creates a new document from its raw components, expanding any
+    /// snippets and variables it includes.
+    ///
+    /// Variables set in the document's frontmatter take precedence over the
+    /// given site-wide variables.
     fn new(
         path: &Path,
         raw: String,
         frontmatter: BTreeMap<String, String>,
         base_path: &str,
+        snippets: &Snippets,
+        variables: &BTreeMap<String, String>,
     ) -> Self {
-        let rename = if path.ends_with("README.md") {
+        let rename = if is_index_file(path) {
             Some("index".to_string())
         } else {
             None
@@ -184,21 +408,91 @@ impl Document {
             opts
         };
 
-        let markdown = doctave_markdown::parse(frontmatter::without(&raw), Some(markdown_options));
+        let mut variables = variables.clone();
+        if let Some(page_variables) = frontmatter
+            .get("variables")
+            .and_then(|v| frontmatter::nested(v))
+        {
+            variables.extend(page_variables);
+        }
+
+        let body = frontmatter::without(&raw);
+        let (expansion, snippet_error) = match snippets.expand(body, &variables) {
+            Ok(mut expansion) => {
+                // Report lines in the file, not just the content after the frontmatter
+                let frontmatter_lines = raw[..raw.len() - body.len()].matches('\n').count();
+                for var in &mut expansion.undefined {
+                    if var.snippet.is_none() {
+                        var.line += frontmatter_lines;
+                    }
+                }
+                for include in &mut expansion.broken_includes {
+                    if include.snippet.is_none() {
+                        include.line += frontmatter_lines;
+                    }
+                }
+
+                (expansion, None)
+            }
+            Err(e) => (
+                Expansion {
+                    content: body.to_string(),
+                    ..Expansion::default()
+                },
+                Some(e),
+            ),
+        };
+        let content = expansion.content;
 
-        Document {
+        let markdown = doctave_markdown::parse(
+            &callouts::expand(&tabs::expand(&code_blocks::expand(&content))),
+            Some(markdown_options),
+        );
+
+        let mut doc = Document {
             id: DOCUMENT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
             path: path.to_path_buf(),
             base_path: base_path.to_owned(),
-            raw,
+            content,
+            snippets: expansion.used,
+            snippet_error,
+            undefined_variables: expansion.undefined,
+            sources: expansion.sources,
+            broken_includes: expansion.broken_includes,
+            undefined_terms: vec![],
             markdown,
+            html: String::new(),
             rename,
             frontmatter,
-        }
+            modified: None,
+            pretty_urls: false,
+            default_toc_depth: toc::DEFAULT_DEPTH,
+        };
+        doc.render(&Callouts::default());
+
+        doc
+    }
+
+    /// Whether this document is the index page of its directory
+    fn is_index(&self) -> bool {
+        is_index_file(&self.path)
     }
 
-    fn original_file_name(&self) -> Option<&OsStr> {
-        self.path.file_name()
+    /// Whether the page is left out of the navigation, set with the `hidden`
+    /// frontmatter field. Hidden pages are still built.
+    fn is_hidden(&self) -> bool {
+        self.frontmatter.get("hidden").map(|h| h == "true") == Some(true)
+    }
+
+    /// Whether the page should be included in search results. Set with the
+    /// `search` frontmatter field, and defaults to true for pages that are
+    /// not hidden.
+    fn is_searchable(&self) -> bool {
+        match self.frontmatter.get("search").map(|s| s.as_str()) {
+            Some("true") => true,
+            Some("false") => false,
+            _ => !self.is_hidden(),
+        }
     }
 
     fn original_path(&self) -> &Path {
@@ -212,25 +506,187 @@ impl Document {
 
     /// The path to the HTML file on disk that will be generated
     fn html_path(&self) -> PathBuf {
-        // TODO(Nik): Refactor this mess to be readable
-        match self.rename {
-            None => self.path.with_file_name(&format!(
-                "{}.html",
-                self.path.file_stem().unwrap().to_str().unwrap()
-            )),
-            Some(ref rename) => self.path.with_file_name(&format!("{}.html", rename)),
+        let path = if let Some(path) = self.custom_html_path() {
+            path
+        } else {
+            // TODO(Nik): Refactor this mess to be readable
+            match self.rename {
+                None => self.path.with_file_name(&format!(
+                    "{}.html",
+                    self.path.file_stem().unwrap().to_str().unwrap()
+                )),
+                Some(ref rename) => self.path.with_file_name(&format!("{}.html", rename)),
+            }
+        };
+
+        if self.pretty_urls {
+            pretty_html_path(path)
+        } else {
+            path
+        }
+    }
+
+    /// The path to the HTML file set with the `permalink` or `slug`
+    /// frontmatter, if any.
+    ///
+    /// A permalink replaces the whole path from the root of the site, while a
+    /// slug only replaces the file name.
+    fn custom_html_path(&self) -> Option<PathBuf> {
+        if let Some(permalink) = self.frontmatter.get("permalink") {
+            Some(uri_to_html_path(permalink.trim()))
+        } else {
+            self.frontmatter
+                .get("slug")
+                .map(|slug| self.path.with_file_name(format!("{}.html", slug.trim())))
+        }
+    }
+
+    /// Applies the site-wide settings that change how the page is built.
+    fn apply_config(&mut self, config: &Config) {
+        self.pretty_urls = config.pretty_urls();
+        self.default_toc_depth = config.toc_depth();
+        self.render(config.callouts());
+    }
+
+    /// Renders the final HTML of the page, inserting tables of contents,
+    /// callouts and code block options into the HTML generated from the
+    /// Markdown.
+    fn render(&mut self, callouts: &Callouts) {
+        let html = toc::insert(&self.markdown.as_html, self.headings(), self.toc_depth());
+        let html = callouts::render(&html, callouts);
+        self.html = code_blocks::render(&html);
+    }
+
+    /// Links the references to glossary terms in the rendered HTML, and the
+    /// first occurrence of each term if `link_first` is set.
+    fn link_terms(&mut self, glossary: &Glossary, link_first: bool) {
+        let (html, undefined) = glossary.link(&self.html, link_first);
+
+        self.html = html;
+        self.undefined_terms = undefined;
+    }
+
+    /// The deepest heading level shown in tables of contents for this page
+    fn toc_depth(&self) -> u32 {
+        self.frontmatter
+            .get("toc_depth")
+            .and_then(|d| d.trim().parse().ok())
+            .unwrap_or(self.default_toc_depth)
+    }
+
+    /// Returns an error if the `toc_depth` set in the frontmatter is not a
+    /// heading level.
+    fn check_toc_depth(&self) -> Result<()> {
+        match self.frontmatter.get("toc_depth") {
+            Some(depth) if !matches!(depth.trim().parse::<u32>(), Ok(1..=6)) => {
+                Err(Error::new(format!(
+                    "Invalid toc_depth `{}` in {}. It must be a number from 1 to 6.",
+                    depth,
+                    self.path.display()
+                )))
+            }
+            _ => Ok(()),
         }
     }
 
+    /// The headings listed in the page's outline. Empty if the outline is
+    /// turned off with `toc: false`.
+    fn outline(&self) -> Vec<&Heading> {
+        if self.frontmatter.get("toc").map(|t| t == "false") == Some(true) {
+            return vec![];
+        }
+
+        self.headings()
+            .iter()
+            .filter(|h| h.level <= self.toc_depth())
+            .collect()
+    }
+
+    /// Returns an error if the `permalink` or `slug` set in the frontmatter
+    /// can't be used as a path.
+    fn check_custom_url(&self) -> Result<()> {
+        let invalid = |field: &str, value: &str| {
+            Err(Error::new(format!(
+                "Invalid {} \"{}\" in {}. {}",
+                field,
+                value,
+                self.path.display(),
+                if field == "slug" {
+                    "Slugs can't be empty or contain slashes."
+                } else {
+                    "Permalinks can't contain \"..\"."
+                }
+            )))
+        };
+
+        if let Some(permalink) = self.frontmatter.get("permalink") {
+            if permalink.split('/').any(|part| part.trim() == "..") {
+                return invalid("permalink", permalink);
+            }
+        } else if let Some(slug) = self.frontmatter.get("slug") {
+            let slug = slug.trim();
+            if slug.is_empty() || slug == ".." || slug.contains('/') || slug.contains('\\') {
+                return invalid("slug", slug);
+            }
+        }
+
+        Ok(())
+    }
+
     /// The URI path to this file.
     ///
-    /// E.g: /foo/bar.html => /foo/bar
+    /// E.g: /foo/bar.html => /foo/bar, or /foo/bar/index.html => /foo/bar/
+    /// with pretty URLs.
     fn uri_path(&self) -> String {
-        format!("{}{}", self.base_path, Link::path_to_uri(&self.html_path()))
+        let uri = Link::path_to_uri(&self.html_path());
+
+        if self.pretty_urls && !uri.is_empty() {
+            format!("{}{}/", self.base_path, uri)
+        } else {
+            format!("{}{}", self.base_path, uri)
+        }
     }
 
     fn markdown_section(&self) -> &str {
-        frontmatter::without(&self.raw)
+        &self.content
+    }
+
+    /// The Markdown content with the callout, tabs and glossary syntax left
+    /// out, for indexing the page for search.
+    fn search_text(&self) -> String {
+        glossary::to_plain_text(&callouts::to_plain_text(&tabs::to_plain_text(
+            &self.content,
+        )))
+    }
+
+    fn undefined_variables(&self) -> &[UndefinedVariable] {
+        &self.undefined_variables
+    }
+
+    fn broken_includes(&self) -> &[BrokenInclude] {
+        &self.broken_includes
+    }
+
+    fn undefined_terms(&self) -> &[String] {
+        &self.undefined_terms
+    }
+
+    /// The source files included in this document as code blocks
+    fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    /// Returns an error if the snippets included in this document could not
+    /// be expanded.
+    fn check_snippets(&self) -> Result<()> {
+        match &self.snippet_error {
+            None => Ok(()),
+            Some(e) => Err(Error::new(format!(
+                "Could not include snippets in {}:\n{}",
+                self.path.display(),
+                e
+            ))),
+        }
     }
 
     fn headings(&self) -> &[Heading] {
@@ -242,7 +698,72 @@ impl Document {
     }
 
     fn html(&self) -> &str {
-        &self.markdown.as_html
+        &self.html
+    }
+
+    /// The languages of the code blocks on this page, as tagged for Prism.
+    fn code_languages(&self) -> Vec<&str> {
+        let html = self.html();
+
+        html.match_indices("class=\"language-")
+            .filter_map(|(start, marker)| {
+                let rest = &html[start + marker.len()..];
+                rest.find('"').map(|end| &rest[..end])
+            })
+            .collect()
+    }
+
+    /// Whether this page contains mathematical notation rendered by KaTeX.
+    fn has_math(&self) -> bool {
+        self.html().contains("class=\"math\"")
+    }
+
+    /// Whether this page contains diagrams rendered by Mermaid.js.
+    fn has_mermaid(&self) -> bool {
+        self.html().contains("class=\"mermaid\"")
+    }
+
+    /// A short summary of the page, used for link previews.
+    ///
+    /// Taken from the `description` frontmatter field if present, otherwise
+    /// generated from the first paragraph of the page.
+    fn description(&self) -> Option<String> {
+        self.frontmatter
+            .get("description")
+            .cloned()
+            .or_else(|| html::first_paragraph(self.html()).map(|p| html::truncate(&p, 160)))
+    }
+
+    /// The publication date of the page, set with the `date` frontmatter field.
+    ///
+    /// Returns an error if the field is present but not a valid date.
+    fn date(&self) -> Result<Option<date::Timestamp>> {
+        self.frontmatter_date("date")
+    }
+
+    /// When the page was last changed. Taken from the `updated` frontmatter
+    /// field if present, otherwise from the git history or the file itself.
+    fn last_updated(&self) -> Result<Option<date::Timestamp>> {
+        Ok(self.frontmatter_date("updated")?.or(self.modified))
+    }
+
+    fn frontmatter_date(&self, field: &str) -> Result<Option<date::Timestamp>> {
+        match self.frontmatter.get(field) {
+            None => Ok(None),
+            Some(value) => date::Timestamp::parse(value).map(Some).ok_or_else(|| {
+                Error::new(format!(
+                    "Invalid {} `{}` in {}. Expected a date such as 2021-01-31.",
+                    field,
+                    value,
+                    self.path.display()
+                ))
+            }),
+        }
+    }
+
+    /// The image to show in link previews, set with the `image` frontmatter field.
+    fn image(&self) -> Option<&str> {
+        self.frontmatter.get("image").map(|i| i.as_ref())
     }
 
     fn title(&self) -> &str {
@@ -33,6 +33,11 @@ fn main() {
                     Arg::with_name("allow-failed-checks")
                         .long("allow-failed-checks")
                         .help("Don't return an error if there are failed checks"),
+                )
+                .arg(
+                    Arg::with_name("single-page")
+                        .long("single-page")
+                        .help("Also build all pages into a single print.html page"),
                 ),
         )
         .subcommand(
@@ -56,12 +61,37 @@ fn main() {
                         }),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Exports your documentation into a single file, such as an e-book")
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .value_name("FORMAT")
+                        .possible_values(&["epub", "json"])
+                        .required(true)
+                        .help("The format to export the documentation into"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .takes_value(true)
+                        .value_name("PATH")
+                        .help(
+                            "Where to write the exported file. \
+                             (Defaults to docs.epub or docs.json in the project root)",
+                        ),
+                ),
+        )
         .get_matches();
 
     let result = match matches.subcommand() {
         ("init", Some(cmd)) => init(cmd),
         ("build", Some(cmd)) => build(cmd),
         ("serve", Some(cmd)) => serve(cmd),
+        ("export", Some(cmd)) => export(cmd),
         _ => Ok(()),
     };
 
@@ -102,6 +132,10 @@ fn build(cmd: &ArgMatches) -> doctave::Result<()> {
         config.set_allow_failed_checks();
     }
 
+    if cmd.is_present("single-page") {
+        config.set_single_page();
+    }
+
     doctave::BuildCommand::run(config)
 }
 
@@ -124,3 +158,23 @@ fn serve(cmd: &ArgMatches) -> doctave::Result<()> {
 
     doctave::ServeCommand::run(options, config)
 }
+
+fn export(cmd: &ArgMatches) -> doctave::Result<()> {
+    let project_dir = doctave::config::project_root().unwrap_or_else(|| {
+        println!("Could not find a doctave project in this directory, or its parents.");
+        std::process::exit(1);
+    });
+
+    let mut config = doctave::Config::load(&project_dir)?;
+
+    if cmd.is_present("no-color") {
+        config.disable_colors();
+    }
+
+    let options = doctave::ExportOptions {
+        format: cmd.value_of("format").unwrap().parse()?,
+        output: cmd.value_of("output").map(std::path::PathBuf::from),
+    };
+
+    doctave::ExportCommand::run(options, config)
+}
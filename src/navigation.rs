@@ -1,5 +1,5 @@
 use crate::config::{Config, DirIncludeRule, NavRule};
-use crate::Directory;
+use crate::{Directory, Document};
 use serde::Serialize;
 
 use std::ffi::OsStr;
@@ -18,7 +18,55 @@ impl<'a> Navigation<'a> {
     pub fn build_for(&self, dir: &Directory) -> Vec<Link> {
         match &self.config.navigation() {
             None => dir.links(false),
-            Some(nav) => self.customize(nav, &dir.links(true)),
+            Some(nav) => self.customize(&self.with_custom_urls(nav, dir), &dir.links(true)),
+        }
+    }
+
+    /// Rules point to source files, while the links of pages with a `slug` or
+    /// `permalink` no longer match their file names. Rewrites the rules for
+    /// such pages to point to their output paths instead.
+    fn with_custom_urls(&self, rules: &[NavRule], root: &Directory) -> Vec<NavRule> {
+        rules
+            .iter()
+            .map(|rule| match rule {
+                NavRule::File(path) => {
+                    let doc = self
+                        .config
+                        .project_root()
+                        .join(path)
+                        .strip_prefix(self.config.docs_dir())
+                        .ok()
+                        .and_then(|p| root.find_doc(p));
+
+                    NavRule::File(self.custom_url_path(path, doc))
+                }
+                NavRule::Dir(path, dir_rule) => {
+                    let index = root
+                        .find_dir(&self.config.project_root().join(path))
+                        .map(|d| d.index());
+
+                    let dir_rule = match dir_rule {
+                        Some(DirIncludeRule::Explicit(nested)) => Some(DirIncludeRule::Explicit(
+                            self.with_custom_urls(nested, root),
+                        )),
+                        other => other.clone(),
+                    };
+
+                    NavRule::Dir(self.custom_url_path(path, index), dir_rule)
+                }
+            })
+            .collect()
+    }
+
+    fn custom_url_path(&self, path: &Path, doc: Option<&Document>) -> PathBuf {
+        match doc.and_then(|d| d.custom_html_path()) {
+            // Matching links skips the first component, which is the docs directory
+            Some(html_path) => path
+                .components()
+                .next()
+                .map(|c| Path::new(c.as_os_str()).join(&html_path))
+                .unwrap_or(html_path),
+            None => path.to_path_buf(),
         }
     }
 
@@ -26,10 +74,8 @@ impl<'a> Navigation<'a> {
     /// doctave.yaml config.
     ///
     /// Note that the config validates that any files/directories referenced
-    /// in the rules already exist, which is why we can reasonably confidently
-    /// unwrap some Nones here. The only case they would trip is if the files
-    /// got removed between the validation and building these rules, which is
-    /// a _very_ small window.
+    /// in the rules already exist. Rules that don't match a link, such as ones
+    /// pointing to hidden pages, are skipped.
     ///
     /// Note that in the case where an explicit path is provided, the link is
     /// not necessarily a direct child of its parent. It could be that links
@@ -52,15 +98,18 @@ impl<'a> Navigation<'a> {
                 rule
             };
 
+            // Hidden pages are not part of the default links, so won't be found
             match rule {
-                NavRule::File(path) => links.push(
-                    self.find_matching_link(path, &default)
-                        .expect("No matching link found"),
-                ),
+                NavRule::File(path) => {
+                    if let Some(link) = self.find_matching_link(path, &default) {
+                        links.push(link);
+                    }
+                }
                 NavRule::Dir(path, dir_rule) => {
-                    let mut index_link = self
-                        .find_matching_link(path, &default)
-                        .expect("No matching link found");
+                    let mut index_link = match self.find_matching_link(path, &default) {
+                        Some(link) => link,
+                        None => continue,
+                    };
 
                     match dir_rule {
                         // Don't include any children
@@ -95,7 +144,8 @@ impl<'a> Navigation<'a> {
 
             let doc_path = Link::path_to_uri(without_docs_part.as_path());
 
-            link_path.trim_start_matches("/") == doc_path.trim_start_matches("/")
+            // Links end with a slash when using pretty URLs
+            link_path.trim_matches('/') == doc_path.trim_start_matches("/")
         });
 
         match search_result {
@@ -175,6 +225,7 @@ mod test {
     use std::collections::BTreeMap;
     use std::path::Path;
 
+    use crate::snippets::Snippets;
     use crate::Document;
 
     fn page(path: &str, name: &str, base_path: Option<&str>) -> Document {
@@ -186,6 +237,8 @@ mod test {
             "Not important".to_string(),
             frontmatter,
             base_path.unwrap_or("/"),
+            &Snippets::default(),
+            &BTreeMap::new(),
         )
     }
 
@@ -195,6 +248,103 @@ mod test {
         Config::from_yaml_str(&Path::new("project"), conf).unwrap()
     }
 
+    #[test]
+    fn hidden_pages() {
+        let config = config(None);
+
+        let mut hidden = page("campaign.md", "Campaign", None);
+        hidden
+            .frontmatter
+            .insert("hidden".to_string(), "true".to_string());
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![
+                page("README.md", "Getting Started", None),
+                page("one.md", "One", None),
+                hidden,
+            ],
+            dirs: vec![],
+        };
+
+        let one = Link {
+            path: String::from("/one"),
+            title: String::from("One"),
+            children: vec![],
+        };
+
+        let navigation = Navigation::new(&config);
+        assert_eq!(navigation.build_for(&root), vec![one.clone()]);
+
+        let rules = vec![
+            NavRule::File(PathBuf::from("docs/campaign.md")),
+            NavRule::File(PathBuf::from("docs/one.md")),
+        ];
+
+        assert_eq!(navigation.customize(&rules, &root.links(true)), vec![one]);
+    }
+
+    #[test]
+    fn custom_urls() {
+        let config = config(None);
+
+        let mut slug = page("one.md", "One", None);
+        slug.frontmatter
+            .insert("slug".to_string(), "first".to_string());
+
+        let mut permalink = page("child/two.md", "Two", None);
+        permalink
+            .frontmatter
+            .insert("permalink".to_string(), "/guides/second/".to_string());
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![page("README.md", "Getting Started", None), slug],
+            dirs: vec![Directory {
+                path: config.docs_dir().join("child"),
+                docs: vec![page("child/README.md", "Child", None), permalink],
+                dirs: vec![],
+            }],
+        };
+
+        let first = Link {
+            path: String::from("/first"),
+            title: String::from("One"),
+            children: vec![],
+        };
+        let second = Link {
+            path: String::from("/guides/second"),
+            title: String::from("Two"),
+            children: vec![],
+        };
+
+        let navigation = Navigation::new(&config);
+        assert_eq!(
+            navigation.build_for(&root),
+            vec![
+                Link {
+                    path: String::from("/child"),
+                    title: String::from("Child"),
+                    children: vec![second.clone()],
+                },
+                first.clone(),
+            ]
+        );
+
+        let rules = vec![
+            NavRule::File(PathBuf::from("docs/child/two.md")),
+            NavRule::File(PathBuf::from("docs/one.md")),
+        ];
+
+        assert_eq!(
+            navigation.customize(
+                &navigation.with_custom_urls(&rules, &root),
+                &root.links(true)
+            ),
+            vec![second, first]
+        );
+    }
+
     #[test]
     fn basic() {
         let config = config(None);
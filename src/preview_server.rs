@@ -7,7 +7,9 @@ use ascii::AsciiString;
 use bunt::termcolor::{ColorChoice, StandardStream};
 use tiny_http::{Request, Response, Server};
 
+use crate::redirects::REDIRECTS_FILE;
 use crate::site::{Site, SiteBackend};
+use crate::site_generator::NOT_FOUND_PAGE;
 
 pub struct PreviewServer<B: SiteBackend> {
     color: bool,
@@ -57,27 +59,60 @@ impl<B: SiteBackend> PreviewServer<B> {
 }
 
 fn handle_request<B: SiteBackend>(request: Request, site: &Site<B>) {
-    let result = {
-        let uri = request.url().parse::<http::Uri>().unwrap();
+    let uri = request.url().parse::<http::Uri>().unwrap();
 
-        let path = PathBuf::from(uri.path());
-
-        match resolve_file(&path, &site)
-            .map(|p| (read_file(site, &p), content_type_for(p.extension())))
-        {
-            Some((data, None)) => request.respond(Response::from_data(data).with_status_code(200)),
-            Some((data, Some(content_type))) => {
-                request.respond(Response::from_data(data).with_status_code(200).with_header(
-                    tiny_http::Header {
-                        field: "Content-Type".parse().unwrap(),
-                        value: AsciiString::from_ascii(content_type).unwrap(),
-                    },
-                ))
-            }
-            None => request.respond(Response::new_empty(tiny_http::StatusCode(404))),
+    let result = match reply_for(uri.path(), site) {
+        Reply::Redirect(location) => request.respond(
+            Response::new_empty(tiny_http::StatusCode(301)).with_header(tiny_http::Header {
+                field: "Location".parse().unwrap(),
+                value: AsciiString::from_ascii(location).unwrap(),
+            }),
+        ),
+        Reply::File(data, None) => request.respond(Response::from_data(data).with_status_code(200)),
+        Reply::File(data, Some(content_type)) => {
+            request.respond(Response::from_data(data).with_status_code(200).with_header(
+                tiny_http::Header {
+                    field: "Content-Type".parse().unwrap(),
+                    value: AsciiString::from_ascii(content_type).unwrap(),
+                },
+            ))
         }
+        Reply::NotFound(Some(data)) => {
+            request.respond(Response::from_data(data).with_status_code(404).with_header(
+                tiny_http::Header {
+                    field: "Content-Type".parse().unwrap(),
+                    value: AsciiString::from_ascii("text/html; charset=utf8").unwrap(),
+                },
+            ))
+        }
+        Reply::NotFound(None) => request.respond(Response::new_empty(tiny_http::StatusCode(404))),
     };
 
+    respond(result)
+}
+
+/// How to respond to a request for a path
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Redirect(String),
+    /// The file's content and content type
+    File(Vec<u8>, Option<&'static str>),
+    /// The content of the 404 page, if one was built
+    NotFound(Option<Vec<u8>>),
+}
+
+fn reply_for<B: SiteBackend>(uri_path: &str, site: &Site<B>) -> Reply {
+    if let Some(location) = find_redirect(uri_path, site) {
+        return Reply::Redirect(location);
+    }
+
+    match resolve_file(Path::new(uri_path), site) {
+        Some(path) => Reply::File(read_file(site, &path), content_type_for(path.extension())),
+        None => Reply::NotFound(site.backend.read_path(Path::new(NOT_FOUND_PAGE))),
+    }
+}
+
+fn respond(result: std::io::Result<()>) {
     match result {
         Ok(()) => {}
         Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
@@ -85,6 +120,34 @@ fn handle_request<B: SiteBackend>(request: Request, site: &Site<B>) {
     }
 }
 
+/// Looks up the path in the `_redirects` file generated for the site,
+/// returning the location to redirect to.
+fn find_redirect<B: SiteBackend>(path: &str, site: &Site<B>) -> Option<String> {
+    let listing = site.backend.read_path(Path::new(REDIRECTS_FILE))?;
+    let listing = String::from_utf8_lossy(&listing);
+    let path = normalize(path);
+
+    listing.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let from = parts.next()?;
+        let to = parts.next()?;
+
+        if normalize(from) == path && to.is_ascii() {
+            Some(to.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Treats `/foo`, `/foo/` and `/foo.html` as the same path
+fn normalize(path: &str) -> &str {
+    match path.trim_end_matches('/').trim_end_matches(".html") {
+        "" => "/",
+        p => p,
+    }
+}
+
 /// Uses some basic logic for resolving a path into the correct file.
 /// This means resolving to an index.html from the root of the directory,
 /// trying with .html extensions with needed, etc.
@@ -144,6 +207,8 @@ fn content_type_for(extension: Option<&OsStr>) -> Option<&'static str> {
             Some("htm") => Some("text/html; charset=utf8"),
             Some("css") => Some("text/css"),
             Some("js") => Some("text/javascript"),
+            Some("json") => Some("application/json"),
+            Some("xml") => Some("application/xml"),
             Some("pdf") => Some("application/pdf"),
             Some("zip") => Some("application/zip"),
             Some("jpg") => Some("image/jpeg"),
@@ -156,3 +221,113 @@ fn content_type_for(extension: Option<&OsStr>) -> Option<&'static str> {
         None => None,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+    use crate::snippets::Snippets;
+    use crate::{Directory, Document};
+    use std::collections::BTreeMap;
+
+    fn page(path: &str, content: &str, pretty_urls: bool) -> Document {
+        let mut doc = Document::new(
+            Path::new(path),
+            content.to_string(),
+            BTreeMap::new(),
+            "/",
+            &Snippets::default(),
+            &BTreeMap::new(),
+        );
+        doc.pretty_urls = pretty_urls;
+        doc
+    }
+
+    fn site(pretty_urls: bool) -> Site<crate::site::InMemorySite> {
+        let config = Config::from_yaml_str(
+            Path::new("project"),
+            &format!("---\ntitle: My project\npretty_urls: {}\n", pretty_urls),
+        )
+        .unwrap();
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![
+                page("README.md", "# Home", pretty_urls),
+                page("one.md", "# One", pretty_urls),
+            ],
+            dirs: vec![Directory {
+                path: config.docs_dir().join("child"),
+                docs: vec![
+                    page("child/README.md", "# Child", pretty_urls),
+                    page("child/two.md", "# Two", pretty_urls),
+                ],
+                dirs: vec![],
+            }],
+        };
+
+        let site = Site::with_root(root, config);
+        site.build().unwrap();
+        site
+    }
+
+    fn served_heading<B: SiteBackend>(path: &str, site: &Site<B>) -> Option<String> {
+        match reply_for(path, site) {
+            Reply::File(data, Some("text/html; charset=utf8")) => {
+                let html = String::from_utf8(data).unwrap();
+                let start = html.find("<h1")?;
+                let end = html[start..].find("</h1>")?;
+
+                Some(html[start..start + end].rsplit('>').next()?.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn pretty_urls_are_built_into_directories() {
+        let site = site(true);
+
+        assert!(site.backend.has_file(Path::new("one/index.html")));
+        assert!(site.backend.has_file(Path::new("child/two/index.html")));
+        assert!(!site.backend.has_file(Path::new("one.html")));
+    }
+
+    #[test]
+    fn both_layouts_serve_the_same_pages() {
+        let flat = site(false);
+        let pretty = site(true);
+
+        for (path, heading) in &[
+            ("/", "Home"),
+            ("/one", "One"),
+            ("/one/", "One"),
+            ("/child", "Child"),
+            ("/child/", "Child"),
+            ("/child/two", "Two"),
+            ("/child/two/", "Two"),
+        ] {
+            assert_eq!(
+                served_heading(path, &flat).as_deref(),
+                Some(*heading),
+                "flat layout, {}",
+                path
+            );
+            assert_eq!(
+                served_heading(path, &pretty).as_deref(),
+                Some(*heading),
+                "pretty layout, {}",
+                path
+            );
+        }
+
+        assert!(matches!(
+            reply_for("/three", &flat),
+            Reply::NotFound(Some(_))
+        ));
+        assert!(matches!(
+            reply_for("/three", &pretty),
+            Reply::NotFound(Some(_))
+        ));
+    }
+}
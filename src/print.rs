@@ -0,0 +1,251 @@
+//! Combines every page of the site into a single document, which can be
+//! printed or handed over as a whole.
+//!
+//! Heading anchors are prefixed with an anchor for their page so that they
+//! stay unique, and links between pages point to the page's section instead.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::html;
+use crate::navigation::Link;
+use crate::{Directory, Document};
+
+pub static PRINT_PAGE: &str = "print.html";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Section {
+    pub title: String,
+    pub anchor: String,
+    /// How deeply the page is nested in the navigation
+    pub level: usize,
+    pub content: String,
+}
+
+/// The pages in the navigation, in order, starting with the root index page.
+pub fn sections(root: &Directory, navigation: &[Link], root_title: &str) -> Vec<Section> {
+    let ordered = pages(root, navigation);
+    let anchors = anchors(&ordered);
+
+    ordered
+        .into_iter()
+        .enumerate()
+        .map(|(i, (doc, level))| {
+            let anchor = anchor_for(doc);
+
+            Section {
+                title: if i == 0 {
+                    root_title.to_string()
+                } else {
+                    doc.title().to_string()
+                },
+                content: rewrite(doc.html(), &anchor, &anchors, |_| String::new()),
+                anchor,
+                level,
+            }
+        })
+        .collect()
+}
+
+/// The documents in the navigation, in order, along with how deeply they are
+/// nested. Starts with the root index page, and lists each page once.
+pub fn pages<'a>(root: &'a Directory, navigation: &[Link]) -> Vec<(&'a Document, usize)> {
+    let mut docs = HashMap::new();
+    collect_docs(root, &mut docs);
+
+    let mut ordered = vec![(root.index(), 0)];
+    let mut seen = HashSet::new();
+    seen.insert(normalize(&root.index().uri_path()));
+    in_navigation_order(navigation, 0, &docs, &mut seen, &mut ordered);
+
+    ordered
+}
+
+/// The anchor of each page, keyed by its normalized URI path.
+pub fn anchors(pages: &[(&Document, usize)]) -> HashMap<String, String> {
+    pages
+        .iter()
+        .map(|(doc, _)| (normalize(&doc.uri_path()), anchor_for(doc)))
+        .collect()
+}
+
+fn collect_docs<'a>(dir: &'a Directory, docs: &mut HashMap<String, &'a Document>) {
+    for doc in &dir.docs {
+        docs.insert(normalize(&doc.uri_path()), doc);
+    }
+
+    for child in &dir.dirs {
+        collect_docs(child, docs);
+    }
+}
+
+fn in_navigation_order<'a>(
+    links: &[Link],
+    level: usize,
+    docs: &HashMap<String, &'a Document>,
+    seen: &mut HashSet<String>,
+    ordered: &mut Vec<(&'a Document, usize)>,
+) {
+    for link in links {
+        let key = normalize(&link.path);
+
+        if let Some(doc) = docs.get(&key) {
+            if seen.insert(key) {
+                ordered.push((doc, level));
+            }
+        }
+
+        in_navigation_order(&link.children, level + 1, docs, seen, ordered);
+    }
+}
+
+/// The anchor of a page's section, based on its URI path.
+///
+/// E.g: /guides/setup => guides-setup
+pub fn anchor_for(doc: &Document) -> String {
+    let path = doc
+        .uri_path()
+        .strip_prefix(&doc.base_path)
+        .map(|p| p.to_string())
+        .unwrap_or_else(|| doc.uri_path());
+    let path = path.trim_matches('/');
+
+    if path.is_empty() {
+        "index".to_string()
+    } else {
+        path.replace('/', "-")
+    }
+}
+
+/// Treats `/foo`, `/foo/`, `/foo.html` and `/foo/index.html` as the same page
+pub fn normalize(uri: &str) -> String {
+    uri.trim_end_matches(".html")
+        .trim_end_matches("/index")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Prefixes the ids in the page's HTML with the page's anchor, and points
+/// links to other pages in the document to their sections.
+///
+/// The `file_for` function returns the file a section lives in, given its
+/// anchor. It should return an empty string when all sections are on the
+/// same page.
+pub fn rewrite<F>(
+    html: &str,
+    anchor: &str,
+    anchors: &HashMap<String, String>,
+    file_for: F,
+) -> String
+where
+    F: Fn(&str) -> String,
+{
+    let html = html::rewrite_attributes(html, &["id", "aria-controls", "aria-labelledby"], |id| {
+        Some(format!("{}--{}", anchor, id))
+    });
+
+    html::rewrite_attributes(&html, &["href"], |url| {
+        let (path, fragment) = match url.find('#') {
+            Some(i) => (&url[..i], Some(&url[i + 1..])),
+            None => (url, None),
+        };
+
+        let target = if path.is_empty() {
+            anchor
+        } else {
+            anchors.get(&normalize(path))?
+        };
+
+        match fragment {
+            Some(fragment) if !fragment.is_empty() => {
+                Some(format!("{}#{}--{}", file_for(target), target, fragment))
+            }
+            _ => Some(format!("{}#{}", file_for(target), target)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+    use crate::navigation::Navigation;
+    use crate::snippets::Snippets;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    fn page(path: &str, name: &str, content: &str) -> Document {
+        let mut frontmatter = BTreeMap::new();
+        frontmatter.insert("title".to_string(), name.to_string());
+
+        Document::new(
+            Path::new(path),
+            content.to_string(),
+            frontmatter,
+            "/",
+            &Snippets::default(),
+            &BTreeMap::new(),
+        )
+    }
+
+    #[test]
+    fn pages_in_navigation_order() {
+        let config = Config::from_yaml_str(Path::new("project"), "---\ntitle: Print\n").unwrap();
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![
+                page("README.md", "Home", "# Home"),
+                page("b.md", "B", "# B"),
+                page("a.md", "A", "# A"),
+            ],
+            dirs: vec![Directory {
+                path: config.docs_dir().join("child"),
+                docs: vec![
+                    page("child/README.md", "Child", "# Child"),
+                    page("child/c.md", "C", "# C"),
+                ],
+                dirs: vec![],
+            }],
+        };
+
+        let navigation = Navigation::new(&config).build_for(&root);
+        let sections = sections(&root, &navigation, "Print");
+
+        assert_eq!(
+            sections
+                .iter()
+                .map(|s| (s.title.as_str(), s.anchor.as_str(), s.level))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Print", "index", 0),
+                ("A", "a", 0),
+                ("B", "b", 0),
+                ("Child", "child", 0),
+                ("C", "child-c", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn rewrites_anchors_and_links() {
+        let mut anchors = HashMap::new();
+        anchors.insert("/guides/setup".to_string(), "guides-setup".to_string());
+
+        let html = "<h2 id=\"usage\">Usage</h2>\
+                    <a href=\"#usage\">Here</a>\
+                    <a href=\"/guides/setup#install\">Setup</a>\
+                    <a href=\"/guides/setup/\">Setup</a>\
+                    <a href=\"https://example.com\">Elsewhere</a>";
+
+        assert_eq!(
+            rewrite(html, "intro", &anchors, |_| String::new()),
+            "<h2 id=\"intro--usage\">Usage</h2>\
+             <a href=\"#intro--usage\">Here</a>\
+             <a href=\"#guides-setup--install\">Setup</a>\
+             <a href=\"#guides-setup\">Setup</a>\
+             <a href=\"https://example.com\">Elsewhere</a>"
+        );
+    }
+}
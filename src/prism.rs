@@ -0,0 +1,268 @@
+//! Knowledge about Prism's grammar files, used to only ship the grammars a
+//! site actually needs.
+//!
+//! The tables below mirror the ones in the autoloader plugin bundled in
+//! `assets/prism.min.js`, and have to be kept in sync with it when Prism is
+//! updated.
+
+use std::collections::BTreeSet;
+
+/// Grammars that have to be loaded before the given grammar can be used.
+static DEPENDENCIES: &[(&str, &[&str])] = &[
+    ("javascript", &["clike"]),
+    ("actionscript", &["javascript"]),
+    ("apex", &["clike", "sql"]),
+    ("arduino", &["cpp"]),
+    ("aspnet", &["markup", "csharp"]),
+    ("birb", &["clike"]),
+    ("bison", &["c"]),
+    ("c", &["clike"]),
+    ("csharp", &["clike"]),
+    ("cpp", &["c"]),
+    ("cfscript", &["clike"]),
+    ("chaiscript", &["clike", "cpp"]),
+    ("coffeescript", &["javascript"]),
+    ("crystal", &["ruby"]),
+    ("css-extras", &["css"]),
+    ("d", &["clike"]),
+    ("dart", &["clike"]),
+    ("django", &["markup-templating"]),
+    ("ejs", &["javascript", "markup-templating"]),
+    ("etlua", &["lua", "markup-templating"]),
+    ("erb", &["ruby", "markup-templating"]),
+    ("fsharp", &["clike"]),
+    ("firestore-security-rules", &["clike"]),
+    ("flow", &["javascript"]),
+    ("ftl", &["markup-templating"]),
+    ("gml", &["clike"]),
+    ("glsl", &["c"]),
+    ("go", &["clike"]),
+    ("groovy", &["clike"]),
+    ("haml", &["ruby"]),
+    ("handlebars", &["markup-templating"]),
+    ("haxe", &["clike"]),
+    ("hlsl", &["c"]),
+    ("idris", &["haskell"]),
+    ("java", &["clike"]),
+    ("javadoc", &["markup", "java", "javadoclike"]),
+    ("jolie", &["clike"]),
+    ("jsdoc", &["javascript", "javadoclike", "typescript"]),
+    ("js-extras", &["javascript"]),
+    ("json5", &["json"]),
+    ("jsonp", &["json"]),
+    ("js-templates", &["javascript"]),
+    ("kotlin", &["clike"]),
+    ("latte", &["clike", "markup-templating", "php"]),
+    ("less", &["css"]),
+    ("lilypond", &["scheme"]),
+    ("liquid", &["markup-templating"]),
+    ("markdown", &["markup"]),
+    ("markup-templating", &["markup"]),
+    ("mongodb", &["javascript"]),
+    ("n4js", &["javascript"]),
+    ("objectivec", &["c"]),
+    ("opencl", &["c"]),
+    ("parser", &["markup"]),
+    ("php", &["markup-templating"]),
+    ("phpdoc", &["php", "javadoclike"]),
+    ("php-extras", &["php"]),
+    ("plsql", &["sql"]),
+    ("processing", &["clike"]),
+    ("protobuf", &["clike"]),
+    ("pug", &["markup", "javascript"]),
+    ("purebasic", &["clike"]),
+    ("purescript", &["haskell"]),
+    ("qsharp", &["clike"]),
+    ("qml", &["javascript"]),
+    ("qore", &["clike"]),
+    ("racket", &["scheme"]),
+    ("cshtml", &["markup", "csharp"]),
+    ("jsx", &["markup", "javascript"]),
+    ("tsx", &["jsx", "typescript"]),
+    ("reason", &["clike"]),
+    ("ruby", &["clike"]),
+    ("sass", &["css"]),
+    ("scss", &["css"]),
+    ("scala", &["java"]),
+    ("shell-session", &["bash"]),
+    ("smarty", &["markup-templating"]),
+    ("solidity", &["clike"]),
+    ("soy", &["markup-templating"]),
+    ("sparql", &["turtle"]),
+    ("sqf", &["clike"]),
+    ("squirrel", &["clike"]),
+    ("t4-cs", &["t4-templating", "csharp"]),
+    ("t4-vb", &["t4-templating", "vbnet"]),
+    ("tap", &["yaml"]),
+    ("tt2", &["clike", "markup-templating"]),
+    ("textile", &["markup"]),
+    ("twig", &["markup-templating"]),
+    ("typescript", &["javascript"]),
+    ("v", &["clike"]),
+    ("vala", &["clike"]),
+    ("vbnet", &["basic"]),
+    ("velocity", &["markup"]),
+    ("wiki", &["markup"]),
+    ("xeora", &["markup"]),
+    ("xml-doc", &["markup"]),
+    ("xquery", &["markup"]),
+];
+
+/// Alternative names for languages, mapping to the name of their grammar.
+static ALIASES: &[(&str, &str)] = &[
+    ("html", "markup"),
+    ("xml", "markup"),
+    ("svg", "markup"),
+    ("mathml", "markup"),
+    ("ssml", "markup"),
+    ("atom", "markup"),
+    ("rss", "markup"),
+    ("js", "javascript"),
+    ("g4", "antlr4"),
+    ("ino", "arduino"),
+    ("adoc", "asciidoc"),
+    ("avs", "avisynth"),
+    ("avdl", "avro-idl"),
+    ("shell", "bash"),
+    ("shortcode", "bbcode"),
+    ("rbnf", "bnf"),
+    ("oscript", "bsl"),
+    ("cs", "csharp"),
+    ("dotnet", "csharp"),
+    ("cfc", "cfscript"),
+    ("coffee", "coffeescript"),
+    ("conc", "concurnas"),
+    ("jinja2", "django"),
+    ("dns-zone", "dns-zone-file"),
+    ("dockerfile", "docker"),
+    ("gv", "dot"),
+    ("eta", "ejs"),
+    ("xlsx", "excel-formula"),
+    ("xls", "excel-formula"),
+    ("gamemakerlanguage", "gml"),
+    ("gni", "gn"),
+    ("go-mod", "go-module"),
+    ("hbs", "handlebars"),
+    ("hs", "haskell"),
+    ("idr", "idris"),
+    ("gitignore", "ignore"),
+    ("hgignore", "ignore"),
+    ("npmignore", "ignore"),
+    ("webmanifest", "json"),
+    ("kt", "kotlin"),
+    ("kts", "kotlin"),
+    ("kum", "kumir"),
+    ("tex", "latex"),
+    ("context", "latex"),
+    ("ly", "lilypond"),
+    ("emacs", "lisp"),
+    ("elisp", "lisp"),
+    ("emacs-lisp", "lisp"),
+    ("md", "markdown"),
+    ("moon", "moonscript"),
+    ("n4jsd", "n4js"),
+    ("nani", "naniscript"),
+    ("objc", "objectivec"),
+    ("qasm", "openqasm"),
+    ("objectpascal", "pascal"),
+    ("px", "pcaxis"),
+    ("pcode", "peoplecode"),
+    ("pq", "powerquery"),
+    ("mscript", "powerquery"),
+    ("pbfasm", "purebasic"),
+    ("purs", "purescript"),
+    ("py", "python"),
+    ("qs", "qsharp"),
+    ("rkt", "racket"),
+    ("razor", "cshtml"),
+    ("rpy", "renpy"),
+    ("robot", "robotframework"),
+    ("rb", "ruby"),
+    ("sh-session", "shell-session"),
+    ("shellsession", "shell-session"),
+    ("smlnj", "sml"),
+    ("sol", "solidity"),
+    ("sln", "solution-file"),
+    ("rq", "sparql"),
+    ("t4", "t4-cs"),
+    ("trickle", "tremor"),
+    ("troy", "tremor"),
+    ("trig", "turtle"),
+    ("ts", "typescript"),
+    ("tsconfig", "typoscript"),
+    ("uscript", "unrealscript"),
+    ("uc", "unrealscript"),
+    ("url", "uri"),
+    ("vb", "visual-basic"),
+    ("vba", "visual-basic"),
+    ("webidl", "web-idl"),
+    ("mathematica", "wolfram"),
+    ("nb", "wolfram"),
+    ("wl", "wolfram"),
+    ("xeoracube", "xeora"),
+    ("yml", "yaml"),
+];
+
+/// Resolves the names of the grammars needed to highlight the given
+/// languages, including any grammars they depend on.
+///
+/// Languages may be referred to by any of their aliases. The returned names
+/// correspond to files named `prism-<name>.min.js`.
+pub fn grammars_for<'a, I>(languages: I) -> BTreeSet<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut grammars = BTreeSet::new();
+    let mut pending = languages
+        .into_iter()
+        .map(|l| canonical_name(l).to_string())
+        .collect::<Vec<_>>();
+
+    while let Some(grammar) = pending.pop() {
+        if let Some((_, deps)) = DEPENDENCIES.iter().find(|(name, _)| *name == grammar) {
+            pending.extend(deps.iter().map(|d| d.to_string()));
+        }
+
+        grammars.insert(grammar);
+    }
+
+    grammars
+}
+
+fn canonical_name(language: &str) -> &str {
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == language)
+        .map(|(_, name)| *name)
+        .unwrap_or(language)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn includes_the_language_itself() {
+        let grammars = grammars_for(vec!["elixir"]);
+
+        assert_eq!(grammars.into_iter().collect::<Vec<_>>(), vec!["elixir"]);
+    }
+
+    #[test]
+    fn resolves_aliases() {
+        let grammars = grammars_for(vec!["rb"]);
+
+        assert!(grammars.contains("ruby"));
+        assert!(!grammars.contains("rb"));
+    }
+
+    #[test]
+    fn resolves_dependencies_recursively() {
+        let grammars = grammars_for(vec!["arduino"]);
+
+        assert_eq!(
+            grammars.into_iter().collect::<Vec<_>>(),
+            vec!["arduino", "c", "clike", "cpp"]
+        );
+    }
+}
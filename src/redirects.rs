@@ -0,0 +1,115 @@
+//! Redirects from old URLs to the pages that replaced them.
+//!
+//! Redirects are set with the `redirects` map in doctave.yaml, or with the
+//! `aliases` frontmatter field of the page being redirected to.
+
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::frontmatter;
+use crate::Directory;
+
+/// Name of the file listing all redirects, in the format Netlify and other
+/// hosts understand.
+pub static REDIRECTS_FILE: &str = "_redirects";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirect {
+    /// The URI path to redirect from, including the base path
+    pub from: String,
+    /// The URI path, including the base path, or full URL to redirect to
+    pub to: String,
+    /// The file the redirect was set in
+    pub source: PathBuf,
+}
+
+impl Redirect {
+    /// Whether the redirect points to a page on this site
+    pub fn is_local(&self) -> bool {
+        !self.to.contains("://")
+    }
+
+    /// The path of the HTML file that performs the redirect, relative to the
+    /// output directory.
+    pub fn stub_path(&self, base_path: &str) -> PathBuf {
+        crate::uri_to_html_path(self.from.strip_prefix(base_path).unwrap_or(&self.from))
+    }
+}
+
+/// Collects the redirects from the config and every page's aliases.
+pub fn find(config: &Config, root: &Directory) -> Vec<Redirect> {
+    let mut redirects = config
+        .redirects()
+        .iter()
+        .map(|(from, to)| Redirect {
+            from: with_base_path(config, from),
+            to: if to.contains("://") {
+                to.clone()
+            } else {
+                with_base_path(config, to)
+            },
+            source: PathBuf::from("doctave.yaml"),
+        })
+        .collect::<Vec<_>>();
+
+    find_aliases(config, root, &mut redirects);
+
+    redirects
+}
+
+fn find_aliases(config: &Config, dir: &Directory, redirects: &mut Vec<Redirect>) {
+    for doc in &dir.docs {
+        if let Some(aliases) = doc.frontmatter.get("aliases") {
+            for alias in frontmatter::list(aliases) {
+                redirects.push(Redirect {
+                    from: with_base_path(config, &alias),
+                    to: doc.uri_path(),
+                    source: doc.original_path().to_path_buf(),
+                });
+            }
+        }
+    }
+
+    for child in &dir.dirs {
+        find_aliases(config, child, redirects);
+    }
+}
+
+fn with_base_path(config: &Config, path: &str) -> String {
+    format!("{}{}", config.base_path(), path.trim_start_matches('/'))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn redirect(from: &str, to: &str) -> Redirect {
+        Redirect {
+            from: from.to_string(),
+            to: to.to_string(),
+            source: PathBuf::from("doctave.yaml"),
+        }
+    }
+
+    #[test]
+    fn stub_paths() {
+        assert_eq!(
+            redirect("/docs/old/page", "/docs/new").stub_path("/docs/"),
+            PathBuf::from("old/page.html")
+        );
+        assert_eq!(
+            redirect("/docs/old/", "/docs/new").stub_path("/docs/"),
+            PathBuf::from("old/index.html")
+        );
+        assert_eq!(
+            redirect("/old.html", "/new").stub_path("/"),
+            PathBuf::from("old.html")
+        );
+    }
+
+    #[test]
+    fn local_and_remote_targets() {
+        assert!(redirect("/old", "/new").is_local());
+        assert!(!redirect("/old", "https://example.com/new").is_local());
+    }
+}
@@ -0,0 +1,196 @@
+//! Rewrites the links in generated pages to be relative to the page linking to
+//! them, so that a site can be browsed from any path, or straight from disk.
+//!
+//! Since browsing from disk doesn't resolve `/foo` to `foo.html` or
+//! `foo/index.html`, links are pointed to the HTML files themselves.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::html;
+use crate::Directory;
+
+#[derive(Debug, Clone)]
+pub struct RelativeLinks {
+    base_path: String,
+    /// The HTML files of all pages, relative to the output directory
+    pages: HashSet<String>,
+}
+
+impl RelativeLinks {
+    pub fn for_directory(base_path: &str, root: &Directory) -> Self {
+        let mut pages = HashSet::new();
+        collect_pages(root, &mut pages);
+
+        RelativeLinks {
+            base_path: base_path.to_string(),
+            pages,
+        }
+    }
+
+    /// Rewrites the links in the HTML of the page at the given path, relative
+    /// to the output directory.
+    pub fn rewrite(&self, html: &str, page: &Path) -> String {
+        html::rewrite_urls(html, |url| self.relative_url(url, page))
+    }
+
+    /// The URL relative to the given page, if it points to somewhere on the
+    /// site.
+    pub fn relative_url(&self, url: &str, page: &Path) -> Option<String> {
+        self.file_for(url)
+            .map(|file| format!("{}{}", Self::root_for(page), file))
+    }
+
+    /// Rewrites the links in the HTML to point to the HTML files from the base
+    /// path, for pages such as `404.html` that are served at any URL.
+    pub fn rewrite_absolute(&self, html: &str) -> String {
+        html::rewrite_urls(html, |url| self.absolute_url(url))
+    }
+
+    /// The URL of the file the URL points to, starting from the base path, if
+    /// it points to somewhere on the site.
+    pub fn absolute_url(&self, url: &str) -> Option<String> {
+        self.file_for(url)
+            .map(|file| format!("{}{}", self.base_path, file))
+    }
+
+    /// The file the URL points to, relative to the root of the site, along
+    /// with any query string or fragment. Returns `None` for URLs that don't
+    /// point to a path on the site.
+    pub fn file_for(&self, url: &str) -> Option<String> {
+        if url.starts_with("//") {
+            return None;
+        }
+
+        let path = url.strip_prefix(&self.base_path).or_else(|| {
+            // The root page's URL is the base path without the trailing slash
+            if url.starts_with('/') && format!("{}/", url) == self.base_path {
+                Some("")
+            } else {
+                None
+            }
+        })?;
+
+        let suffix_start = path.find(&['?', '#'][..]).unwrap_or(path.len());
+        let (path, suffix) = path.split_at(suffix_start);
+
+        let file = if path.is_empty() || path.ends_with('/') {
+            format!("{}index.html", path)
+        } else if self.pages.contains(path) {
+            path.to_string()
+        } else if self.pages.contains(&format!("{}.html", path)) {
+            format!("{}.html", path)
+        } else if self.pages.contains(&format!("{}/index.html", path)) {
+            format!("{}/index.html", path)
+        } else {
+            path.to_string()
+        };
+
+        Some(format!("{}{}", file, suffix))
+    }
+
+    /// The relative path from the page to the root of the site, such as
+    /// `../../` for `foo/bar/index.html`. Empty for pages at the root.
+    pub fn root_for(page: &Path) -> String {
+        let depth = page.parent().map(|p| p.components().count()).unwrap_or(0);
+
+        "../".repeat(depth)
+    }
+}
+
+fn collect_pages(dir: &Directory, pages: &mut HashSet<String>) {
+    for doc in &dir.docs {
+        pages.insert(to_url_path(&doc.html_path()));
+    }
+
+    for child in &dir.dirs {
+        collect_pages(child, pages);
+    }
+}
+
+/// Joins the path with forward slashes, which URLs use on all platforms
+pub fn to_url_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn links(base_path: &str, pages: &[&str]) -> RelativeLinks {
+        RelativeLinks {
+            base_path: base_path.to_string(),
+            pages: pages.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn resolves_urls_to_files() {
+        let links = links("/docs/", &["index.html", "one.html", "child/index.html"]);
+
+        assert_eq!(links.file_for("/docs/"), Some("index.html".to_string()));
+        assert_eq!(links.file_for("/docs"), Some("index.html".to_string()));
+        assert_eq!(links.file_for("/docs/one"), Some("one.html".to_string()));
+        assert_eq!(
+            links.file_for("/docs/one#usage"),
+            Some("one.html#usage".to_string())
+        );
+        assert_eq!(
+            links.file_for("/docs/child"),
+            Some("child/index.html".to_string())
+        );
+        assert_eq!(
+            links.file_for("/docs/assets/app.js?v=1"),
+            Some("assets/app.js?v=1".to_string())
+        );
+        assert_eq!(links.file_for("/other"), None);
+        assert_eq!(links.file_for("https://example.com/docs/one"), None);
+        assert_eq!(links.file_for("#top"), None);
+    }
+
+    #[test]
+    fn makes_urls_relative_to_the_page() {
+        let links = links("/", &["index.html", "one.html", "child/two/index.html"]);
+
+        assert_eq!(
+            links.relative_url("/one", Path::new("index.html")),
+            Some("one.html".to_string())
+        );
+        assert_eq!(
+            links.relative_url("/", Path::new("child/two/index.html")),
+            Some("../../index.html".to_string())
+        );
+        assert_eq!(
+            links.relative_url("/child/two/", Path::new("one.html")),
+            Some("child/two/index.html".to_string())
+        );
+    }
+
+    #[test]
+    fn makes_urls_absolute() {
+        let links = links("/docs/", &["index.html", "one.html", "child/index.html"]);
+
+        assert_eq!(
+            links.absolute_url("/docs/one#usage"),
+            Some("/docs/one.html#usage".to_string())
+        );
+        assert_eq!(
+            links.absolute_url("/docs/child/"),
+            Some("/docs/child/index.html".to_string())
+        );
+        assert_eq!(links.absolute_url("#top"), None);
+    }
+
+    #[test]
+    fn root_of_the_site() {
+        assert_eq!(RelativeLinks::root_for(Path::new("index.html")), "");
+        assert_eq!(RelativeLinks::root_for(Path::new("a/b.html")), "../");
+        assert_eq!(
+            RelativeLinks::root_for(Path::new("a/b/index.html")),
+            "../../"
+        );
+    }
+}
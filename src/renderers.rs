@@ -0,0 +1,203 @@
+//! Renders math notation and Mermaid diagrams when the site is built, with
+//! external commands set in `doctave.yaml`:
+//!
+//! ```yaml
+//! renderers:
+//!   math: npx katex --display-mode
+//!   mermaid: npx mmdc --input - --output - --outputFormat svg
+//! ```
+//!
+//! Each command gets the source of a formula or diagram on its standard
+//! input, and writes the HTML, MathML or SVG to show in its place to its
+//! standard output. Without a command, the source is left for KaTeX and
+//! Mermaid.js to render in the browser.
+
+use std::collections::HashMap;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use crate::html;
+use crate::{Error, Result};
+
+lazy_static! {
+    /// Output of the renderers, by command and source. `serve` rebuilds the
+    /// whole site on every change, and renderers are slow to start.
+    static ref RENDERED: Mutex<HashMap<(String, String), String>> = Mutex::new(HashMap::new());
+}
+
+/// The commands that render math and diagrams, if any
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Renderers {
+    math: Option<String>,
+    mermaid: Option<String>,
+    /// Where the commands are run, so they can use tools installed in the
+    /// project
+    working_dir: PathBuf,
+}
+
+impl Renderers {
+    pub fn new(math: Option<String>, mermaid: Option<String>, working_dir: &Path) -> Self {
+        Renderers {
+            math,
+            mermaid,
+            working_dir: working_dir.to_path_buf(),
+        }
+    }
+
+    /// The command that renders math notation
+    pub fn math(&self) -> Option<&str> {
+        self.math.as_deref()
+    }
+
+    /// The command that renders Mermaid diagrams
+    pub fn mermaid(&self) -> Option<&str> {
+        self.mermaid.as_deref()
+    }
+
+    /// Replaces the math notation and diagrams in the rendered HTML of a
+    /// page with the output of their commands. Kinds without a command are
+    /// left alone.
+    pub fn render(&self, html: &str) -> Result<String> {
+        let mut html = html.to_string();
+
+        if let Some(command) = self.math() {
+            html = self.replace(&html, "math", command)?;
+        }
+        if let Some(command) = self.mermaid() {
+            html = self.replace(&html, "mermaid", command)?;
+        }
+
+        Ok(html)
+    }
+
+    /// Replaces each `<div class="{class}">` with a `<div class="{class}-rendered">`
+    /// holding the output of the command, so that scripts in the browser
+    /// leave it alone.
+    fn replace(&self, html: &str, class: &str, command: &str) -> Result<String> {
+        let start_tag = format!("<div class=\"{}\">", class);
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+
+        while let Some(start) = rest.find(&start_tag) {
+            let source_start = start + start_tag.len();
+            let source_end = match rest[source_start..].find("</div>") {
+                Some(end) => source_start + end,
+                None => break,
+            };
+
+            let source = html::unescape(&rest[source_start..source_end]);
+            let rendered = self.run(command, &source)?;
+
+            out.push_str(&rest[..start]);
+            out.push_str(&format!(
+                "<div class=\"{}-rendered\">{}</div>",
+                class, rendered
+            ));
+            rest = &rest[source_end + "</div>".len()..];
+        }
+
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    fn run(&self, command: &str, source: &str) -> Result<String> {
+        let key = (command.to_string(), source.to_string());
+        if let Some(rendered) = RENDERED.lock().unwrap().get(&key) {
+            return Ok(rendered.clone());
+        }
+
+        let mut child = shell(command)
+            .current_dir(&self.working_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::io(e, format!("Could not run renderer `{}`", command)))?;
+
+        let written = child
+            .stdin
+            .take()
+            .expect("renderer stdin was not piped")
+            .write_all(source.as_bytes());
+
+        // Commands that fail may exit before reading their input, which the
+        // exit status below reports better
+        match written {
+            Err(e) if e.kind() != ErrorKind::BrokenPipe => {
+                return Err(Error::io(
+                    e,
+                    format!("Could not write to renderer `{}`", command),
+                ));
+            }
+            _ => {}
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| Error::io(e, format!("Could not run renderer `{}`", command)))?;
+
+        if !output.status.success() {
+            return Err(Error::new(format!(
+                "Renderer `{}` failed to render:\n\n{}\n\n{}",
+                command,
+                source.trim(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let rendered = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        RENDERED.lock().unwrap().insert(key, rendered.clone());
+
+        Ok(rendered)
+    }
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(all(test, not(windows)))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn renders_with_commands() {
+        let renderers = Renderers::new(Some("tr a-z A-Z".to_string()), None, &std::env::temp_dir());
+        let html = "<div class=\"math\">x &lt; y</div>\n<div class=\"mermaid\">graph TD</div>";
+
+        assert_eq!(
+            renderers.render(html).unwrap(),
+            "<div class=\"math-rendered\">X < Y</div>\n<div class=\"mermaid\">graph TD</div>"
+        );
+    }
+
+    #[test]
+    fn reports_failing_commands() {
+        let renderers = Renderers::new(
+            None,
+            Some("echo 'Parse error' >&2; exit 1".to_string()),
+            &std::env::temp_dir(),
+        );
+
+        let error = renderers
+            .render("<div class=\"mermaid\">graph ?</div>")
+            .unwrap_err();
+
+        assert_eq!(
+            format!("{}", error),
+            "Renderer `echo 'Parse error' >&2; exit 1` failed to render:\n\ngraph ?\n\nParse error"
+        );
+    }
+}
@@ -6,9 +6,11 @@ use bunt::termcolor::{ColorChoice, StandardStream};
 use crossbeam_channel::bounded;
 
 use crate::config::Config;
+use crate::ignore::IgnoreRules;
 use crate::livereload_server::LivereloadServer;
 use crate::preview_server::PreviewServer;
 use crate::site::Site;
+use crate::snippets::{Snippets, SNIPPETS_DIR};
 use crate::watcher::Watcher;
 use crate::Result;
 
@@ -34,9 +36,9 @@ impl ServeCommand {
         // Do initial build ---------------------------
 
         let start = Instant::now();
-        site.build().unwrap();
+        site.build()?;
 
-        if let Err(e) = site.check_dead_links() {
+        if let Err(e) = site.check() {
             bunt::writeln!(stdout, "{$bold}{$yellow}WARNING{/$}{/$}")?;
             println!("{}", e);
         }
@@ -46,7 +48,14 @@ impl ServeCommand {
         // Watcher ------------------------------------
 
         let (watch_snd, watch_rcv) = bounded(128);
-        let watcher = Watcher::new(vec![config.docs_dir().to_path_buf()], watch_snd);
+        let (sources_snd, sources_rcv) = bounded(128);
+        let watcher = Watcher::new(
+            vec![config.docs_dir().to_path_buf()],
+            IgnoreRules::for_config(&config),
+            watch_snd,
+        )
+        .watch_sources(sources_rcv);
+        sources_snd.send(site.root().sources()).unwrap();
         thread::Builder::new()
             .name("watcher".into())
             .spawn(move || watcher.run())
@@ -83,13 +92,31 @@ impl ServeCommand {
             bunt::writeln!(stdout, "    File {$bold}{}{/$} {}.", path.display(), msg)?;
 
             site.reset().unwrap();
+
+            let snippets_dir = config.docs_dir().join(SNIPPETS_DIR);
+            if let Some(name) = Snippets::name_for(&snippets_dir, &path) {
+                for doc in site.root().docs_using_snippet(&name) {
+                    println!("      Included in {}", doc.original_path().display());
+                }
+            }
+            for doc in site.root().docs_including_source(&path) {
+                println!("      Included in {}", doc.original_path().display());
+            }
+
             let start = Instant::now();
-            site.rebuild().unwrap();
+            let rebuilt = site.rebuild();
+            sources_snd.send(site.root().sources()).unwrap();
+
+            if let Err(e) = rebuilt {
+                bunt::writeln!(stdout, "{$bold}{$red}ERROR{/$}{/$}")?;
+                println!("{}\n", e);
+                continue;
+            }
             let duration = start.elapsed();
 
             bunt::writeln!(stdout, "    Site rebuilt in {$bold}{:?}{/$}\n", duration)?;
 
-            if let Err(e) = site.check_dead_links() {
+            if let Err(e) = site.check() {
                 bunt::writeln!(stdout, "{$bold}{$yellow}WARNING{/$}{/$}")?;
                 println!("{}", e);
             }
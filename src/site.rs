@@ -3,10 +3,15 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 
+use crate::broken_includes_checker;
 use crate::broken_links_checker;
 use crate::config::Config;
+use crate::date::Timestamp;
 use crate::docs_finder;
+use crate::git;
 use crate::site_generator::SiteGenerator;
+use crate::undefined_terms_checker;
+use crate::undefined_variables_checker;
 use crate::Directory;
 use crate::{Error, Result};
 
@@ -84,9 +89,47 @@ impl<B: SiteBackend> Site<B> {
         self.backend.build()
     }
 
+    /// Runs every check on the built site. The problems found by all checks
+    /// are reported together, so that fixing one doesn't reveal the next.
+    pub fn check(&self) -> Result<()> {
+        let mut errors = vec![
+            self.check_dead_links(),
+            self.check_undefined_variables(),
+            self.check_broken_includes(),
+            self.check_undefined_terms(),
+        ]
+        .into_iter()
+        .filter_map(|result| result.err())
+        .collect::<Vec<_>>();
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(Error::new(
+                errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )),
+        }
+    }
+
     pub fn check_dead_links(&self) -> Result<()> {
         broken_links_checker::run(&self)
     }
+
+    pub fn check_undefined_variables(&self) -> Result<()> {
+        undefined_variables_checker::run(self)
+    }
+
+    pub fn check_broken_includes(&self) -> Result<()> {
+        broken_includes_checker::run(self)
+    }
+
+    pub fn check_undefined_terms(&self) -> Result<()> {
+        undefined_terms_checker::run(self)
+    }
 }
 
 pub trait SiteBackend: Send + Sync {
@@ -140,6 +183,8 @@ impl<T: SiteBackend> SiteBackend for &T {
 pub struct InMemorySite {
     config: Config,
     content: RwLock<InMemoryContent>,
+    /// Read from git once, since the site is reloaded on every change
+    commit_times: HashMap<PathBuf, Timestamp>,
 }
 
 #[derive(Debug)]
@@ -150,12 +195,15 @@ struct InMemoryContent {
 
 impl InMemorySite {
     pub fn new(config: Config) -> Self {
+        let commit_times = git::last_commit_times(config.docs_dir());
+
         InMemorySite {
             content: RwLock::new(InMemoryContent {
-                root: docs_finder::find(&config),
+                root: docs_finder::find_with_history(&config, &commit_times),
                 rendered: HashMap::new(),
             }),
             config,
+            commit_times,
         }
     }
 
@@ -167,6 +215,7 @@ impl InMemorySite {
                 rendered: HashMap::new(),
             }),
             config,
+            commit_times: HashMap::new(),
         }
     }
 }
@@ -208,7 +257,7 @@ impl SiteBackend for InMemorySite {
     fn reset(&self) -> Result<()> {
         let mut content = self.content.write().unwrap();
         content.rendered = HashMap::new();
-        content.root = docs_finder::find(&self.config);
+        content.root = docs_finder::find_with_history(&self.config, &self.commit_times);
 
         Ok(())
     }
@@ -340,6 +389,9 @@ impl SiteBackend for DiskBackedSite {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::snippets::Snippets;
+    use crate::Document;
+    use std::collections::BTreeMap;
 
     #[test]
     fn you_can_add_a_file_and_read_it_back() {
@@ -357,4 +409,81 @@ mod test {
         assert_eq!(site.read_path(uri).unwrap(), content.as_bytes());
         assert!(site.has_file(uri));
     }
+
+    #[test]
+    fn reports_the_problems_of_all_checks_together() {
+        let config = Config::from_yaml_str(Path::new("project"), "---\ntitle: Title").unwrap();
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![Document::new(
+                Path::new("README.md"),
+                "[nowhere](/nowhere) and {{ nothing }}".to_string(),
+                BTreeMap::new(),
+                "/",
+                &Snippets::default(),
+                &BTreeMap::new(),
+            )],
+            dirs: vec![],
+        };
+
+        let site = Site::with_root(root, config);
+        site.build().unwrap();
+        let error = site.check().unwrap_err().to_string();
+
+        assert!(
+            error.contains("[nowhere](/nowhere)"),
+            "Error was: {}",
+            error
+        );
+        assert!(error.contains("nothing"), "Error was: {}", error);
+    }
+
+    /// A project in a new temporary directory, with the given files
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("doctave-site-{}-{}", name, std::process::id()));
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn redirects_can_be_built_over_a_previous_build() {
+        let root = project(
+            "redirects",
+            &[
+                ("docs/README.md", "# Home"),
+                ("docs/guides/one.md", "---\naliases: /first\n---\n# One"),
+            ],
+        );
+        let config = Config::from_yaml_str(&root, "---\ntitle: Rebuild\n").unwrap();
+
+        let first = Site::disk_backed(config.clone()).build();
+        let second = Site::disk_backed(config).build();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(first.is_ok(), "First build failed: {:?}", first);
+        assert!(second.is_ok(), "Second build failed: {:?}", second);
+    }
+
+    #[test]
+    fn not_found_page_is_rebuilt_over_a_previous_build() {
+        let root = project(
+            "not-found",
+            &[("docs/README.md", "# Home"), ("docs/_404.md", "# Gone")],
+        );
+        let config = Config::from_yaml_str(&root, "---\ntitle: Rebuild\n").unwrap();
+
+        Site::disk_backed(config.clone()).build().unwrap();
+        fs::write(root.join("docs").join("_404.md"), "# Moved away").unwrap();
+        Site::disk_backed(config).build().unwrap();
+
+        let page = fs::read_to_string(root.join("site").join("404.html")).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(page.contains("Moved away"));
+    }
 }
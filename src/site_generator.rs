@@ -1,6 +1,7 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::ffi::OsStr;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use elasticlunr::Index;
@@ -8,20 +9,31 @@ use rayon::prelude::*;
 use serde::Serialize;
 use walkdir::WalkDir;
 
-use crate::config::Config;
+use crate::config::{Config, Feed};
+use crate::highlight;
 use crate::navigation::{Link, Navigation};
+use crate::print::{self, PRINT_PAGE};
+use crate::redirects::{self, REDIRECTS_FILE};
+use crate::relative_links::RelativeLinks;
 use crate::site::{BuildMode, SiteBackend};
-use crate::Directory;
+use crate::snippets::Snippets;
+use crate::{Directory, Document};
 use crate::{Error, Result};
 
 static INCLUDE_DIR: &str = "_include";
 static HEAD_FILE: &str = "_head.html";
+/// Markdown source of the 404 page, in the docs directory
+pub static NOT_FOUND_FILE: &str = "_404.md";
+/// The built 404 page, which hosts serve for URLs that have no page
+pub static NOT_FOUND_PAGE: &str = "404.html";
 
 pub struct SiteGenerator<'a, T: SiteBackend> {
     config: Config,
     root: Directory,
     site: Box<&'a T>,
     timestamp: String,
+    /// Set when links should be relative to the page they are on
+    relative_links: Option<RelativeLinks>,
 }
 
 impl<'a, T: SiteBackend> SiteGenerator<'a, T> {
@@ -32,24 +44,51 @@ impl<'a, T: SiteBackend> SiteGenerator<'a, T> {
             .duration_since(UNIX_EPOCH)
             .expect("Time went backwards");
 
+        let root = site.root();
+        let config = site.config().clone();
+
         SiteGenerator {
-            root: site.root(),
+            relative_links: if config.relative_links() {
+                Some(RelativeLinks::for_directory(config.base_path(), &root))
+            } else {
+                None
+            },
+            root,
             site: Box::new(site),
-            config: site.config().clone(),
+            config,
             timestamp: format!("{}", since_the_epoch.as_secs()),
         }
     }
 
     pub fn run(&self) -> Result<()> {
+        let mut pages = HashMap::new();
+        check_destinations(&self.root, &mut pages)?;
+
         let nav_builder = Navigation::new(&self.config);
         let navigation = nav_builder.build_for(&self.root);
 
         let head_include = self.read_head_include()?;
+        let not_found = self.not_found_doc(&pages)?;
+        let asset_usage = AssetUsage::for_directory(&self.root);
+        let feeds = self.feed_links();
+
+        let mut site_usage = asset_usage.clone();
+        if let Some(doc) = &not_found {
+            site_usage.add(doc);
+        }
 
         self.build_includes()?;
-        self.build_assets()?;
-        self.build_directory(&self.root, &navigation, head_include.as_deref())?;
+        self.build_assets(&site_usage)?;
+        self.build_directory(&self.root, &navigation, &feeds, head_include.as_deref())?;
+        if let Some(doc) = not_found {
+            self.build_not_found_page(doc, &navigation, &feeds, head_include.as_deref())?;
+        }
+        if self.config.single_page() {
+            self.build_print_page(&navigation, &asset_usage, head_include.as_deref())?;
+        }
         self.build_search_index(&self.root)?;
+        self.build_feeds()?;
+        self.build_redirects(&pages)?;
 
         Ok(())
     }
@@ -90,15 +129,20 @@ impl<'a, T: SiteBackend> SiteGenerator<'a, T> {
         Ok(())
     }
 
-    /// Builds fixed assets required by Doctave
-    fn build_assets(&self) -> Result<()> {
+    /// Builds fixed assets required by Doctave.
+    ///
+    /// Assets for code highlighting, math notation and diagrams are only
+    /// included if some page on the site makes use of them.
+    fn build_assets(&self, usage: &AssetUsage) -> Result<()> {
         // Add JS
-        self.site
-            .add_file(
-                &self.config.out_dir().join("assets").join("mermaid.js"),
-                crate::MERMAID_JS.into(),
-            )
-            .map_err(|e| Error::io(e, "Could not write mermaid.js to assets directory"))?;
+        if usage.mermaid && self.config.renderers().mermaid().is_none() {
+            self.site
+                .add_file(
+                    &self.config.out_dir().join("assets").join("mermaid.js"),
+                    crate::MERMAID_JS.into(),
+                )
+                .map_err(|e| Error::io(e, "Could not write mermaid.js to assets directory"))?;
+        }
         self.site
             .add_file(
                 &self.config.out_dir().join("assets").join("elasticlunr.js"),
@@ -116,24 +160,72 @@ impl<'a, T: SiteBackend> SiteGenerator<'a, T> {
         }
         self.site
             .add_file(
-                &self.config.out_dir().join("assets").join("prism.js"),
-                crate::PRISM_JS.into(),
+                &self.config.out_dir().join("assets").join("doctave-app.js"),
+                crate::APP_JS.into(),
             )
-            .map_err(|e| Error::io(e, "Could not write prism.js to assets directory"))?;
+            .map_err(|e| Error::io(e, "Could not write doctave-app.js to assets directory"))?;
+
+        if usage.math {
+            self.build_math_assets()?;
+        }
+
+        if !usage.languages.is_empty() {
+            self.build_code_assets(&usage.languages)?;
+        }
+
+        // Add styles
         self.site
             .add_file(
-                &self.config.out_dir().join("assets").join("katex.js"),
-                crate::KATEX_JS.into(),
+                &self.config.out_dir().join("assets").join("normalize.css"),
+                crate::NORMALIZE_CSS.into(),
             )
-            .map_err(|e| Error::io(e, "Could not write katex.js to assets directory"))?;
+            .map_err(|e| Error::io(e, "Could not write normalize.css to assets directory"))?;
+
+        let mut data = serde_json::Map::new();
+        data.insert(
+            "theme_main".to_string(),
+            serde_json::Value::String(self.config.main_color().to_css_string()),
+        );
+        data.insert(
+            "theme_main_dark".to_string(),
+            serde_json::Value::String(self.config.main_color_dark().to_css_string()),
+        );
+
+        let mut out = Vec::new();
+
+        crate::HANDLEBARS
+            .render_to_write("style.css", &data, &mut out)
+            .map_err(|e| Error::handlebars(e, "Could not write custom style sheet"))?;
+
+        let destination = self
+            .config
+            .out_dir()
+            .join("assets")
+            .join("doctave-style.css");
+
+        self.site.add_file(&destination, out.into())?;
+
+        Ok(())
+    }
+
+    /// Builds the KaTeX script, styles and fonts. Math rendered when the site
+    /// is built only needs the styles and fonts.
+    fn build_math_assets(&self) -> Result<()> {
+        if self.config.renderers().math().is_none() {
+            self.site
+                .add_file(
+                    &self.config.out_dir().join("assets").join("katex.js"),
+                    crate::KATEX_JS.into(),
+                )
+                .map_err(|e| Error::io(e, "Could not write katex.js to assets directory"))?;
+        }
         self.site
             .add_file(
-                &self.config.out_dir().join("assets").join("doctave-app.js"),
-                crate::APP_JS.into(),
+                &self.config.out_dir().join("assets").join("katex.css"),
+                crate::KATEX_CSS.into(),
             )
-            .map_err(|e| Error::io(e, "Could not write doctave-app.js to assets directory"))?;
+            .map_err(|e| Error::io(e, "Could not write katex.css to assets directory"))?;
 
-        // Add fonts
         for font in crate::KATEX_FONTS
             .entries()
             .iter()
@@ -152,26 +244,24 @@ impl<'a, T: SiteBackend> SiteGenerator<'a, T> {
                 .map_err(|e| Error::io(e, "Could not write katex fonts to assets directory"))?;
         }
 
-        // Add prism grammars
-        for font in crate::PRISM_GRAMMARS
-            .entries()
+        Ok(())
+    }
+
+    /// Builds Prism, its themes, and the grammars for the given languages.
+    ///
+    /// If code is highlighted when the site is built, Prism is only needed
+    /// for the languages the build can't highlight.
+    fn build_code_assets(&self, languages: &BTreeSet<String>) -> Result<()> {
+        let in_browser = languages
             .iter()
-            .filter_map(|f| f.as_file())
-        {
-            self.site
-                .add_file(
-                    &self
-                        .config
-                        .out_dir()
-                        .join("assets")
-                        .join("prism-grammars")
-                        .join(font.path().file_name().unwrap()),
-                    Vec::from(font.contents()),
-                )
-                .map_err(|e| Error::io(e, "Could not write prism grammars to assets directory"))?;
+            .filter(|l| !self.config.server_side_highlighting() || !highlight::supports(l))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        if !in_browser.is_empty() {
+            self.build_prism(&in_browser)?;
         }
 
-        // Add styles
         self.site
             .add_file(
                 &self
@@ -192,42 +282,39 @@ impl<'a, T: SiteBackend> SiteGenerator<'a, T> {
                 crate::GH_COLORS_CSS.into(),
             )
             .map_err(|e| Error::io(e, "Could not write prism-ghcolors.css to assets directory"))?;
+
+        Ok(())
+    }
+
+    /// Builds Prism and the grammars for the given languages
+    fn build_prism(&self, languages: &BTreeSet<String>) -> Result<()> {
         self.site
             .add_file(
-                &self.config.out_dir().join("assets").join("normalize.css"),
-                crate::NORMALIZE_CSS.into(),
-            )
-            .map_err(|e| Error::io(e, "Could not write normalize.css to assets directory"))?;
-        self.site
-            .add_file(
-                &self.config.out_dir().join("assets").join("katex.css"),
-                crate::KATEX_CSS.into(),
+                &self.config.out_dir().join("assets").join("prism.js"),
+                crate::PRISM_JS.into(),
             )
-            .map_err(|e| Error::io(e, "Could not write prism-atom-dark.css to assets directory"))?;
-
-        let mut data = serde_json::Map::new();
-        data.insert(
-            "theme_main".to_string(),
-            serde_json::Value::String(self.config.main_color().to_css_string()),
-        );
-        data.insert(
-            "theme_main_dark".to_string(),
-            serde_json::Value::String(self.config.main_color_dark().to_css_string()),
-        );
-
-        let mut out = Vec::new();
-
-        crate::HANDLEBARS
-            .render_to_write("style.css", &data, &mut out)
-            .map_err(|e| Error::handlebars(e, "Could not write custom style sheet"))?;
+            .map_err(|e| Error::io(e, "Could not write prism.js to assets directory"))?;
 
-        let destination = self
-            .config
-            .out_dir()
-            .join("assets")
-            .join("doctave-style.css");
+        for grammar in crate::prism::grammars_for(languages.iter().map(|l| l.as_str())) {
+            let file_name = format!("prism-{}.min.js", grammar);
 
-        self.site.add_file(&destination, out.into())?;
+            // Languages Prism does not know about are left unhighlighted
+            if let Some(file) = crate::PRISM_GRAMMARS.get_file(&file_name) {
+                self.site
+                    .add_file(
+                        &self
+                            .config
+                            .out_dir()
+                            .join("assets")
+                            .join("prism-grammars")
+                            .join(&file_name),
+                        Vec::from(file.contents()),
+                    )
+                    .map_err(|e| {
+                        Error::io(e, "Could not write prism grammars to assets directory")
+                    })?;
+            }
+        }
 
         Ok(())
     }
@@ -236,51 +323,26 @@ impl<'a, T: SiteBackend> SiteGenerator<'a, T> {
         &self,
         dir: &Directory,
         nav: &[Link],
+        feeds: &[FeedLink],
         head_include: Option<&str>,
     ) -> Result<()> {
         let results: Result<Vec<()>> = dir
             .docs
             .par_iter()
             .map(|doc| {
-                let page_title = if doc.uri_path() == "/" {
-                    self.config.title().to_string()
-                } else {
-                    doc.title().to_string()
-                };
-
-                let data = TemplateData {
-                    content: doc.html().to_string(),
-                    headings: doc
-                        .headings()
-                        .iter()
-                        .map(|heading| {
-                            let mut map = BTreeMap::new();
-                            map.insert("title", heading.title.clone());
-                            map.insert("anchor", heading.anchor.clone());
-                            map.insert("level", heading.level.to_string());
-
-                            map
-                        })
-                        .collect::<Vec<_>>(),
-                    navigation: &nav,
-                    current_path: doc.uri_path(),
-                    project_title: self.config.title().to_string(),
-                    logo: self.config.logo().map(|l| l.to_string()),
-                    build_mode: self.config.build_mode().to_string(),
-                    base_path: self.config.base_path().to_owned(),
-                    timestamp: &self.timestamp,
-                    page_title,
-                    head_include,
-                };
+                doc.check_snippets()?;
+
+                let data = self.page_data(doc, nav, feeds, head_include)?;
 
                 let mut out = Vec::new();
 
                 crate::HANDLEBARS
                     .render_to_write("page", &data, &mut out)
                     .map_err(|e| Error::handlebars(e, "Could not render template"))?;
+                let out = self.with_relative_links(out, &doc.html_path());
 
                 self.site
-                    .add_file(&doc.destination(self.config.out_dir()), out.into())?;
+                    .add_file(&doc.destination(self.config.out_dir()), out)?;
 
                 Ok(())
             })
@@ -289,40 +351,553 @@ impl<'a, T: SiteBackend> SiteGenerator<'a, T> {
 
         dir.dirs
             .par_iter()
-            .map(|d| self.build_directory(&d, &nav, head_include))
+            .map(|d| self.build_directory(&d, &nav, feeds, head_include))
             .collect()
     }
 
+    /// The data used to render a document with the page template
+    fn page_data<'b>(
+        &'b self,
+        doc: &Document,
+        nav: &'b [Link],
+        feeds: &'b [FeedLink],
+        head_include: Option<&'b str>,
+    ) -> Result<TemplateData<'b>> {
+        let page_title = if doc.uri_path() == "/" {
+            self.config.title().to_string()
+        } else {
+            doc.title().to_string()
+        };
+
+        Ok(TemplateData {
+            content: self.prerendered(doc.html())?,
+            headings: doc
+                .outline()
+                .into_iter()
+                .map(|heading| {
+                    let mut map = BTreeMap::new();
+                    map.insert("title", heading.title.clone());
+                    map.insert("anchor", heading.anchor.clone());
+                    map.insert("level", heading.level.to_string());
+
+                    map
+                })
+                .collect::<Vec<_>>(),
+            navigation: nav,
+            feeds,
+            current_path: doc.uri_path(),
+            project_title: self.config.title().to_string(),
+            logo: self.config.logo().map(|l| l.to_string()),
+            build_mode: self.config.build_mode().to_string(),
+            base_path: self.config.base_path().to_owned(),
+            timestamp: &self.timestamp,
+            page_title,
+            head_include,
+            description: doc
+                .description()
+                .or_else(|| self.config.description().map(|d| d.to_string()))
+                .unwrap_or_else(|| format!("Documentation for {}", self.config.title())),
+            image: doc
+                .image()
+                .or_else(|| self.config.image())
+                .map(|i| self.absolute_url(i)),
+            canonical_url: self
+                .config
+                .site_url()
+                .map(|url| format!("{}{}", url, doc.uri_path())),
+            last_updated: doc.last_updated()?.map(|time| LastUpdated {
+                datetime: time.to_rfc3339(),
+                display: time.to_date_string(),
+            }),
+            edit_url: self.edit_url(doc),
+            has_code: !doc.code_languages().is_empty(),
+            highlight_in_browser: self.highlights_in_browser(doc.code_languages().into_iter()),
+            has_math: doc.has_math(),
+            math_in_browser: self.config.renderers().math().is_none(),
+            has_mermaid: doc.has_mermaid() && self.config.renderers().mermaid().is_none(),
+            not_found: false,
+            suggested_pages: None,
+            relative_links: self.relative_links.is_some(),
+            relative_root: RelativeLinks::root_for(&doc.html_path()),
+        })
+    }
+
+    /// Loads the document of the `404.html` page from `_404.md` in the docs
+    /// directory, or a default message if there is no such file. A page of
+    /// the docs that maps to `404.html` takes precedence, so there is none.
+    fn not_found_doc(&self, pages: &HashMap<PathBuf, &Path>) -> Result<Option<Document>> {
+        if pages.contains_key(Path::new(NOT_FOUND_PAGE)) {
+            return Ok(None);
+        }
+
+        let source = self.config.docs_dir().join(NOT_FOUND_FILE);
+        let snippets = Snippets::load(&self.config);
+
+        let mut doc = if source.is_file() {
+            Document::load(
+                &source,
+                Path::new(NOT_FOUND_FILE),
+                self.config.base_path(),
+                &snippets,
+                self.config.variables(),
+            )
+        } else {
+            Document::new(
+                Path::new(NOT_FOUND_FILE),
+                "# Page not found\n\nThe page you were looking for does not exist. \
+                 It may have been moved or deleted."
+                    .to_string(),
+                BTreeMap::new(),
+                self.config.base_path(),
+                &snippets,
+                self.config.variables(),
+            )
+        };
+        doc.check_snippets()?;
+        doc.apply_config(&self.config);
+        doc.frontmatter
+            .entry("title".to_string())
+            .or_insert_with(|| "Page not found".to_string());
+
+        Ok(Some(doc))
+    }
+
+    /// Builds the `404.html` page, which hosts serve for any missing URL
+    fn build_not_found_page(
+        &self,
+        doc: Document,
+        nav: &[Link],
+        feeds: &[FeedLink],
+        head_include: Option<&str>,
+    ) -> Result<()> {
+        let mut data = self.page_data(&doc, nav, feeds, head_include)?;
+        // The page is served for any missing URL, so it has no location of its own
+        data.current_path = String::new();
+        data.canonical_url = None;
+        data.last_updated = None;
+        data.not_found = true;
+        data.relative_root = self.config.base_path().to_string();
+
+        let mut pages = Vec::new();
+        self.suggested_pages(&self.root, &mut pages);
+        // Escaped so that no title can close the script tag it is written to
+        data.suggested_pages = Some(
+            serde_json::to_string(&pages)
+                .map_err(|e| Error::new(format!("Could not list pages: {}", e)))?
+                .replace("</", "<\\/"),
+        );
+
+        let mut out = Vec::new();
+        crate::HANDLEBARS
+            .render_to_write("page", &data, &mut out)
+            .map_err(|e| Error::handlebars(e, "Could not render template"))?;
+
+        // Hosts serve the page for missing URLs at any depth, so its links
+        // can't be relative to it
+        let out = match &self.relative_links {
+            Some(links) => links
+                .rewrite_absolute(&String::from_utf8_lossy(&out))
+                .into_bytes(),
+            None => out,
+        };
+
+        self.site
+            .add_file(&self.config.out_dir().join(NOT_FOUND_PAGE), out)
+            .map_err(|e| Error::io(e, "Could not write 404 page"))
+    }
+
+    /// The pages the 404 page can suggest, which are all pages, including the
+    /// ones that are hidden or left out of search
+    fn suggested_pages<'d>(&self, dir: &'d Directory, pages: &mut Vec<SuggestedPage<'d>>) {
+        for doc in &dir.docs {
+            let url = match &self.relative_links {
+                Some(links) => links
+                    .absolute_url(&doc.uri_path())
+                    .unwrap_or_else(|| doc.uri_path()),
+                None => doc.uri_path(),
+            };
+
+            pages.push(SuggestedPage {
+                title: doc.title(),
+                url,
+            });
+        }
+        for child in &dir.dirs {
+            self.suggested_pages(child, pages);
+        }
+    }
+
+    /// Builds all pages in the navigation into a single `print.html` page
+    fn build_print_page(
+        &self,
+        nav: &[Link],
+        usage: &AssetUsage,
+        head_include: Option<&str>,
+    ) -> Result<()> {
+        let data = PrintData {
+            project_title: self.config.title(),
+            base_path: if self.relative_links.is_some() {
+                RelativeLinks::root_for(Path::new(PRINT_PAGE))
+            } else {
+                self.config.base_path().to_string()
+            },
+            timestamp: &self.timestamp,
+            head_include,
+            sections: print::sections(&self.root, nav, self.config.title())
+                .into_iter()
+                .map(|section| {
+                    Ok(print::Section {
+                        content: self.prerendered(&section.content)?,
+                        ..section
+                    })
+                })
+                .collect::<Result<_>>()?,
+            has_code: !usage.languages.is_empty(),
+            highlight_in_browser: self
+                .highlights_in_browser(usage.languages.iter().map(|l| l.as_str())),
+            has_math: usage.math,
+            math_in_browser: self.config.renderers().math().is_none(),
+            has_mermaid: usage.mermaid && self.config.renderers().mermaid().is_none(),
+        };
+
+        let mut out = Vec::new();
+        crate::HANDLEBARS
+            .render_to_write("print", &data, &mut out)
+            .map_err(|e| Error::handlebars(e, "Could not render print template"))?;
+        let out = self.with_relative_links(out, Path::new(PRINT_PAGE));
+
+        self.site
+            .add_file(&self.config.out_dir().join(PRINT_PAGE), out)
+            .map_err(|e| Error::io(e, "Could not write print page"))
+    }
+
+    /// Writes an HTML page for each redirect that forwards visitors to the new
+    /// location, and lists all redirects in a `_redirects` file for hosts
+    /// that support them.
+    ///
+    /// Conflicts are checked against the pages built in this run rather than
+    /// the output directory, which may still hold files from a previous build.
+    fn build_redirects(&self, pages: &HashMap<PathBuf, &Path>) -> Result<()> {
+        let redirects = redirects::find(&self.config, &self.root);
+
+        if redirects.is_empty() {
+            return Ok(());
+        }
+
+        let mut taken = pages.keys().cloned().collect::<HashSet<_>>();
+        taken.insert(PathBuf::from(NOT_FOUND_PAGE));
+        if self.config.single_page() {
+            taken.insert(PathBuf::from(PRINT_PAGE));
+        }
+
+        let mut listing = String::new();
+
+        for redirect in &redirects {
+            let mut stub = redirect.stub_path(self.config.base_path());
+            if self.config.pretty_urls() {
+                stub = crate::pretty_html_path(stub);
+            }
+
+            if taken.contains(&stub) || taken.contains(&stub.with_extension("").join("index.html"))
+            {
+                return Err(Error::new(format!(
+                    "The redirect from {} in {} conflicts with an existing page or redirect.",
+                    redirect.from,
+                    redirect.source.display()
+                )));
+            }
+
+            let to = match &self.relative_links {
+                Some(links) => links
+                    .relative_url(&redirect.to, &stub)
+                    .unwrap_or_else(|| redirect.to.clone()),
+                None => redirect.to.clone(),
+            };
+
+            let mut data = BTreeMap::new();
+            data.insert("to", &to);
+
+            let mut out = Vec::new();
+            crate::HANDLEBARS
+                .render_to_write("redirect", &data, &mut out)
+                .map_err(|e| Error::handlebars(e, "Could not render redirect template"))?;
+
+            self.site
+                .add_file(&self.config.out_dir().join(&stub), out)
+                .map_err(|e| Error::io(e, "Could not write redirect page"))?;
+
+            listing.push_str(&format!("{} {} 301\n", redirect.from, redirect.to));
+            taken.insert(stub);
+        }
+
+        self.site
+            .add_file(
+                &self.config.out_dir().join(REDIRECTS_FILE),
+                listing.into_bytes(),
+            )
+            .map_err(|e| Error::io(e, "Could not write _redirects file"))
+    }
+
+    /// The feeds available on the site, to be advertised on every page
+    fn feed_links(&self) -> Vec<FeedLink> {
+        self.config
+            .feeds()
+            .iter()
+            .filter_map(|feed| {
+                let dir = self.feed_directory(feed)?;
+
+                Some(FeedLink {
+                    title: self.feed_title(feed, dir),
+                    url: self.feed_url(dir, "feed.xml"),
+                })
+            })
+            .collect()
+    }
+
+    /// Builds an Atom feed, and optionally an RSS feed, for the pages in each
+    /// of the directories configured as feeds.
+    ///
+    /// Pages are ordered by their `date` frontmatter field, newest first.
+    /// Pages without a date are left out. The feed was last updated when its
+    /// newest page was, so that it only changes when its pages do.
+    fn build_feeds(&self) -> Result<()> {
+        for feed in self.config.feeds() {
+            let dir = match self.feed_directory(feed) {
+                Some(dir) => dir,
+                // No pages in the directory
+                None => continue,
+            };
+
+            let mut dated = Vec::new();
+            for doc in dir.docs.iter().filter(|d| d.path != dir.index().path) {
+                if let Some(date) = doc.date()? {
+                    dated.push((date, doc));
+                }
+            }
+            dated.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+
+            let updated = dated.first().map(|(date, _)| *date);
+
+            let data = FeedData {
+                title: self.feed_title(feed, dir),
+                project_title: self.config.title(),
+                page_url: self.page_url(dir.index()),
+                feed_url: self.feed_url(dir, "feed.xml"),
+                rss_url: self.feed_url(dir, "rss.xml"),
+                updated: updated.map(|date| date.to_rfc3339()),
+                updated_rss: updated.map(|date| date.to_rfc2822()),
+                entries: dated
+                    .iter()
+                    .map(|(date, doc)| {
+                        Ok(FeedEntry {
+                            title: doc.title(),
+                            url: self.page_url(doc),
+                            updated: date.to_rfc3339(),
+                            published: date.to_rfc2822(),
+                            summary: doc.description(),
+                            content: self.config.renderers().render(doc.html())?,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            };
+
+            let relative_dir = dir
+                .path()
+                .strip_prefix(self.config.docs_dir())
+                .expect("feed directory was not inside the docs directory");
+
+            let mut out = Vec::new();
+            crate::HANDLEBARS
+                .render_to_write("atom.xml", &data, &mut out)
+                .map_err(|e| Error::handlebars(e, "Could not render Atom feed"))?;
+            self.site
+                .add_file(
+                    &self.config.out_dir().join(relative_dir).join("feed.xml"),
+                    out,
+                )
+                .map_err(|e| Error::io(e, "Could not write Atom feed"))?;
+
+            if feed.rss {
+                let mut out = Vec::new();
+                crate::HANDLEBARS
+                    .render_to_write("rss.xml", &data, &mut out)
+                    .map_err(|e| Error::handlebars(e, "Could not render RSS feed"))?;
+                self.site
+                    .add_file(
+                        &self.config.out_dir().join(relative_dir).join("rss.xml"),
+                        out,
+                    )
+                    .map_err(|e| Error::io(e, "Could not write RSS feed"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn feed_directory(&self, feed: &Feed) -> Option<&Directory> {
+        self.root
+            .find_dir(&self.config.project_root().join(&feed.path))
+    }
+
+    fn feed_title(&self, feed: &Feed, dir: &Directory) -> String {
+        feed.title
+            .clone()
+            .unwrap_or_else(|| format!("{} - {}", self.config.title(), dir.index().title()))
+    }
+
+    /// Full URL to a feed file inside the given directory
+    fn feed_url(&self, dir: &Directory, file_name: &str) -> String {
+        let relative_dir = dir
+            .path()
+            .strip_prefix(self.config.docs_dir())
+            .expect("feed directory was not inside the docs directory");
+
+        self.absolute_url(&Link::path_to_uri_with_extension(
+            &relative_dir.join(file_name),
+        ))
+    }
+
+    /// Link to edit the source of the page, if a repository is configured.
+    /// Generated pages have no source file, so can't be edited.
+    fn edit_url(&self, doc: &Document) -> Option<String> {
+        let source = self.config.docs_dir().join(doc.original_path());
+
+        if !source.is_file() {
+            return None;
+        }
+
+        let relative = source
+            .strip_prefix(self.config.project_root())
+            .unwrap_or_else(|_| doc.original_path());
+
+        self.config.edit_url(relative)
+    }
+
+    /// Full URL to the given page
+    fn page_url(&self, doc: &Document) -> String {
+        format!("{}{}", self.config.site_url().unwrap_or(""), doc.uri_path())
+    }
+
+    /// Turns a path to a file on the site into a URL that can be shared.
+    ///
+    /// Full URLs are kept as is. Other paths are resolved relative to the
+    /// base path, and prefixed with the site URL if one is configured.
+    fn absolute_url(&self, path: &str) -> String {
+        if path.contains("://") {
+            return path.to_string();
+        }
+
+        format!(
+            "{}{}{}",
+            self.config.site_url().unwrap_or(""),
+            self.config.base_path(),
+            path.trim_start_matches('/')
+        )
+    }
+
     fn build_search_index(&self, root: &Directory) -> Result<()> {
         let mut index = Index::new(&["title", "uri", "body"]);
 
         self.build_search_index_for_dir(root, &mut index);
 
-        {
+        let json = index.to_json();
+
+        self.site
+            .add_file(
+                &self.config.out_dir().join("search_index.json"),
+                json.as_bytes().into(),
+            )
+            .map_err(|e| Error::io(e, "Could not create search index"))?;
+
+        // Browsers don't allow fetching the index when browsing from disk, so
+        // it is also provided as a script
+        if self.relative_links.is_some() {
             self.site
                 .add_file(
-                    &self.config.out_dir().join("search_index.json"),
-                    index.to_json().as_bytes().into(),
+                    &self.config.out_dir().join("search_index.js"),
+                    format!("var SEARCH_INDEX = {};\n", json).into_bytes(),
                 )
-                .map_err(|e| Error::io(e, "Could not create search index"))
+                .map_err(|e| Error::io(e, "Could not create search index"))?;
         }
+
+        Ok(())
     }
 
     fn build_search_index_for_dir(&self, root: &Directory, index: &mut Index) {
-        for doc in &root.docs {
+        for doc in root.docs.iter().filter(|d| d.is_searchable()) {
+            // Relative to the root of the site, since results are shown on every page
+            let uri = match &self.relative_links {
+                Some(links) => links
+                    .file_for(&doc.uri_path())
+                    .unwrap_or_else(|| doc.uri_path()),
+                None => doc.uri_path(),
+            };
+
             index.add_doc(
                 &doc.id.to_string(),
-                &[
-                    &doc.title(),
-                    &doc.uri_path().as_str(),
-                    doc.markdown_section(),
-                ],
+                &[&doc.title(), uri.as_str(), &doc.search_text()],
             );
         }
         for dir in &root.dirs {
             self.build_search_index_for_dir(&dir, index);
         }
     }
+
+    /// Whether Prism is needed to highlight code in the given languages
+    fn highlights_in_browser<'l>(&self, mut languages: impl Iterator<Item = &'l str>) -> bool {
+        if self.config.server_side_highlighting() {
+            languages.any(|l| !highlight::supports(l))
+        } else {
+            languages.next().is_some()
+        }
+    }
+
+    /// The HTML of a page, with its code highlighted and its math and
+    /// diagrams rendered, if that is done when the site is built
+    fn prerendered(&self, html: &str) -> Result<String> {
+        let html = self.config.renderers().render(html)?;
+
+        if self.config.server_side_highlighting() {
+            Ok(highlight::highlight(&html))
+        } else {
+            Ok(html)
+        }
+    }
+
+    /// Makes the links in the rendered page relative to it, if enabled
+    fn with_relative_links(&self, out: Vec<u8>, page: &Path) -> Vec<u8> {
+        match &self.relative_links {
+            Some(links) => links
+                .rewrite(&String::from_utf8_lossy(&out), page)
+                .into_bytes(),
+            None => out,
+        }
+    }
+}
+
+/// Fails if two source files would be built into the same page, such as a
+/// `README.md` and an `index.md` in the same directory, or pages with the
+/// same permalink.
+fn check_destinations<'a>(dir: &'a Directory, seen: &mut HashMap<PathBuf, &'a Path>) -> Result<()> {
+    for doc in &dir.docs {
+        doc.check_custom_url()?;
+        doc.check_toc_depth()?;
+
+        if let Some(other) = seen.insert(doc.html_path(), doc.original_path()) {
+            return Err(Error::new(format!(
+                "Both {} and {} would be built into {}. Rename or remove one of them.",
+                other.display(),
+                doc.original_path().display(),
+                doc.html_path().display()
+            )));
+        }
+    }
+
+    for child in &dir.dirs {
+        check_destinations(child, seen)?;
+    }
+
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -330,12 +905,119 @@ pub struct TemplateData<'a> {
     pub content: String,
     pub headings: Vec<BTreeMap<&'static str, String>>,
     pub navigation: &'a [Link],
+    pub feeds: &'a [FeedLink],
     pub head_include: Option<&'a str>,
     pub current_path: String,
     pub page_title: String,
+    pub description: String,
+    pub image: Option<String>,
+    pub canonical_url: Option<String>,
+    pub last_updated: Option<LastUpdated>,
+    pub edit_url: Option<String>,
     pub base_path: String,
     pub logo: Option<String>,
     pub project_title: String,
     pub build_mode: String,
     pub timestamp: &'a str,
+    pub has_code: bool,
+    /// Whether Prism highlights the code blocks on the page
+    pub highlight_in_browser: bool,
+    pub has_math: bool,
+    /// Whether KaTeX renders the math on the page
+    pub math_in_browser: bool,
+    pub has_mermaid: bool,
+    pub not_found: bool,
+    /// The pages the 404 page can suggest, as a JSON array
+    pub suggested_pages: Option<String>,
+    pub relative_links: bool,
+    /// Relative path from the page to the root of the site
+    pub relative_root: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SuggestedPage<'a> {
+    title: &'a str,
+    url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PrintData<'a> {
+    project_title: &'a str,
+    base_path: String,
+    timestamp: &'a str,
+    head_include: Option<&'a str>,
+    sections: Vec<print::Section>,
+    has_code: bool,
+    highlight_in_browser: bool,
+    has_math: bool,
+    math_in_browser: bool,
+    has_mermaid: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LastUpdated {
+    pub datetime: String,
+    pub display: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedLink {
+    pub title: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FeedData<'a> {
+    title: String,
+    project_title: &'a str,
+    page_url: String,
+    feed_url: String,
+    rss_url: String,
+    /// Left out of feeds that have no pages with a date
+    updated: Option<String>,
+    updated_rss: Option<String>,
+    entries: Vec<FeedEntry<'a>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FeedEntry<'a> {
+    title: &'a str,
+    url: String,
+    updated: String,
+    published: String,
+    summary: Option<String>,
+    content: String,
+}
+
+/// Which of the optional assets are needed to render the pages of a site.
+#[derive(Debug, Default, Clone)]
+struct AssetUsage {
+    languages: BTreeSet<String>,
+    math: bool,
+    mermaid: bool,
+}
+
+impl AssetUsage {
+    fn for_directory(dir: &Directory) -> Self {
+        let mut usage = AssetUsage::default();
+        usage.collect(dir);
+        usage
+    }
+
+    fn collect(&mut self, dir: &Directory) {
+        for doc in &dir.docs {
+            self.add(doc);
+        }
+
+        for child in &dir.dirs {
+            self.collect(child);
+        }
+    }
+
+    fn add(&mut self, doc: &Document) {
+        self.languages
+            .extend(doc.code_languages().into_iter().map(|l| l.to_string()));
+        self.math |= doc.has_math();
+        self.mermaid |= doc.has_mermaid();
+    }
 }
@@ -0,0 +1,550 @@
+//! Reusable pieces of Markdown that pages can include with `{{> name }}`, and
+//! variables that can be referenced with `{{ name }}`.
+//!
+//! Snippets live in the `_snippets` directory inside the docs directory. The
+//! name of a snippet is its path inside that directory, without the `.md`
+//! file extension, e.g. `{{> install/linux }}` includes `_snippets/install/linux.md`.
+//! Snippets can include other snippets, and reference variables.
+//!
+//! Source files from the project can be included as code blocks with
+//! `{{#code path }}`, see `code_includes`.
+//!
+//! Directives in fenced code blocks and inline code are left as is, so that
+//! pages can show templates written in other languages. Elsewhere, braces can
+//! be escaped with a backslash, `\{{ name }}`, to show them as is.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::code_includes;
+use crate::config::Config;
+use crate::containers;
+use crate::frontmatter;
+
+pub static SNIPPETS_DIR: &str = "_snippets";
+
+static OPEN: &str = "{{";
+static CLOSE: &str = "}}";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snippets {
+    snippets: BTreeMap<String, String>,
+    /// Source files included with `{{#code path }}` are relative to this
+    project_root: PathBuf,
+}
+
+/// The result of expanding the snippets in a piece of Markdown
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Expansion {
+    pub content: String,
+    /// Names of all the snippets that were included, directly or not
+    pub used: Vec<String>,
+    /// Variables that were referenced but not defined. They are left as is.
+    pub undefined: Vec<UndefinedVariable>,
+    /// Source files that were included as code blocks
+    pub sources: Vec<PathBuf>,
+    /// Source files that could not be included. They are left as is.
+    pub broken_includes: Vec<BrokenInclude>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UndefinedVariable {
+    pub name: String,
+    pub line: usize,
+    /// The snippet the variable was referenced in, if not the page itself
+    pub snippet: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrokenInclude {
+    pub directive: String,
+    /// What went wrong
+    pub message: String,
+    pub line: usize,
+    /// The snippet the source file was included in, if not the page itself
+    pub snippet: Option<String>,
+}
+
+impl Snippets {
+    /// Loads all the snippets with the configured extensions from the
+    /// `_snippets` directory inside the docs directory. Returns no snippets
+    /// if the directory does not exist.
+    pub fn load(config: &Config) -> Self {
+        let dir = config.docs_dir().join(SNIPPETS_DIR);
+        let mut snippets = BTreeMap::new();
+
+        for entry in WalkDir::new(&dir).into_iter().filter_map(|e| e.ok()) {
+            let extension = entry.path().extension().and_then(OsStr::to_str);
+
+            if !entry.file_type().is_file()
+                || !config
+                    .extensions()
+                    .iter()
+                    .any(|e| Some(e.as_str()) == extension)
+            {
+                continue;
+            }
+
+            if let Ok(raw) = fs::read_to_string(entry.path()) {
+                let name = Snippets::name_for(&dir, entry.path()).unwrap();
+
+                snippets.insert(name, frontmatter::without(&raw).to_string());
+            }
+        }
+
+        Snippets {
+            snippets,
+            project_root: config.project_root().to_path_buf(),
+        }
+    }
+
+    /// The name a snippet at the given path is included by, if the path is
+    /// inside the snippets directory.
+    pub fn name_for(snippets_dir: &Path, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(snippets_dir).ok()?.with_extension("");
+
+        Some(
+            relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        )
+    }
+
+    #[cfg(test)]
+    pub fn insert(&mut self, name: &str, content: &str) {
+        self.snippets.insert(name.to_string(), content.to_string());
+    }
+
+    /// Replaces all snippet directives in the input with the snippets'
+    /// content, and variable references with their values.
+    ///
+    /// Returns an error describing the problem if a snippet does not exist,
+    /// or if snippets include each other in a cycle.
+    pub fn expand(
+        &self,
+        input: &str,
+        variables: &BTreeMap<String, String>,
+    ) -> std::result::Result<Expansion, String> {
+        let mut expansion = Expansion {
+            content: String::with_capacity(input.len()),
+            used: Vec::new(),
+            undefined: Vec::new(),
+            sources: Vec::new(),
+            broken_includes: Vec::new(),
+        };
+
+        self.expand_into(input, variables, &mut Vec::new(), &mut expansion)?;
+
+        Ok(expansion)
+    }
+
+    fn expand_into<'a>(
+        &'a self,
+        input: &str,
+        variables: &BTreeMap<String, String>,
+        stack: &mut Vec<&'a str>,
+        expansion: &mut Expansion,
+    ) -> std::result::Result<(), String> {
+        let code = code_ranges(input);
+        let mut rest = input;
+
+        while let Some(start) = rest.find(OPEN) {
+            let offset = input.len() - rest.len();
+
+            if let Some(range) = code.iter().find(|r| r.contains(&(offset + start))) {
+                expansion.content.push_str(&input[offset..range.end]);
+                rest = &input[range.end..];
+                continue;
+            }
+
+            let (before, directive) = rest.split_at(start);
+
+            if let Some(escaped) = before.strip_suffix('\\') {
+                expansion.content.push_str(escaped);
+                expansion.content.push_str(OPEN);
+                rest = &directive[OPEN.len()..];
+                continue;
+            }
+
+            let inner = directive[OPEN.len()..]
+                .find(CLOSE)
+                .map(|end| &directive[OPEN.len()..OPEN.len() + end])
+                .filter(|inner| !inner.contains('\n'));
+
+            let inner = match inner {
+                Some(inner) => inner,
+                None => {
+                    expansion.content.push_str(before);
+                    expansion.content.push_str(OPEN);
+                    rest = &directive[OPEN.len()..];
+                    continue;
+                }
+            };
+
+            let line = line_number(input, input.len() - directive.len());
+            let after = &directive[OPEN.len() + inner.len() + CLOSE.len()..];
+
+            if let Some(requested) = inner.strip_prefix('>') {
+                expansion.content.push_str(before);
+                rest = after;
+
+                self.include(requested.trim(), line, variables, stack, expansion)?;
+            } else if let Some(source) = inner
+                .strip_prefix("#code")
+                .filter(|s| s.starts_with(char::is_whitespace))
+            {
+                expansion.content.push_str(before);
+                rest = after;
+
+                self.include_source(source, line, stack, expansion);
+            } else if is_variable_name(inner.trim()) {
+                expansion.content.push_str(before);
+                rest = after;
+
+                match variables.get(inner.trim()) {
+                    Some(value) => expansion.content.push_str(value),
+                    None => {
+                        expansion
+                            .content
+                            .push_str(&directive[..directive.len() - after.len()]);
+                        expansion.undefined.push(UndefinedVariable {
+                            name: inner.trim().to_string(),
+                            line,
+                            snippet: stack.last().map(|s| s.to_string()),
+                        });
+                    }
+                }
+            } else {
+                // Not something we recognize, so leave it for the Markdown
+                expansion.content.push_str(before);
+                expansion.content.push_str(OPEN);
+                rest = &directive[OPEN.len()..];
+            }
+        }
+
+        expansion.content.push_str(rest);
+
+        Ok(())
+    }
+
+    fn include<'a>(
+        &'a self,
+        requested: &str,
+        line: usize,
+        variables: &BTreeMap<String, String>,
+        stack: &mut Vec<&'a str>,
+        expansion: &mut Expansion,
+    ) -> std::result::Result<(), String> {
+        let location = match stack.last() {
+            Some(parent) => format!("line {} of snippet `{}`", line, parent),
+            None => format!("line {}", line),
+        };
+
+        // Allow including snippets with their file extension
+        let name = match requested.rsplit_once('.') {
+            Some((name, _)) if !self.snippets.contains_key(requested) => name,
+            _ => requested,
+        };
+        let (name, content) = self.snippets.get_key_value(name).ok_or_else(|| {
+            format!(
+                "Could not find snippet `{}` on {}. Snippets are read from the {} directory.",
+                requested, location, SNIPPETS_DIR
+            )
+        })?;
+
+        if stack.contains(&name.as_str()) {
+            return Err(format!(
+                "Snippets include each other in a cycle on {}: {} -> {}",
+                location,
+                stack.join(" -> "),
+                name
+            ));
+        }
+
+        if !expansion.used.contains(name) {
+            expansion.used.push(name.clone());
+        }
+
+        stack.push(name);
+        self.expand_into(content.trim_end_matches('\n'), variables, stack, expansion)?;
+        stack.pop();
+
+        Ok(())
+    }
+
+    /// Includes a source file as a code block. Problems are recorded in the
+    /// expansion, leaving the directive as is.
+    fn include_source(&self, source: &str, line: usize, stack: &[&str], expansion: &mut Expansion) {
+        // Code blocks have to start on a line of their own, and lines after
+        // the first are indented like the directive, such as inside lists.
+        let line_start = expansion.content.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let indent = if expansion.content[line_start..].trim().is_empty() {
+            expansion.content[line_start..].to_string()
+        } else {
+            expansion.content.push('\n');
+            String::new()
+        };
+
+        match code_includes::include(&self.project_root, source, &indent) {
+            Ok(inclusion) => {
+                expansion.content.push_str(&inclusion.content);
+                if !expansion.sources.contains(&inclusion.path) {
+                    expansion.sources.push(inclusion.path);
+                }
+            }
+            Err(message) => {
+                let directive = format!("{{{{#code {} }}}}", source.trim());
+
+                expansion.content.push_str(&directive);
+                expansion.broken_includes.push(BrokenInclude {
+                    directive,
+                    message,
+                    line,
+                    snippet: stack.last().map(|s| s.to_string()),
+                });
+            }
+        }
+    }
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// The byte ranges of the input inside fenced code blocks and inline code,
+/// where directives are shown as written
+fn code_ranges(input: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut fence = None;
+    let mut text_start = 0;
+    let mut offset = 0;
+
+    for line in input.split('\n') {
+        let line_end = offset + line.len();
+
+        match fence {
+            Some((open, start)) => {
+                if containers::closes_fence(line, open) {
+                    ranges.push(start..line_end);
+                    fence = None;
+                    text_start = line_end;
+                }
+            }
+            None => {
+                if let Some(open) = containers::opens_fence(line) {
+                    inline_code_ranges(input, text_start..offset, &mut ranges);
+                    fence = Some((open, offset));
+                }
+            }
+        }
+
+        offset = line_end + 1;
+    }
+
+    match fence {
+        // An unclosed fence runs to the end of the input
+        Some((_, start)) => ranges.push(start..input.len()),
+        None => inline_code_ranges(input, text_start..input.len(), &mut ranges),
+    }
+
+    ranges
+}
+
+/// Adds the ranges of the inline code spans in the given part of the input.
+/// Spans don't continue past a blank line.
+fn inline_code_ranges(input: &str, within: Range<usize>, ranges: &mut Vec<Range<usize>>) {
+    let text = &input[within.clone()];
+    let run_length = |at: usize| text[at..].chars().take_while(|c| *c == '`').count();
+    let mut from = 0;
+
+    while let Some(found) = text[from..].find('`') {
+        let start = from + found;
+        let length = run_length(start);
+        let mut search = start + length;
+        let mut end = None;
+
+        while let Some(found) = text[search..].find('`') {
+            let close = search + found;
+            if text[start..close].contains("\n\n") {
+                break;
+            }
+            if run_length(close) == length {
+                end = Some(close + length);
+                break;
+            }
+            search = close + run_length(close);
+        }
+
+        match end {
+            Some(end) => {
+                ranges.push(within.start + start..within.start + end);
+                from = end;
+            }
+            None => from = start + length,
+        }
+    }
+}
+
+fn line_number(input: &str, offset: usize) -> usize {
+    input[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn snippets() -> Snippets {
+        let mut snippets = Snippets::default();
+        snippets.insert("warning", "**Careful!**\n");
+        snippets.insert("install/linux", "Run `make`.\n\n{{> warning }}\n");
+        snippets.insert("loop/a", "{{> loop/b }}");
+        snippets.insert("loop/b", "{{> loop/a }}");
+        snippets.insert("version", "Version {{ version }}");
+        snippets
+    }
+
+    #[test]
+    fn expands_nested_snippets() {
+        let expansion = snippets()
+            .expand(
+                "# Install\n\n{{> install/linux }}\n\nDone.",
+                &BTreeMap::new(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            expansion.content,
+            "# Install\n\nRun `make`.\n\n**Careful!**\n\nDone."
+        );
+        assert_eq!(expansion.used, vec!["install/linux", "warning"]);
+    }
+
+    #[test]
+    fn escaped_directives_are_left_alone() {
+        let expansion = snippets()
+            .expand("Write \\{{> warning }} to include", &BTreeMap::new())
+            .unwrap();
+
+        assert_eq!(expansion.content, "Write {{> warning }} to include");
+        assert!(expansion.used.is_empty());
+    }
+
+    #[test]
+    fn directives_in_code_are_left_alone() {
+        let input = "```\n{{> x }}\n```\n\nUse `{{> x }}` or ``{{> x }}``.\n\n~~~~\n{{> x }}";
+        let expansion = snippets().expand(input, &BTreeMap::new()).unwrap();
+
+        assert_eq!(expansion.content, input);
+        assert!(expansion.used.is_empty());
+    }
+
+    #[test]
+    fn code_includes_in_code_are_left_alone() {
+        let input = "```\n{{#code src/missing.rs }}\n```\n\nWrite `{{#code path }}`.";
+        let expansion = snippets().expand(input, &BTreeMap::new()).unwrap();
+
+        assert_eq!(expansion.content, input);
+        assert!(expansion.broken_includes.is_empty());
+    }
+
+    #[test]
+    fn inline_code_ends_at_blank_lines() {
+        let expansion = snippets()
+            .expand("A ` tick\n\n{{> warning }} and `code`", &BTreeMap::new())
+            .unwrap();
+
+        assert_eq!(expansion.content, "A ` tick\n\n**Careful!** and `code`");
+    }
+
+    #[test]
+    fn reports_missing_snippets_with_line() {
+        let error = snippets()
+            .expand("# Title\n\n{{> missing }}", &BTreeMap::new())
+            .unwrap_err();
+
+        assert!(
+            error.contains("Could not find snippet `missing` on line 3"),
+            "Error was: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn substitutes_variables() {
+        let mut variables = BTreeMap::new();
+        variables.insert("product".to_string(), "Doctave".to_string());
+        variables.insert("version".to_string(), "0.4.2".to_string());
+
+        let expansion = snippets()
+            .expand(
+                "{{ product }} {{> version }}, not \\{{ product }} or {{ #if }}",
+                &variables,
+            )
+            .unwrap();
+
+        assert_eq!(
+            expansion.content,
+            "Doctave Version 0.4.2, not {{ product }} or {{ #if }}"
+        );
+        assert!(expansion.undefined.is_empty());
+    }
+
+    #[test]
+    fn reports_undefined_variables() {
+        let expansion = snippets()
+            .expand("# Title\n\n{{ nope }} and {{> version }}", &BTreeMap::new())
+            .unwrap();
+
+        assert_eq!(
+            expansion.content,
+            "# Title\n\n{{ nope }} and Version {{ version }}"
+        );
+        assert_eq!(
+            expansion.undefined,
+            vec![
+                UndefinedVariable {
+                    name: "nope".to_string(),
+                    line: 3,
+                    snippet: None,
+                },
+                UndefinedVariable {
+                    name: "version".to_string(),
+                    line: 1,
+                    snippet: Some("version".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn variables_in_code_are_left_alone() {
+        let input = "```yaml\nrun: echo ${{ secrets.TOKEN }}\n```\n\nSet `{{ version }}`.";
+        let expansion = snippets().expand(input, &BTreeMap::new()).unwrap();
+
+        assert_eq!(expansion.content, input);
+        assert!(expansion.undefined.is_empty());
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let error = snippets()
+            .expand("{{> loop/a }}", &BTreeMap::new())
+            .unwrap_err();
+
+        assert!(
+            error.contains("loop/a -> loop/b -> loop/a"),
+            "Error was: {}",
+            error
+        );
+    }
+}
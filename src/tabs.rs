@@ -0,0 +1,274 @@
+//! Tabbed content, written as a `tabs` container with a `== Title` line
+//! starting each tab:
+//!
+//! ```markdown
+//! :::tabs
+//! == macOS
+//! brew install doctave
+//! == Linux
+//! cargo install doctave
+//! :::
+//! ```
+//!
+//! The tabs are turned into HTML in the Markdown before it is parsed, so
+//! that the content of each tab is rendered as usual. `app.js` switches
+//! between the tabs, and keeps tabs with the same title selected across the
+//! page. Without it, such as in the single page and print output, the tabs
+//! are shown one after the other.
+
+use crate::containers;
+use crate::html;
+
+/// A container the tabs may be nested in, or a group of tabs
+enum Frame {
+    Container(usize),
+    Tabs(Group),
+}
+
+struct Group {
+    colons: usize,
+    id: usize,
+    titles: Vec<String>,
+    /// Where the tab list goes in the output, once all tabs are known
+    list_at: usize,
+}
+
+/// Replaces the tabs in the Markdown with the HTML of a tab widget.
+/// Tabs inside code blocks are left alone.
+pub fn expand(markdown: &str) -> String {
+    transform(markdown, false)
+}
+
+/// Replaces the tabs in the Markdown with their titles and content, leaving
+/// out the tabs syntax. Used for indexing pages for search.
+pub fn to_plain_text(markdown: &str) -> String {
+    transform(markdown, true)
+}
+
+fn transform(markdown: &str, plain: bool) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut fence: Option<(char, usize)> = None;
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut groups = 0;
+
+    for line in markdown.lines() {
+        if let Some(open) = fence {
+            if containers::closes_fence(line, open) {
+                fence = None;
+            }
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+
+        if let Some(open) = containers::opens_fence(line) {
+            fence = Some(open);
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+
+        if let Some((colons, opening)) = containers::container_opening(line) {
+            if opening.name == "tabs" {
+                groups += 1;
+                if !plain {
+                    out.push_str("<div class=\"tabs\">\n");
+                }
+                frames.push(Frame::Tabs(Group {
+                    colons,
+                    id: groups,
+                    titles: Vec::new(),
+                    list_at: out.len(),
+                }));
+                continue;
+            }
+
+            frames.push(Frame::Container(colons));
+        }
+
+        if let Some(colons) = containers::container_closing(line) {
+            let closes = match frames.last() {
+                Some(Frame::Container(c)) => *c <= colons,
+                Some(Frame::Tabs(group)) => group.colons <= colons,
+                None => false,
+            };
+
+            if closes {
+                if let Some(Frame::Tabs(group)) = frames.pop() {
+                    if !plain {
+                        close_group(&group, &mut out);
+                    }
+                    continue;
+                }
+            }
+        }
+
+        if let Some(Frame::Tabs(group)) = frames.last_mut() {
+            if let Some(title) = tab_title(line) {
+                if plain {
+                    out.push_str(title);
+                    out.push_str("\n\n");
+                } else {
+                    open_panel(group, title, &mut out);
+                }
+                group.titles.push(title.to_string());
+                continue;
+            }
+        }
+
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    // Close tabs left open at the end of the page
+    while let Some(frame) = frames.pop() {
+        if let Frame::Tabs(group) = frame {
+            if !plain {
+                close_group(&group, &mut out);
+            }
+        }
+    }
+
+    if !markdown.ends_with('\n') && out.ends_with('\n') {
+        out.pop();
+    }
+
+    out
+}
+
+/// Matches `== Title`
+fn tab_title(line: &str) -> Option<&str> {
+    if containers::is_indented_code(line) {
+        return None;
+    }
+
+    let title = line.trim().strip_prefix("==")?.trim();
+
+    if title.is_empty() || title.starts_with('=') {
+        None
+    } else {
+        Some(title)
+    }
+}
+
+fn open_panel(group: &Group, title: &str, out: &mut String) {
+    let index = group.titles.len();
+
+    if index > 0 {
+        out.push_str("\n\n</div>\n");
+    }
+
+    out.push_str(&format!(
+        "<div class=\"tab-panel\" role=\"tabpanel\" id=\"tabs-{id}-{index}-panel\" \
+         aria-labelledby=\"tabs-{id}-{index}\" data-tab=\"{key}\"{hidden}>\n\
+         <p class=\"tab-title\">{title}</p>\n\n",
+        id = group.id,
+        index = index,
+        key = key(title),
+        hidden = if index > 0 { " hidden=\"hidden\"" } else { "" },
+        title = html::escape(title),
+    ));
+}
+
+/// Closes the last panel, and inserts the list of tabs above the panels.
+fn close_group(group: &Group, out: &mut String) {
+    if !group.titles.is_empty() {
+        out.push_str("\n\n</div>\n");
+    }
+    out.push_str("</div>\n\n");
+
+    let mut list = String::from("<div class=\"tab-list\" role=\"tablist\">");
+    for (index, title) in group.titles.iter().enumerate() {
+        list.push_str(&format!(
+            "<button type=\"button\" class=\"tab\" role=\"tab\" id=\"tabs-{id}-{index}\" \
+             aria-controls=\"tabs-{id}-{index}-panel\" aria-selected=\"{selected}\" \
+             tabindex=\"{tabindex}\" data-tab=\"{key}\">{title}</button>",
+            id = group.id,
+            index = index,
+            selected = index == 0,
+            tabindex = if index == 0 { 0 } else { -1 },
+            key = key(title),
+            title = html::escape(title),
+        ));
+    }
+    list.push_str("</div>\n");
+
+    out.insert_str(group.list_at, &list);
+}
+
+/// Identifies tabs with the same title, so that they are selected together
+fn key(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn expands_tabs() {
+        let markdown =
+            "Before\n\n:::tabs\n== macOS\nUse *brew*\n== Linux & BSD\nUse cargo\n:::\nAfter";
+
+        assert_eq!(
+            expand(markdown),
+            "Before\n\n\
+             <div class=\"tabs\">\n\
+             <div class=\"tab-list\" role=\"tablist\">\
+             <button type=\"button\" class=\"tab\" role=\"tab\" id=\"tabs-1-0\" \
+             aria-controls=\"tabs-1-0-panel\" aria-selected=\"true\" tabindex=\"0\" \
+             data-tab=\"macos\">macOS</button>\
+             <button type=\"button\" class=\"tab\" role=\"tab\" id=\"tabs-1-1\" \
+             aria-controls=\"tabs-1-1-panel\" aria-selected=\"false\" tabindex=\"-1\" \
+             data-tab=\"linux---bsd\">Linux &amp; BSD</button>\
+             </div>\n\
+             <div class=\"tab-panel\" role=\"tabpanel\" id=\"tabs-1-0-panel\" \
+             aria-labelledby=\"tabs-1-0\" data-tab=\"macos\">\n\
+             <p class=\"tab-title\">macOS</p>\n\n\
+             Use *brew*\n\
+             \n\n</div>\n\
+             <div class=\"tab-panel\" role=\"tabpanel\" id=\"tabs-1-1-panel\" \
+             aria-labelledby=\"tabs-1-1\" data-tab=\"linux---bsd\" hidden=\"hidden\">\n\
+             <p class=\"tab-title\">Linux &amp; BSD</p>\n\n\
+             Use cargo\n\
+             \n\n</div>\n\
+             </div>\n\n\
+             After"
+        );
+    }
+
+    #[test]
+    fn leaves_nested_callouts_for_later() {
+        let markdown = "::::tabs\n== One\n:::tip\n== Not a tab\n:::\n== Two\n::::";
+
+        assert_eq!(
+            to_plain_text(markdown),
+            "One\n\n:::tip\n== Not a tab\n:::\nTwo\n"
+        );
+    }
+
+    #[test]
+    fn numbers_groups_on_the_page() {
+        let markdown = ":::tabs\n== A\n:::\n\n:::tabs\n== B\n:::";
+        let html = expand(markdown);
+
+        assert!(html.contains("id=\"tabs-1-0\""));
+        assert!(html.contains("id=\"tabs-2-0\""));
+    }
+
+    #[test]
+    fn leaves_code_blocks_alone() {
+        let markdown = "```\n:::tabs\n== A\n:::\n```\n\n    :::tabs";
+
+        assert_eq!(expand(markdown), markdown);
+    }
+}
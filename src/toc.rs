@@ -0,0 +1,154 @@
+//! Tables of contents built from the headings of a page.
+//!
+//! A `[[toc]]` marker on a line of its own is replaced with a nested list of
+//! the headings on the page, leaving out the page's title.
+
+use crate::html;
+use crate::Heading;
+
+/// The marker, as rendered into HTML
+static MARKER: &str = "<p>[[toc]]</p>";
+
+/// The deepest heading level shown in tables of contents, unless configured
+/// otherwise
+pub static DEFAULT_DEPTH: u32 = 3;
+
+#[derive(Debug, PartialEq)]
+struct Entry<'a> {
+    heading: &'a Heading,
+    children: Vec<Entry<'a>>,
+}
+
+/// Replaces any `[[toc]]` markers in the HTML with a table of contents
+/// listing headings up to the given level.
+pub fn insert(html: &str, headings: &[Heading], depth: u32) -> String {
+    if !html.contains(MARKER) {
+        return html.to_string();
+    }
+
+    html.replace(MARKER, &render(headings, depth))
+}
+
+/// Renders the headings from level 2 up to the given depth as a nested list.
+pub fn render(headings: &[Heading], depth: u32) -> String {
+    let headings = headings
+        .iter()
+        .filter(|h| h.level > 1 && h.level <= depth)
+        .collect::<Vec<_>>();
+
+    let mut out = String::from("<div class=\"toc\">");
+    render_entries(&tree(&headings), &mut out);
+    out.push_str("</div>");
+
+    out
+}
+
+/// Nests each heading under the closest heading above it with a lower
+/// level. Skipped levels, such as an h4 directly under an h2, are nested
+/// one level deep.
+fn tree<'a>(headings: &[&'a Heading]) -> Vec<Entry<'a>> {
+    let mut entries: Vec<Entry<'a>> = Vec::new();
+    let mut rest = headings;
+
+    while let Some((first, tail)) = rest.split_first() {
+        let end = tail
+            .iter()
+            .position(|h| h.level <= first.level)
+            .unwrap_or(tail.len());
+
+        entries.push(Entry {
+            heading: first,
+            children: tree(&tail[..end]),
+        });
+        rest = &tail[end..];
+    }
+
+    entries
+}
+
+fn render_entries(entries: &[Entry], out: &mut String) {
+    if entries.is_empty() {
+        return;
+    }
+
+    out.push_str("<ul>");
+    for entry in entries {
+        out.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a>",
+            html::escape(&entry.heading.anchor),
+            html::escape(&entry.heading.title)
+        ));
+        render_entries(&entry.children, out);
+        out.push_str("</li>");
+    }
+    out.push_str("</ul>");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn heading(level: u32, title: &str) -> Heading {
+        Heading {
+            title: title.to_string(),
+            anchor: title.to_lowercase().replace(' ', "-"),
+            level,
+        }
+    }
+
+    #[test]
+    fn nests_headings_by_level() {
+        let headings = vec![
+            heading(1, "Title"),
+            heading(2, "Install"),
+            heading(3, "From source"),
+            heading(4, "Linux"),
+            heading(2, "Usage"),
+            heading(4, "Flags"),
+        ];
+
+        assert_eq!(
+            render(&headings, 6),
+            "<div class=\"toc\"><ul>\
+             <li><a href=\"#install\">Install</a><ul>\
+             <li><a href=\"#from-source\">From source</a><ul>\
+             <li><a href=\"#linux\">Linux</a></li>\
+             </ul></li>\
+             </ul></li>\
+             <li><a href=\"#usage\">Usage</a><ul>\
+             <li><a href=\"#flags\">Flags</a></li>\
+             </ul></li>\
+             </ul></div>"
+        );
+    }
+
+    #[test]
+    fn limits_the_depth() {
+        let headings = vec![
+            heading(2, "Install"),
+            heading(3, "From source"),
+            heading(2, "Usage & flags"),
+        ];
+
+        assert_eq!(
+            render(&headings, 2),
+            "<div class=\"toc\"><ul>\
+             <li><a href=\"#install\">Install</a></li>\
+             <li><a href=\"#usage-&amp;-flags\">Usage &amp; flags</a></li>\
+             </ul></div>"
+        );
+    }
+
+    #[test]
+    fn replaces_the_marker() {
+        let headings = vec![heading(1, "Title"), heading(2, "Usage")];
+        let html = "<h1>Title</h1>\n<p>[[toc]]</p>\n<h2>Usage</h2>\n<p>Not [[toc]] here</p>";
+
+        assert_eq!(
+            insert(html, &headings, 6),
+            "<h1>Title</h1>\n\
+             <div class=\"toc\"><ul><li><a href=\"#usage\">Usage</a></li></ul></div>\n\
+             <h2>Usage</h2>\n<p>Not [[toc]] here</p>"
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::glossary;
+use crate::site::{Site, SiteBackend};
+use crate::Directory;
+use crate::{Error, Result};
+
+use std::path::PathBuf;
+
+pub fn run<B: SiteBackend>(site: &Site<B>) -> Result<()> {
+    // A glossary that can't be read leaves every reference undefined
+    glossary::load(&site.config)?;
+
+    let mut undefined = Vec::new();
+    find_undefined_terms(&site.root(), &mut undefined);
+
+    if undefined.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::undefined_terms(undefined))
+    }
+}
+
+fn find_undefined_terms(dir: &Directory, undefined: &mut Vec<(PathBuf, String)>) {
+    for doc in &dir.docs {
+        for term in doc.undefined_terms() {
+            undefined.push((doc.original_path().to_owned(), term.clone()));
+        }
+    }
+
+    for child_dir in &dir.dirs {
+        find_undefined_terms(child_dir, undefined);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+    use crate::frontmatter;
+    use crate::snippets::Snippets;
+    use crate::Document;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    fn page(path: &str, content: &str) -> Document {
+        Document::new(
+            Path::new(path),
+            content.to_string(),
+            frontmatter::parse(content).unwrap(),
+            "/",
+            &Snippets::default(),
+            &BTreeMap::new(),
+        )
+    }
+
+    #[test]
+    fn detects_undefined_terms() {
+        let config =
+            Config::from_yaml_str(Path::new("project"), "---\ntitle: My project\n").unwrap();
+
+        let mut root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![
+                page(
+                    "README.md",
+                    "# Welcome\n\nEach [[runner]] needs a [[Workspace]].",
+                ),
+                page(
+                    "glossary.md",
+                    "# Glossary\n\n## Runner\n\nThe machine a job runs on.",
+                ),
+            ],
+            dirs: vec![],
+        };
+        glossary::link_terms(&mut root, &config);
+
+        let site = Site::with_root(root, config);
+        let error = run(&site).unwrap_err();
+
+        assert_eq!(
+            format!("{}", error),
+            "Detected undefined glossary terms.\n\
+             The following terms are not defined in the glossary:\n\n\
+             \tREADME.md : [[Workspace]]\n"
+        );
+    }
+}
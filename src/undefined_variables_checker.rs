@@ -0,0 +1,93 @@
+use crate::site::{Site, SiteBackend};
+use crate::snippets::UndefinedVariable;
+use crate::Directory;
+use crate::{Error, Result};
+
+use std::path::PathBuf;
+
+pub fn run<B: SiteBackend>(site: &Site<B>) -> Result<()> {
+    let mut undefined = Vec::new();
+    find_undefined_variables(&site.root(), &mut undefined);
+
+    if undefined.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::undefined_variables(undefined))
+    }
+}
+
+fn find_undefined_variables(dir: &Directory, undefined: &mut Vec<(PathBuf, UndefinedVariable)>) {
+    for doc in &dir.docs {
+        for variable in doc.undefined_variables() {
+            undefined.push((doc.original_path().to_owned(), variable.clone()));
+        }
+    }
+
+    for child_dir in &dir.dirs {
+        find_undefined_variables(child_dir, undefined);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+    use crate::frontmatter;
+    use crate::snippets::Snippets;
+    use crate::Document;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    fn page(path: &str, content: &str) -> Document {
+        Document::new(
+            Path::new(path),
+            content.to_string(),
+            frontmatter::parse(content).unwrap(),
+            "/",
+            &Snippets::default(),
+            &BTreeMap::new(),
+        )
+    }
+
+    fn config() -> Config {
+        Config::from_yaml_str(Path::new("project"), "---\ntitle: My project\n").unwrap()
+    }
+
+    #[test]
+    fn detects_undefined_variables() {
+        let config = config();
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![page("README.md", "# Welcome\n\nVersion {{ version }}")],
+            dirs: vec![],
+        };
+
+        let site = Site::with_root(root, config);
+        let error = run(&site).unwrap_err();
+
+        assert!(
+            format!("{}", error).contains("README.md : line 3 : {{ version }}"),
+            "Error was: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn frontmatter_variables_are_defined() {
+        let config = config();
+
+        let root = Directory {
+            path: config.docs_dir().to_path_buf(),
+            docs: vec![page(
+                "README.md",
+                "---\nvariables:\n  version: 1.0\n---\n\nVersion {{ version }}",
+            )],
+            dirs: vec![],
+        };
+
+        let site = Site::with_root(root, config);
+
+        assert!(run(&site).is_ok());
+    }
+}
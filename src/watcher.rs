@@ -1,21 +1,46 @@
+use std::collections::HashSet;
 use std::path::PathBuf;
-use std::sync::mpsc::channel;
+use std::sync::mpsc::{channel, RecvTimeoutError};
 use std::time::Duration;
 
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender};
 use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher as NotifyWatcher};
 
+use crate::ignore::IgnoreRules;
+
 pub struct Watcher {
     paths: Vec<PathBuf>,
+    ignore: IgnoreRules,
     channel: Sender<(PathBuf, String)>,
+    /// Receives the source files included in the docs, which are watched
+    /// on top of the paths
+    sources: Option<Receiver<Vec<PathBuf>>>,
+    watched_sources: HashSet<PathBuf>,
 }
 
 impl Watcher {
-    pub fn new(paths: Vec<PathBuf>, channel: Sender<(PathBuf, String)>) -> Self {
-        Watcher { paths, channel }
+    pub fn new(
+        paths: Vec<PathBuf>,
+        ignore: IgnoreRules,
+        channel: Sender<(PathBuf, String)>,
+    ) -> Self {
+        Watcher {
+            paths,
+            ignore,
+            channel,
+            sources: None,
+            watched_sources: HashSet::new(),
+        }
     }
 
-    pub fn run(self) {
+    /// Also watches the source files sent on the channel. The latest list
+    /// should be sent every time the site is built.
+    pub fn watch_sources(mut self, sources: Receiver<Vec<PathBuf>>) -> Self {
+        self.sources = Some(sources);
+        self
+    }
+
+    pub fn run(mut self) {
         let (tx, rx) = channel();
         let mut watcher = watcher(tx, Duration::from_secs(1)).unwrap();
 
@@ -26,7 +51,9 @@ impl Watcher {
         }
 
         loop {
-            let should_continue = match rx.recv() {
+            self.watch_new_sources(&mut watcher);
+
+            let should_continue = match rx.recv_timeout(Duration::from_millis(500)) {
                 Ok(event) => match event {
                     DebouncedEvent::NoticeWrite(_) => true,
                     DebouncedEvent::NoticeRemove(_) => true,
@@ -39,6 +66,7 @@ impl Watcher {
                     }
                     _ => true,
                 },
+                Err(RecvTimeoutError::Timeout) => true,
                 Err(e) => {
                     println!("watch error: {:?}", e);
                     true
@@ -51,12 +79,45 @@ impl Watcher {
         }
     }
 
+    /// Starts watching the source files that were included since the last
+    /// check. Files inside the watched paths are already covered.
+    fn watch_new_sources<W: NotifyWatcher>(&mut self, watcher: &mut W) {
+        let sources = match &self.sources {
+            Some(sources) => sources.try_iter().last(),
+            None => None,
+        };
+
+        for source in sources.into_iter().flatten() {
+            if self.paths.iter().any(|p| source.starts_with(p))
+                || self.watched_sources.contains(&source)
+            {
+                continue;
+            }
+
+            if watcher.watch(&source, RecursiveMode::NonRecursive).is_ok() {
+                self.watched_sources.insert(source);
+            }
+        }
+    }
+
     /// Notifies the listening end (Main thread) that there the paths
-    /// being monitored have updated.
+    /// being monitored have updated. Changes to ignored files are skipped.
     ///
     /// Returns false if the notification could not be send, meaning
     /// the main thread has gone away.
-    fn notify<S: Into<String>>(&self, path: PathBuf, msg: S) -> bool {
+    fn notify<S: Into<String>>(&mut self, path: PathBuf, msg: S) -> bool {
+        let is_source = self.watched_sources.contains(&path);
+
+        if !is_source && self.ignore.is_ignored(&path, path.is_dir()) {
+            return true;
+        }
+
+        // Editors often replace files when saving them, so sources are
+        // watched again once the rebuilt site sends them over
+        if is_source {
+            self.watched_sources.remove(&path);
+        }
+
         self.channel.send((path, msg.into())).is_ok()
     }
 }
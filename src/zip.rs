@@ -0,0 +1,177 @@
+//! A minimal writer for ZIP archives, as used by EPUB containers.
+//!
+//! Files are stored without compression, which keeps the writer small and is
+//! what EPUB requires for the `mimetype` file anyway. Archives are limited
+//! to 4 GiB and 65535 files, since the ZIP64 extensions are not written.
+
+use std::convert::TryFrom;
+
+use crate::{Error, Result};
+
+/// Builds a ZIP archive in memory.
+#[derive(Debug, Default)]
+pub struct ZipWriter {
+    data: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        ZipWriter::default()
+    }
+
+    /// Adds a file to the archive. Files are written in the order they are
+    /// added.
+    pub fn add_file(&mut self, name: &str, content: &[u8]) -> Result<()> {
+        field::<u16>(name.len(), "file name")?;
+
+        let entry = Entry {
+            name: name.to_string(),
+            crc: crc32(content),
+            size: field(content.len(), "file")?,
+            offset: field(self.data.len(), "archive")?,
+        };
+
+        // Local file header
+        self.data.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        self.write_entry_fields(&entry);
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(content);
+
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Writes the central directory, and returns the finished archive.
+    pub fn finish(mut self) -> Result<Vec<u8>> {
+        let directory_offset: u32 = field(self.data.len(), "archive")?;
+        let entries = std::mem::take(&mut self.entries);
+        let count: u16 = field(entries.len(), "number of files")?;
+
+        for entry in &entries {
+            // Central directory file header
+            self.data.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            // Version made by
+            self.data.extend_from_slice(&20u16.to_le_bytes());
+            self.write_entry_fields(entry);
+            // File comment length, disk number, internal & external attributes
+            self.data.extend_from_slice(&[0; 10]);
+            self.data.extend_from_slice(&entry.offset.to_le_bytes());
+            self.data.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let directory_size: u32 = field(self.data.len() - directory_offset as usize, "archive")?;
+
+        // End of central directory record
+        self.data.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        self.data.extend_from_slice(&[0; 4]);
+        self.data.extend_from_slice(&count.to_le_bytes());
+        self.data.extend_from_slice(&count.to_le_bytes());
+        self.data.extend_from_slice(&directory_size.to_le_bytes());
+        self.data.extend_from_slice(&directory_offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+
+        Ok(self.data)
+    }
+
+    /// The fields shared between the local and central directory headers,
+    /// from the version needed to extract up to the extra field length.
+    fn write_entry_fields(&mut self, entry: &Entry) {
+        // Version needed to extract, flags (UTF-8 names), stored compression
+        self.data.extend_from_slice(&10u16.to_le_bytes());
+        self.data.extend_from_slice(&0x0800u16.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        // Modification time and date, set to 1980-01-01 for reproducible output
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+        self.data.extend_from_slice(&0x0021u16.to_le_bytes());
+        self.data.extend_from_slice(&entry.crc.to_le_bytes());
+        // Compressed and uncompressed size are the same when stored
+        self.data.extend_from_slice(&entry.size.to_le_bytes());
+        self.data.extend_from_slice(&entry.size.to_le_bytes());
+        self.data
+            .extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes());
+    }
+}
+
+/// Fits a size or count into its field in the archive, or fails if the
+/// archive would need ZIP64 to store it.
+fn field<T: TryFrom<usize>>(value: usize, what: &str) -> Result<T> {
+    T::try_from(value).map_err(|_| {
+        Error::new(format!(
+            "Could not create the archive: the {} is too large for the ZIP format",
+            what
+        ))
+    })
+}
+
+/// The CRC-32 checksum used by ZIP archives
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for byte in data {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn writes_an_archive() {
+        let mut zip = ZipWriter::new();
+        zip.add_file("mimetype", b"application/epub+zip").unwrap();
+        zip.add_file("a/b.txt", b"hello").unwrap();
+        let data = zip.finish().unwrap();
+
+        // The first file's name and content are readable at fixed offsets,
+        // which EPUB readers rely on to detect the format
+        assert_eq!(&data[0..4], b"PK\x03\x04");
+        assert_eq!(&data[30..38], b"mimetype");
+        assert_eq!(&data[38..58], b"application/epub+zip");
+
+        // End of central directory record lists both files
+        let end = data.len() - 22;
+        assert_eq!(&data[end..end + 4], b"PK\x05\x06");
+        assert_eq!(u16::from_le_bytes([data[end + 10], data[end + 11]]), 2);
+    }
+
+    #[test]
+    fn fails_instead_of_overflowing() {
+        let mut zip = ZipWriter::new();
+        assert!(zip.add_file(&"x".repeat(70_000), b"").is_err());
+
+        for i in 0..=u16::MAX as usize {
+            zip.add_file(&i.to_string(), b"").unwrap();
+        }
+        let error = zip.finish().unwrap_err();
+
+        assert!(
+            format!("{}", error).contains("number of files is too large"),
+            "Error was: {}",
+            error
+        );
+    }
+}
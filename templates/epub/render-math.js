@@ -0,0 +1,9 @@
+// Renders math notation for readers that support scripted content. Other
+// readers show the notation's source instead.
+for (let element of document.getElementsByClassName("math")) {
+    try {
+        katex.render(element.textContent, element, { displayMode: true });
+    } catch (e) {
+        element.classList.add("katex-error");
+    }
+}
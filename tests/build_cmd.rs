@@ -163,6 +163,1259 @@ integration_test!(mermaid_js, |area| {
     area.assert_contains(&index, "Car]\n</div>");
 });
 
+integration_test!(link_previews, |area| {
+    area.mkdir(Path::new("docs").join("_include").join("assets"));
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Previews
+    site_url: https://example.com
+    base_path: /docs
+    image: assets/preview.png
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs")
+            .join("_include")
+            .join("assets")
+            .join("preview.png"),
+        b"",
+    );
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Previews
+
+        The first *paragraph* of the page.
+
+        The second paragraph.
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("custom.md"),
+        indoc! {"
+        ---
+        title: Custom
+        description: A custom description
+        image: https://cdn.example.com/custom.png
+        ---
+
+        Not the description.
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(
+        &index,
+        "<meta name=\"description\" content=\"The first paragraph of the page.\">",
+    );
+    area.assert_contains(
+        &index,
+        "<link rel=\"canonical\" href=\"https://example.com/docs/\">",
+    );
+    area.assert_contains(
+        &index,
+        "<meta property=\"og:image\" content=\"https://example.com/docs/assets/preview.png\">",
+    );
+
+    let custom = Path::new("site").join("custom.html");
+    area.assert_contains(
+        &custom,
+        "<meta property=\"og:description\" content=\"A custom description\">",
+    );
+    area.assert_contains(
+        &custom,
+        "<meta name=\"twitter:image\" content=\"https://cdn.example.com/custom.png\">",
+    );
+    area.assert_contains(
+        &custom,
+        "<meta property=\"og:url\" content=\"https://example.com/docs/custom\">",
+    );
+});
+
+integration_test!(feeds, |area| {
+    area.mkdir(Path::new("docs").join("changelog"));
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Feeds
+    site_url: https://example.com
+    feeds:
+      - path: docs/changelog
+        title: Release notes
+        rss: true
+    "}
+        .as_bytes(),
+    );
+    area.write_file(Path::new("docs").join("README.md"), b"# Hi");
+    area.write_file(
+        Path::new("docs").join("changelog").join("README.md"),
+        b"# Changelog",
+    );
+    area.write_file(
+        Path::new("docs").join("changelog").join("v1.md"),
+        indoc! {"
+        ---
+        title: Version 1.0
+        date: 2021-01-31
+        ---
+
+        The <em>first</em> release.
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("changelog").join("v2.md"),
+        indoc! {"
+        ---
+        title: Version 2.0
+        date: 2022-04-02
+        ---
+
+        The second release.
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("changelog").join("draft.md"),
+        b"# Not dated, so not published",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let atom = Path::new("site").join("changelog").join("feed.xml");
+    area.assert_contains(&atom, "<title>Release notes</title>");
+    area.assert_contains(&atom, "<updated>2022-04-02T00:00:00Z</updated>");
+    area.assert_contains(&atom, "<link href=\"https://example.com/changelog/v1\" />");
+    area.refute_contains(&atom, "draft");
+
+    let feed = std::fs::read_to_string(area.path.join(&atom)).unwrap();
+    assert!(feed.find("Version 2.0").unwrap() < feed.find("Version 1.0").unwrap());
+
+    let rss = Path::new("site").join("changelog").join("rss.xml");
+    area.assert_contains(&rss, "<pubDate>Sun, 31 Jan 2021 00:00:00 +0000</pubDate>");
+    area.assert_contains(
+        &rss,
+        "<lastBuildDate>Sat, 02 Apr 2022 00:00:00 +0000</lastBuildDate>",
+    );
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(
+        &index,
+        "<link rel=\"alternate\" type=\"application/atom+xml\" title=\"Release notes\" \
+         href=\"https://example.com/changelog/feed.xml\">",
+    );
+});
+
+integration_test!(feeds_without_dated_pages_have_no_updated_date, |area| {
+    area.mkdir(Path::new("docs").join("changelog"));
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Feeds
+    site_url: https://example.com
+    feeds:
+      - path: docs/changelog
+        rss: true
+    "}
+        .as_bytes(),
+    );
+    area.write_file(Path::new("docs").join("README.md"), b"# Hi");
+    area.write_file(
+        Path::new("docs").join("changelog").join("README.md"),
+        b"# Changelog",
+    );
+    area.write_file(
+        Path::new("docs").join("changelog").join("draft.md"),
+        b"# Not dated yet",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    // Rebuilding the site must not change the feeds
+    area.refute_contains(
+        Path::new("site").join("changelog").join("feed.xml"),
+        "<updated>",
+    );
+    area.refute_contains(
+        Path::new("site").join("changelog").join("rss.xml"),
+        "<lastBuildDate>",
+    );
+});
+
+integration_test!(last_updated_and_edit_links, |area| {
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Edits
+    repository:
+      edit_url: https://github.com/example/project/edit/main/{path}
+    "}
+        .as_bytes(),
+    );
+    area.mkdir(Path::new("docs").join("guides"));
+    area.write_file(Path::new("docs").join("README.md"), b"# Edits");
+    area.write_file(
+        Path::new("docs").join("guides").join("setup.md"),
+        indoc! {"
+        ---
+        updated: 2021-01-31
+        ---
+
+        # Setup
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let setup = Path::new("site").join("guides").join("setup.html");
+    area.assert_contains(
+        &setup,
+        "<time datetime='2021-01-31T00:00:00Z'>2021-01-31</time>",
+    );
+    area.assert_contains(
+        &setup,
+        "<a href='https://github.com/example/project/edit/main/docs/guides/setup.md'>Edit this page</a>",
+    );
+
+    // Generated index pages have no source file to edit
+    let index = Path::new("site").join("guides").join("index.html");
+    area.refute_contains(&index, "Edit this page");
+});
+
+integration_test!(snippets, |area| {
+    area.create_config();
+    area.mkdir(Path::new("docs").join("_snippets"));
+    area.write_file(
+        Path::new("docs").join("_snippets").join("warning.md"),
+        b"**Mind the gap**\n",
+    );
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Snippets
+
+        {{> warning }}
+
+        Use \\{{> warning }} to include it.
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(&index, "<strong>Mind the gap</strong>");
+    area.assert_contains(&index, "Use {{&gt; warning }} to include it.");
+    area.refute_exists(Path::new("site").join("_snippets").join("warning.html"));
+});
+
+integration_test!(missing_snippets_fail_the_build, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Snippets
+
+        {{> nope }}
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_failed(&result);
+
+    let stdout = std::str::from_utf8(&result.stdout).unwrap();
+    assert!(stdout.contains("Could not include snippets in README.md"));
+    assert!(stdout.contains("Could not find snippet `nope` on line 3"));
+});
+
+integration_test!(variables, |area| {
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Variables
+    variables:
+      product: Doctave
+      version: 0.4.2
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        ---
+        variables:
+          version: 0.5.0
+        ---
+
+        # {{ product }} {{ version }}
+
+        Literal \\{{ version }}
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(&index, "Doctave 0.5.0</h1>");
+    area.assert_contains(&index, "Literal {{ version }}");
+});
+
+integration_test!(undefined_variables_fail_the_build, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        ---
+        title: Home
+        ---
+
+        # Version {{ version }}
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_failed(&result);
+    assert_output(&result, "Detected undefined variables");
+    assert_output(&result, "README.md : line 5 : {{ version }}");
+
+    let result = area.cmd(&["build", "--allow-failed-checks"]);
+    assert_success(&result);
+});
+
+integration_test!(code_includes, |area| {
+    area.create_config();
+    area.mkdir("src");
+    area.write_file(
+        Path::new("src").join("lib.rs"),
+        indoc! {"
+        pub fn setup() {
+            // #region config
+            let config = Config::load();
+            // #endregion config
+        }
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Code
+
+        {{#code src/lib.rs#config title=\"lib.rs\" }}
+
+        {{#code src/lib.rs#L1-L1 }}
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(&index, "<div class=\"code-block-title\">lib.rs</div>");
+    area.assert_contains(
+        &index,
+        "<code class=\"language-rust\">let config = Config::load();\n</code>",
+    );
+    area.assert_contains(
+        &index,
+        "<code class=\"language-rust\">pub fn setup() {\n</code>",
+    );
+    area.refute_contains(&index, "#region");
+});
+
+integration_test!(missing_code_includes_fail_the_build, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Code
+
+        {{#code src/missing.rs }}
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_failed(&result);
+    assert_output(&result, "Detected source files that could not be included");
+    assert_output(
+        &result,
+        "README.md : line 3 : {{#code src/missing.rs }} : Could not read `src/missing.rs`",
+    );
+
+    let result = area.cmd(&["build", "--allow-failed-checks"]);
+    assert_success(&result);
+});
+
+integration_test!(custom_extensions_and_index_names, |area| {
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Extensions
+    extensions: [md, markdown]
+    "}
+        .as_bytes(),
+    );
+    area.mkdir(Path::new("docs").join("guides"));
+    area.mkdir(Path::new("docs").join("reference"));
+    area.write_file(Path::new("docs").join("index.md"), b"# Home");
+    area.write_file(
+        Path::new("docs").join("guides").join("setup.markdown"),
+        b"# Setup",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("_index.md"),
+        b"# Guides index",
+    );
+    area.write_file(
+        Path::new("docs").join("reference").join("notes.txt"),
+        b"# Not a page",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    area.assert_contains(Path::new("site").join("index.html"), "Home</h1>");
+    area.assert_contains(
+        Path::new("site").join("guides").join("setup.html"),
+        "Setup</h1>",
+    );
+    area.assert_contains(
+        Path::new("site").join("guides").join("index.html"),
+        "Guides index</h1>",
+    );
+    area.refute_exists(Path::new("site").join("guides").join("_index.html"));
+    area.refute_exists(Path::new("site").join("reference"));
+});
+
+integration_test!(conflicting_sources_fail_the_build, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(Path::new("docs").join("README.md"), b"# Readme");
+    area.write_file(Path::new("docs").join("index.md"), b"# Index");
+
+    let result = area.cmd(&["build"]);
+    assert_failed(&result);
+    assert_output(&result, "would be built into index.html");
+});
+
+integration_test!(ignored_files, |area| {
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Ignored
+    gitignore: true
+    ignore:
+      - node_modules/
+      - \"*.draft.md\"
+    "}
+        .as_bytes(),
+    );
+    area.write_file(Path::new(".gitignore"), b"docs/generated\n");
+    area.mkdir(Path::new("docs").join("node_modules").join("pkg"));
+    area.mkdir(Path::new("docs").join("generated"));
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+    area.write_file(Path::new("docs").join("idea.draft.md"), b"# Idea");
+    area.write_file(
+        Path::new("docs")
+            .join("node_modules")
+            .join("pkg")
+            .join("README.md"),
+        b"# Package",
+    );
+    area.write_file(Path::new("docs").join("generated").join("api.md"), b"# API");
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    area.assert_exists(Path::new("site").join("index.html"));
+    area.refute_exists(Path::new("site").join("idea.draft.html"));
+    area.refute_exists(Path::new("site").join("node_modules"));
+    area.refute_exists(Path::new("site").join("generated"));
+});
+
+integration_test!(nested_gitignore_files, |area| {
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Ignored
+    gitignore: true
+    "}
+        .as_bytes(),
+    );
+    area.mkdir(Path::new(".git").join("info"));
+    area.write_file(
+        Path::new(".git").join("info").join("exclude"),
+        b"secret.md\n",
+    );
+    area.write_file(Path::new(".gitignore"), b"scratch-*.md\n");
+    area.mkdir(Path::new("docs").join("guides"));
+    area.write_file(
+        Path::new("docs").join("guides").join(".gitignore"),
+        b"wip-*.md\n!scratch-notes.md\n",
+    );
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+    area.write_file(Path::new("docs").join("secret.md"), b"# Secret");
+    area.write_file(Path::new("docs").join("scratch-pad.md"), b"# Scratch");
+    area.write_file(Path::new("docs").join("wip-idea.md"), b"# Idea");
+    area.write_file(
+        Path::new("docs").join("guides").join("wip-setup.md"),
+        b"# Setup",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("scratch-notes.md"),
+        b"# Notes",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    area.refute_exists(Path::new("site").join("secret.html"));
+    area.refute_exists(Path::new("site").join("scratch-pad.html"));
+    area.refute_exists(Path::new("site").join("guides").join("wip-setup.html"));
+    // Rules in a nested .gitignore only apply below it, and override the ones above
+    area.assert_exists(Path::new("site").join("wip-idea.html"));
+    area.assert_exists(Path::new("site").join("guides").join("scratch-notes.html"));
+});
+
+integration_test!(hidden_pages, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Home
+
+        Check out our [campaign](/campaign).
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("campaign.md"),
+        indoc! {"
+        ---
+        title: Campaign
+        hidden: true
+        ---
+
+        # Limited time offer
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("landing.md"),
+        indoc! {"
+        ---
+        title: Landing
+        hidden: true
+        search: true
+        ---
+
+        # Welcome aboard
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    area.assert_contains(
+        Path::new("site").join("campaign.html"),
+        "Limited time offer</h1>",
+    );
+    area.refute_contains(Path::new("site").join("index.html"), ">Campaign</a>");
+    area.refute_contains(Path::new("site").join("index.html"), ">Landing</a>");
+
+    let search_index = Path::new("site").join("search_index.json");
+    area.refute_contains(&search_index, "Limited time offer");
+    area.assert_contains(&search_index, "Welcome aboard");
+});
+
+integration_test!(redirects, |area| {
+    area.write_file(
+        "doctave.yaml",
+        indoc! {"
+        ---
+        title: Redirects
+        redirects:
+          /getting-started: /tutorial
+          /old/api/: https://api.example.com
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+    area.write_file(
+        Path::new("docs").join("tutorial.md"),
+        indoc! {"
+        ---
+        title: Tutorial
+        aliases:
+          - /setup
+        ---
+
+        # Tutorial
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    area.assert_contains(
+        Path::new("site").join("getting-started.html"),
+        "url=/tutorial",
+    );
+    area.assert_contains(Path::new("site").join("setup.html"), "url=/tutorial");
+    area.assert_contains(
+        Path::new("site").join("old").join("api").join("index.html"),
+        "url=https://api.example.com",
+    );
+
+    let redirects = Path::new("site").join("_redirects");
+    area.assert_contains(&redirects, "/getting-started /tutorial 301");
+    area.assert_contains(&redirects, "/setup /tutorial 301");
+    area.assert_contains(&redirects, "/old/api/ https://api.example.com 301");
+});
+
+integration_test!(broken_redirects_fail_the_build, |area| {
+    area.write_file(
+        "doctave.yaml",
+        indoc! {"
+        ---
+        title: Redirects
+        redirects:
+          /getting-started: /nowhere
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+
+    let result = area.cmd(&["build"]);
+    assert_failed(&result);
+    assert_output(&result, "Detected broken redirects");
+    assert_output(&result, "doctave.yaml : /getting-started -> /nowhere");
+});
+
+integration_test!(default_not_found_page, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let page = Path::new("site").join("404.html");
+    area.assert_contains(&page, "Page not found</h1>");
+    area.assert_contains(&page, "id='not-found-suggestion'");
+    area.assert_contains(&page, "id='search-box'");
+});
+
+integration_test!(not_found_page_suggests_pages_left_out_of_search, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+    area.write_file(
+        Path::new("docs").join("changelog.md"),
+        b"---\ntitle: Changelog\nsearch: false\n---\n# Changelog",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let page = Path::new("site").join("404.html");
+    area.assert_contains(&page, "var SUGGESTED_PAGES = [");
+    area.assert_contains(&page, "{\"title\":\"Changelog\",\"url\":\"/changelog\"}");
+});
+
+integration_test!(not_found_page_links_from_the_base_path, |area| {
+    area.write_file(
+        "doctave.yaml",
+        indoc! {"
+        ---
+        title: Relative
+        base_path: /docs
+        relative_links: true
+    "}
+        .as_bytes(),
+    );
+    area.mkdir(Path::new("docs").join("guides"));
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+    area.write_file(
+        Path::new("docs").join("guides").join("README.md"),
+        b"# Guides",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("setup.md"),
+        b"# Setup",
+    );
+    area.write_file(
+        Path::new("docs").join("_404.md"),
+        b"# Lost\n\nRead the [setup guide](/guides/setup).",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    // Served for missing URLs such as /docs/a/b/missing, so nothing can be relative
+    let page = Path::new("site").join("404.html");
+    area.assert_contains(&page, "href=\"/docs/guides/setup.html\"");
+    area.assert_contains(&page, "href=\"/docs/assets/doctave-style.css?v=");
+    area.assert_contains(&page, "src=\"/docs/search_index.js?v=");
+    area.assert_contains(&page, "var BASE_PATH = \"/docs/\";");
+    area.assert_contains(&page, "\"url\":\"/docs/guides/setup.html\"");
+});
+
+integration_test!(custom_not_found_page, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+    area.write_file(
+        Path::new("docs").join("_404.md"),
+        indoc! {"
+        ---
+        title: Lost
+        ---
+
+        # Nothing to see here
+
+        Head back [home](/).
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let page = Path::new("site").join("404.html");
+    area.assert_contains(&page, "Nothing to see here</h1>");
+    area.assert_contains(&page, "<title>Lost</title>");
+    area.refute_exists(Path::new("site").join("_404.html"));
+    area.refute_contains(Path::new("site").join("index.html"), ">Lost</a>");
+});
+
+integration_test!(slugs_and_permalinks, |area| {
+    area.create_config();
+    area.mkdir(Path::new("docs").join("guides"));
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Home
+
+        Start with the [setup](/setup) and the [first steps](/guides/first-steps).
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("README.md"),
+        b"# Guides",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("01_first_steps.md"),
+        indoc! {"
+        ---
+        title: First steps
+        slug: first-steps
+        ---
+
+        # First steps
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("installing.md"),
+        indoc! {"
+        ---
+        title: Setup
+        permalink: /setup
+        ---
+
+        # Setup
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    area.assert_exists(Path::new("site").join("guides").join("first-steps.html"));
+    area.assert_exists(Path::new("site").join("setup.html"));
+    area.refute_exists(Path::new("site").join("guides").join("installing.html"));
+
+    area.assert_contains(Path::new("site").join("index.html"), "href=\"/setup\"");
+    area.assert_contains(
+        Path::new("site").join("search_index.json"),
+        "\"/guides/first-steps\"",
+    );
+});
+
+integration_test!(conflicting_permalinks_fail_the_build, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+    area.write_file(Path::new("docs").join("setup.md"), b"# Setup");
+    area.write_file(
+        Path::new("docs").join("install.md"),
+        indoc! {"
+        ---
+        permalink: /setup
+        ---
+
+        # Install
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_failed(&result);
+    assert_output(&result, "would be built into setup.html");
+});
+
+integration_test!(pretty_urls, |area| {
+    area.write_file(
+        "doctave.yaml",
+        indoc! {"
+        ---
+        title: Pretty
+        pretty_urls: true
+        redirects:
+          /old: /guides/setup
+    "}
+        .as_bytes(),
+    );
+    area.mkdir(Path::new("docs").join("guides"));
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        b"# Home\n\nRead the [setup guide](/guides/setup).",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("README.md"),
+        b"# Guides",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("setup.md"),
+        b"# Setup",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    area.assert_exists(Path::new("site").join("index.html"));
+    area.assert_exists(Path::new("site").join("guides").join("index.html"));
+    area.assert_exists(
+        Path::new("site")
+            .join("guides")
+            .join("setup")
+            .join("index.html"),
+    );
+    area.refute_exists(Path::new("site").join("guides").join("setup.html"));
+    area.assert_exists(Path::new("site").join("old").join("index.html"));
+
+    area.assert_contains(
+        Path::new("site").join("index.html"),
+        "href=\"/guides/setup/\"",
+    );
+});
+
+integration_test!(relative_links, |area| {
+    area.write_file(
+        "doctave.yaml",
+        indoc! {"
+        ---
+        title: Relative
+        base_path: /docs
+        relative_links: true
+    "}
+        .as_bytes(),
+    );
+    area.mkdir(Path::new("docs").join("guides"));
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        b"# Home\n\nRead the [setup guide](/guides/setup#install).",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("README.md"),
+        b"# Guides",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("setup.md"),
+        b"# Setup\n\nBack [home](/).",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(&index, "href=\"guides/setup.html#install\"");
+    area.assert_contains(&index, "href=\"assets/doctave-style.css?v=");
+    area.assert_contains(&index, "var BASE_PATH = \"\";");
+
+    let setup = Path::new("site").join("guides").join("setup.html");
+    area.assert_contains(&setup, "href=\"../index.html\"");
+    area.assert_contains(&setup, "href=\"../assets/doctave-style.css?v=");
+    area.assert_contains(&setup, "src=\"../search_index.js?v=");
+    area.assert_contains(&setup, "var BASE_PATH = \"../\";");
+    area.refute_contains(&setup, "href=\"/docs/");
+
+    let search_index = Path::new("site").join("search_index.js");
+    area.assert_contains(&search_index, "var SEARCH_INDEX = ");
+    area.assert_contains(&search_index, "\"guides/setup.html\"");
+});
+
+integration_test!(single_page, |area| {
+    area.create_config();
+    area.mkdir(Path::new("docs").join("guides"));
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        b"# Home\n\nStart with the [setup](/guides/setup).",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("README.md"),
+        b"# Guides",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("setup.md"),
+        b"# Setup\n\nSome setup instructions.",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+    area.refute_exists(Path::new("site").join("print.html"));
+
+    let result = area.cmd(&["build", "--single-page"]);
+    assert_success(&result);
+
+    let print = Path::new("site").join("print.html");
+    area.assert_contains(&print, "<section class='print-page' id='index'>");
+    area.assert_contains(&print, "<section class='print-page' id='guides'>");
+    area.assert_contains(&print, "<section class='print-page' id='guides-setup'>");
+    area.assert_contains(&print, "Some setup instructions.");
+    area.assert_contains(&print, "<a href=\"#guides-setup\">setup</a>");
+    area.assert_contains(&print, "<a href='#guides-setup'>setup</a>");
+});
+
+integration_test!(table_of_contents_depth, |area| {
+    area.write_file(
+        "doctave.yaml",
+        indoc! {"
+        ---
+        title: Contents
+        toc_depth: 2
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        b"# Home\n\n## Install\n\n### From source",
+    );
+    area.write_file(
+        Path::new("docs").join("reference.md"),
+        indoc! {"
+        ---
+        toc_depth: 4
+        ---
+
+        # Reference
+
+        ## Commands
+
+        #### Flags
+
+        ##### Details
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("plain.md"),
+        indoc! {"
+        ---
+        toc: false
+        ---
+
+        # Plain
+
+        ## Section
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(&index, "<a href='#install'>Install</a>");
+    area.refute_contains(&index, "<a href='#from-source'>From source</a>");
+
+    let reference = Path::new("site").join("reference.html");
+    area.assert_contains(&reference, "<a href='#flags'>Flags</a>");
+    area.refute_contains(&reference, "<a href='#details'>Details</a>");
+
+    let plain = Path::new("site").join("plain.html");
+    area.refute_contains(&plain, "On this page");
+    area.refute_contains(&plain, "<a href='#section'>Section</a>");
+});
+
+integration_test!(inline_table_of_contents, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Home
+
+        [[toc]]
+
+        ## Install
+
+        ### From source
+
+        #### Linux
+
+        ## Usage
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(
+        &index,
+        "<div class=\"toc\"><ul>\
+         <li><a href=\"#install\">Install</a><ul>\
+         <li><a href=\"#from-source\">From source</a></li>\
+         </ul></li>\
+         <li><a href=\"#usage\">Usage</a></li>\
+         </ul></div>",
+    );
+    area.refute_contains(&index, "[[toc]]");
+});
+
+integration_test!(invalid_toc_depth_fails_the_build, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        b"---\ntoc_depth: deep\n---\n\n# Home",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_failed(&result);
+    assert_output(&result, "Invalid toc_depth `deep` in README.md");
+});
+
+integration_test!(callouts, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Home
+
+        > [!NOTE]
+        > Remember to *restart*.
+
+        :::tip Faster builds
+        Use the release flag.
+        :::
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(
+        &index,
+        "<div class=\"callout info callout-note\"><p class=\"callout-title\">\
+         <span class=\"callout-icon\" aria-hidden=\"true\">ℹ️</span>Note</p>\n\
+         <p>Remember to <em>restart</em>.</p>",
+    );
+    area.assert_contains(
+        &index,
+        "<div class=\"callout success callout-tip\"><p class=\"callout-title\">\
+         <span class=\"callout-icon\" aria-hidden=\"true\">💡</span>Faster builds</p>",
+    );
+    area.refute_contains(&index, "[!NOTE]");
+    area.refute_contains(&index, ":::");
+
+    let search_index = Path::new("site").join("search_index.json");
+    area.assert_contains(&search_index, "Remember to *restart*.");
+    area.assert_contains(&search_index, "Faster builds");
+    area.refute_contains(&search_index, "[!NOTE]");
+    area.refute_contains(&search_index, ":::tip");
+});
+
+integration_test!(custom_callout_types, |area| {
+    area.write_file(
+        "doctave.yaml",
+        indoc! {"
+        ---
+        title: Callouts
+        callouts:
+          deprecated:
+            title: Deprecated
+            style: error
+            icon: X
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        b"# Home\n\n> [!DEPRECATED]\n> Use the new API.",
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    area.assert_contains(
+        Path::new("site").join("index.html"),
+        "<div class=\"callout error callout-deprecated\"><p class=\"callout-title\">\
+         <span class=\"callout-icon\" aria-hidden=\"true\">X</span>Deprecated</p>",
+    );
+});
+
+integration_test!(tabs, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        b"# Home\n\nSee [building on Linux](/install#building-on-linux).",
+    );
+    area.write_file(
+        Path::new("docs").join("install.md"),
+        indoc! {"
+        # Install
+
+        :::tabs
+        == macOS
+        Use *brew*.
+        == Linux
+        ## Building on Linux
+
+        Use cargo.
+        :::
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build", "--single-page"]);
+    assert_success(&result);
+
+    let install = Path::new("site").join("install.html");
+    area.assert_contains(
+        &install,
+        "<button type=\"button\" class=\"tab\" role=\"tab\" id=\"tabs-1-1\" \
+         aria-controls=\"tabs-1-1-panel\" aria-selected=\"false\" tabindex=\"-1\" \
+         data-tab=\"linux\">Linux</button>",
+    );
+    area.assert_contains(
+        &install,
+        "<div class=\"tab-panel\" role=\"tabpanel\" id=\"tabs-1-0-panel\" \
+         aria-labelledby=\"tabs-1-0\" data-tab=\"macos\">",
+    );
+    area.assert_contains(&install, "<p>Use <em>brew</em>.</p>");
+    area.assert_contains(&install, "id=\"building-on-linux\"");
+    area.refute_contains(&install, ":::");
+
+    let print = Path::new("site").join("print.html");
+    area.assert_contains(
+        &print,
+        "id=\"install--tabs-1-1-panel\" aria-labelledby=\"install--tabs-1-1\"",
+    );
+    area.assert_contains(&print, "<a href=\"#install--building-on-linux\">");
+
+    let search_index = Path::new("site").join("search_index.json");
+    area.assert_contains(&search_index, "macOS");
+    area.refute_contains(&search_index, ":::tabs");
+    area.refute_contains(&search_index, "== Linux");
+});
+
+integration_test!(code_block_options, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Home
+
+        ```rust title=\"src/main.rs\" {2} showLineNumbers
+        fn main() {
+            println!(\"Hello\");
+        }
+        ```
+
+        ```
+        plain
+        ```
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(
+        &index,
+        "<div class=\"code-block\"><div class=\"code-block-title\">src/main.rs</div>\
+         <pre class=\"with-line-numbers\"><span class=\"code-lines\" aria-hidden=\"true\">\
+         <span class=\"code-line\" data-line=\"1\"></span>\
+         <span class=\"code-line highlighted\" data-line=\"2\"></span>\
+         <span class=\"code-line\" data-line=\"3\"></span></span>\
+         <code class=\"language-rust\">",
+    );
+    area.assert_contains(
+        &index,
+        "<div class=\"code-block\"><pre><code>plain\n</code></pre>\
+         <button type=\"button\" class=\"copy-code\" aria-label=\"Copy code\">Copy</button></div>",
+    );
+    area.refute_contains(&index, "showLineNumbers");
+    area.refute_contains(&index, "code-block-options");
+    area.assert_contains(
+        Path::new("site")
+            .join("assets")
+            .join("prism-grammars")
+            .join("prism-rust.min.js"),
+        "Prism",
+    );
+});
+
 integration_test!(search_index, |area| {
     area.mkdir("docs");
     area.create_config();
@@ -634,6 +1887,10 @@ integration_test!(includes_katex_bundles, |area| {
         Path::new("docs").join("README.md"),
         indoc! {"
         # New phone, who dis?
+
+        ```math
+        a^2 + b^2 = c^2
+        ```
     "}
         .as_bytes(),
     );
@@ -644,6 +1901,63 @@ integration_test!(includes_katex_bundles, |area| {
     area.assert_exists(area.path.join("site").join("assets").join("katex-fonts"));
     area.assert_exists(area.path.join("site").join("assets").join("katex.js"));
     area.assert_exists(area.path.join("site").join("assets").join("katex.css"));
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(&index, "assets/katex.js");
+});
+
+integration_test!(skips_katex_bundles_without_math, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # New phone, who dis?
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    area.refute_exists(area.path.join("site").join("assets").join("katex-fonts"));
+    area.refute_exists(area.path.join("site").join("assets").join("katex.js"));
+    area.refute_exists(area.path.join("site").join("assets").join("mermaid.js"));
+
+    let index = Path::new("site").join("index.html");
+    area.refute_contains(&index, "assets/katex.js");
+    area.refute_contains(&index, "assets/mermaid.js");
+});
+
+integration_test!(includes_katex_bundles_for_not_found_page, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # New phone, who dis?
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("_404.md"),
+        indoc! {"
+        # Lost
+
+        ```math
+        404 = 4 \\cdot 101
+        ```
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    area.assert_exists(area.path.join("site").join("assets").join("katex.js"));
+
+    let not_found = Path::new("site").join("404.html");
+    area.assert_contains(&not_found, "assets/katex.js");
 });
 
 integration_test!(includes_prism_grammars, |area| {
@@ -653,6 +1967,17 @@ integration_test!(includes_prism_grammars, |area| {
         Path::new("docs").join("README.md"),
         indoc! {"
         # New phone, who dis?
+
+        ```cpp
+        int main() {}
+        ```
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("plain.md"),
+        indoc! {"
+        # No code here
     "}
         .as_bytes(),
     );
@@ -660,6 +1985,288 @@ integration_test!(includes_prism_grammars, |area| {
     let result = area.cmd(&["build"]);
     assert_success(&result);
 
-    area.assert_exists(area.path.join("site").join("assets").join("prism-grammars"));
+    let grammars = area.path.join("site").join("assets").join("prism-grammars");
+
     area.assert_exists(area.path.join("site").join("assets").join("prism.js"));
+    area.assert_exists(grammars.join("prism-cpp.min.js"));
+    // Dependencies of the C++ grammar
+    area.assert_exists(grammars.join("prism-c.min.js"));
+    area.refute_exists(grammars.join("prism-ruby.min.js"));
+
+    area.assert_contains(Path::new("site").join("index.html"), "assets/prism.js");
+    area.refute_contains(Path::new("site").join("plain.html"), "assets/prism.js");
+});
+
+integration_test!(server_side_highlighting, |area| {
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Highlighting
+    syntax_highlighting: server
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Highlighting
+
+        ```rust
+        fn main() {} // entry point
+        ```
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build", "--single-page"]);
+    assert_success(&result);
+
+    let assets = area.path.join("site").join("assets");
+    area.refute_exists(assets.join("prism.js"));
+    area.refute_exists(assets.join("prism-grammars"));
+    area.assert_exists(assets.join("prism-ghcolors.css"));
+
+    for page in &["index.html", "print.html"] {
+        let page = Path::new("site").join(page);
+
+        area.assert_contains(&page, "<pre class=\"language-rust\">");
+        area.assert_contains(&page, "<span class=\"token keyword\">fn</span>");
+        area.assert_contains(&page, "<span class=\"token comment\">// entry point</span>");
+        area.refute_contains(&page, "assets/prism.js");
+    }
+});
+
+integration_test!(server_side_highlighting_falls_back_to_prism, |area| {
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Highlighting
+    syntax_highlighting: server
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Highlighting
+
+        ```rust
+        fn main() {}
+        ```
+
+        ```haskell
+        main = putStrLn \"hi\"
+        ```
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let assets = area.path.join("site").join("assets");
+    area.assert_exists(assets.join("prism.js"));
+    area.assert_exists(assets.join("prism-grammars").join("prism-haskell.min.js"));
+    area.refute_exists(assets.join("prism-grammars").join("prism-rust.min.js"));
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(&index, "<pre class=\"language-rust\">");
+    area.assert_contains(&index, "<pre><code class=\"language-haskell\">");
+    area.assert_contains(&index, "assets/prism.js");
+});
+
+#[cfg(not(windows))]
+integration_test!(server_side_renderers, |area| {
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Renderers
+    renderers:
+      math: tr a-z A-Z
+      mermaid: echo '<svg>diagram</svg>'
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Renderers
+
+        ```math
+        x < y
+        ```
+
+        ```mermaid
+        graph TD;
+        ```
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(&index, "<div class=\"math-rendered\">X < Y</div>");
+    area.assert_contains(
+        &index,
+        "<div class=\"mermaid-rendered\"><svg>diagram</svg></div>",
+    );
+    area.refute_contains(&index, "assets/katex.js");
+    area.refute_contains(&index, "assets/mermaid.js");
+    area.assert_contains(&index, "assets/katex.css");
+
+    let assets = area.path.join("site").join("assets");
+    area.refute_exists(assets.join("katex.js"));
+    area.refute_exists(assets.join("mermaid.js"));
+    area.assert_exists(assets.join("katex.css"));
+});
+
+#[cfg(not(windows))]
+integration_test!(failing_renderers_fail_the_build, |area| {
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Renderers
+    renderers:
+      math: echo 'Undefined control sequence' >&2; exit 1
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Renderers
+
+        ```math
+        \\nope
+        ```
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_failed(&result);
+    assert_output(&result, "failed to render");
+    assert_output(&result, "Undefined control sequence");
+});
+
+integration_test!(glossary_from_yaml, |area| {
+    area.write_file(
+        Path::new("doctave.yaml"),
+        indoc! {"
+    ---
+    title: Glossary
+    glossary:
+      link_terms: true
+    "}
+        .as_bytes(),
+    );
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("glossary.yaml"),
+        indoc! {"
+        Workspace: A directory that holds the projects of one team.
+        Runner: The machine a job is run on.
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Jobs
+
+        Jobs run in a workspace, on a [[runner]]. Every workspace has a runner.
+
+        `workspace`
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let glossary = Path::new("site").join("glossary.html");
+    area.assert_contains(&glossary, "<h2 id=\"runner\">Runner</h2>");
+    area.assert_contains(&glossary, "<h2 id=\"workspace\">Workspace</h2>");
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(
+        &index,
+        "Jobs run in a <a class=\"glossary-term\" href=\"/glossary#workspace\" \
+         data-definition=\"A directory that holds the projects of one team.\">workspace</a>, \
+         on a <a class=\"glossary-term\" href=\"/glossary#runner\" \
+         data-definition=\"The machine a job is run on.\">runner</a>. \
+         Every workspace has a runner.",
+    );
+    area.assert_contains(&index, "<code>workspace</code>");
+});
+
+integration_test!(glossary_page, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("glossary.md"),
+        indoc! {"
+        # Glossary
+
+        ## Runner
+
+        The machine a job is run on.
+    "}
+        .as_bytes(),
+    );
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Jobs
+
+        Jobs run on a runner, set with [[Runners]].
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_success(&result);
+
+    let index = Path::new("site").join("index.html");
+    area.assert_contains(
+        &index,
+        "Jobs run on a runner, set with <a class=\"glossary-term\" href=\"/glossary#runner\" \
+         data-definition=\"The machine a job is run on.\">Runners</a>.",
+    );
+});
+
+integration_test!(undefined_glossary_terms_fail_the_build, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(
+        Path::new("docs").join("glossary.yaml"),
+        b"Runner: The machine a job is run on.\n",
+    );
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        indoc! {"
+        # Jobs
+
+        Jobs run in a [[workspace]].
+    "}
+        .as_bytes(),
+    );
+
+    let result = area.cmd(&["build"]);
+    assert_failed(&result);
+    assert_output(&result, "Detected undefined glossary terms");
+    assert_output(&result, "README.md : [[workspace]]");
+
+    let result = area.cmd(&["build", "--allow-failed-checks"]);
+    assert_success(&result);
 });
@@ -0,0 +1,90 @@
+#[allow(dead_code)]
+mod support;
+
+use std::path::Path;
+use support::*;
+
+integration_test!(export_epub, |area| {
+    area.create_config();
+    area.mkdir(Path::new("docs").join("guides"));
+    area.mkdir(Path::new("docs").join("_include"));
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        b"# Home\n\nStart with the [setup](/guides/setup).\n\n![Logo](/logo.png)",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("README.md"),
+        b"# Guides",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("setup.md"),
+        b"# Setup\n\nSome setup instructions.",
+    );
+    area.write_file(
+        Path::new("docs").join("_include").join("logo.png"),
+        b"not really a png",
+    );
+
+    let result = area.cmd(&["export", "--format", "epub"]);
+    assert_success(&result);
+
+    let epub = std::fs::read(area.path.join("docs.epub")).unwrap();
+    assert!(epub.starts_with(b"PK\x03\x04"));
+
+    let content = String::from_utf8_lossy(&epub);
+    assert!(content.contains("mimetypeapplication/epub+zip"));
+    assert!(content.contains("OEBPS/pages/guides-setup.xhtml"));
+    assert!(content.contains("Some setup instructions."));
+    assert!(content.contains("<a href=\"guides-setup.xhtml#guides-setup\">setup</a>"));
+    assert!(content.contains("src=\"../images/logo.png\""));
+    assert!(content.contains("OEBPS/images/logo.pngnot really a png"));
+    assert!(content.contains("<a href=\"pages/guides-setup.xhtml\">setup</a>"));
+});
+
+integration_test!(export_to_a_custom_path, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+
+    let result = area.cmd(&["export", "--format", "epub", "--output", "book.epub"]);
+    assert_success(&result);
+
+    area.assert_exists("book.epub");
+    area.refute_exists("docs.epub");
+});
+
+integration_test!(export_requires_a_known_format, |area| {
+    area.create_config();
+    area.mkdir("docs");
+    area.write_file(Path::new("docs").join("README.md"), b"# Home");
+
+    let result = area.cmd(&["export", "--format", "pdf"]);
+    assert_failed(&result);
+});
+
+integration_test!(export_json, |area| {
+    area.create_config();
+    area.mkdir(Path::new("docs").join("guides"));
+    area.write_file(
+        Path::new("docs").join("README.md"),
+        b"# Home\n\nStart with the [setup](/guides/setup).",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("README.md"),
+        b"# Guides",
+    );
+    area.write_file(
+        Path::new("docs").join("guides").join("setup.md"),
+        b"---\ntitle: Setup\n---\n\n# Setup\n\n## Installing",
+    );
+
+    let result = area.cmd(&["export", "--format", "json"]);
+    assert_success(&result);
+
+    let json = Path::new("docs.json");
+    area.assert_contains(&json, "\"schema_version\": 1");
+    area.assert_contains(&json, "\"path\": \"guides/setup.md\"");
+    area.assert_contains(&json, "\"uri_path\": \"/guides/setup\"");
+    area.assert_contains(&json, "# Setup\\n\\n## Installing\"");
+    area.assert_contains(&json, "\"navigation\": [");
+});
@@ -0,0 +1,10 @@
+use std::io;
+
+fn main() {
+    // #region setup
+    let a = 1;
+    // #region inner
+    let b = 2;
+    // #endregion inner
+    // #endregion
+}